@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use alloy::primitives::{Address, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    campaign::campaign,
+    config::{ApprovalConfig, ApprovalFallback},
+    errors::TransferVetoed,
+};
+
+#[derive(Serialize)]
+struct ApprovalRequest {
+    wallet: Address,
+    recipient: Address,
+    token: Address,
+    amount: U256,
+    payout: &'static str,
+}
+
+#[derive(Deserialize)]
+struct ApprovalResponse {
+    approved: bool,
+    #[serde(default)]
+    reason: Option<String>,
+}
+
+async fn request_approval(
+    approval_config: &ApprovalConfig,
+    request: &ApprovalRequest,
+) -> eyre::Result<ApprovalResponse> {
+    let response = reqwest::Client::new()
+        .post(&approval_config.url)
+        .timeout(Duration::from_secs(approval_config.timeout))
+        .json(request)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    Ok(response)
+}
+
+// A veto is final for the wallet, an unreachable service is either retried later or approved
+pub async fn ensure_approved(
+    approval_config: &ApprovalConfig,
+    wallet: Address,
+    recipient: Address,
+    amount: U256,
+    payout: &'static str,
+) -> eyre::Result<()> {
+    let request = ApprovalRequest {
+        wallet,
+        recipient,
+        token: campaign().token,
+        amount,
+        payout,
+    };
+
+    let reason = match request_approval(approval_config, &request).await {
+        Ok(response) if response.approved => return Ok(()),
+        Ok(response) => response
+            .reason
+            .unwrap_or_else(|| String::from("no reason given")),
+        Err(e) => match approval_config.fallback {
+            ApprovalFallback::Approve => {
+                tracing::warn!("Approval service failed for {wallet}, approving by fallback: {e}");
+                return Ok(());
+            }
+            ApprovalFallback::Deny => eyre::bail!("Approval service failed for {wallet}: {e}"),
+        },
+    };
+
+    Err(TransferVetoed { reason }.into())
+}
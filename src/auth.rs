@@ -0,0 +1,69 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex},
+};
+
+use alloy::{
+    hex,
+    primitives::Address,
+    signers::{local::PrivateKeySigner, SignerSync},
+};
+use rand::{distributions::Alphanumeric, Rng};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+use crate::{config::ProofAuthConfig, journal::unix_timestamp};
+
+const NONCE_LENGTH: usize = 16;
+
+static SIGNERS: LazyLock<Mutex<HashMap<Address, PrivateKeySigner>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn register_signer(signer: &PrivateKeySigner) {
+    SIGNERS
+        .lock()
+        .unwrap()
+        .insert(signer.address(), signer.clone());
+}
+
+fn render_message(template: &str, address: Address) -> String {
+    let nonce: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(NONCE_LENGTH)
+        .map(char::from)
+        .collect();
+
+    template
+        .replace("{address}", &address.to_checksum(None))
+        .replace("{timestamp}", &unix_timestamp().to_string())
+        .replace("{nonce}", &nonce)
+}
+
+pub fn sign_request(
+    address: Address,
+    auth: &ProofAuthConfig,
+    headers: &mut HeaderMap,
+) -> eyre::Result<()> {
+    let signer = SIGNERS
+        .lock()
+        .unwrap()
+        .get(&address)
+        .cloned()
+        .ok_or_else(|| eyre::eyre!("No signer loaded for {address}"))?;
+
+    let message = render_message(&auth.message, address);
+    let signature = signer.sign_message_sync(message.as_bytes())?;
+
+    headers.insert(
+        HeaderName::try_from(auth.signature_header.as_str())?,
+        HeaderValue::from_str(&hex::encode_prefixed(signature.as_bytes()))?,
+    );
+
+    if let Some(message_header) = &auth.message_header {
+        headers.insert(
+            HeaderName::try_from(message_header.as_str())?,
+            HeaderValue::from_str(&hex::encode_prefixed(message.as_bytes()))?,
+        );
+    }
+
+    Ok(())
+}
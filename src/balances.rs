@@ -0,0 +1,82 @@
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{utils::format_ether, U256},
+};
+use rand::thread_rng;
+
+use crate::{
+    campaign::campaign,
+    claimer::IERC20,
+    config::Config,
+    constants::MULTICALL3_ADDRESS,
+    multicall::{self, Multicall3},
+    pool::init_provider_pool,
+    token::format_token,
+    utils::read_private_keys,
+};
+
+const BALANCES_BATCH_SIZE: usize = 100;
+
+pub async fn balances(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let wallets = read_private_keys().await?;
+
+    let mut total_eth = U256::ZERO;
+    let mut total_scr = U256::ZERO;
+
+    for batch in wallets.chunks(BALANCES_BATCH_SIZE) {
+        let provider = providers.choose(&mut thread_rng());
+        let addresses: Vec<_> = batch
+            .iter()
+            .map(|wallet| {
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet)
+            })
+            .collect();
+
+        let calls = addresses
+            .iter()
+            .flat_map(|address| {
+                [
+                    multicall::call(
+                        MULTICALL3_ADDRESS,
+                        &Multicall3::getEthBalanceCall { addr: *address },
+                    ),
+                    multicall::call(
+                        campaign().token,
+                        &IERC20::balanceOfCall { account: *address },
+                    ),
+                ]
+            })
+            .collect();
+        let results = multicall::aggregate(&provider, calls).await?;
+
+        for (address, results) in addresses.iter().zip(results.chunks(2)) {
+            let eth = multicall::decode::<Multicall3::getEthBalanceCall>(&results[0])
+                .map(|balance| balance.balance);
+            let scr =
+                multicall::decode::<IERC20::balanceOfCall>(&results[1]).map(|balance| balance._0);
+
+            match (eth, scr) {
+                (Some(eth), Some(scr)) => {
+                    total_eth += eth;
+                    total_scr += scr;
+                    tracing::info!(
+                        "{address} | {} ETH | {}",
+                        format_ether(eth),
+                        format_token(scr)
+                    );
+                }
+                _ => tracing::error!("{address} | failed to read balances"),
+            }
+        }
+    }
+
+    tracing::info!(
+        "{} wallets hold {} ETH and {} in total",
+        wallets.len(),
+        format_ether(total_eth),
+        format_token(total_scr)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,158 @@
+use std::time::{Duration, Instant};
+
+use alloy::providers::{Provider, ProviderBuilder};
+use reqwest::{Client, Proxy, StatusCode};
+
+use crate::{
+    campaign::campaign,
+    config::{write_infra_ordering, Config},
+};
+
+const BENCH_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_HEAD_LAG: u64 = 2;
+
+struct RpcReport {
+    url: String,
+    latency: Option<Duration>,
+    block_number: Option<u64>,
+    error: Option<String>,
+}
+
+struct ProxyReport {
+    proxy: String,
+    latency: Option<Duration>,
+    status: String,
+}
+
+async fn bench_rpc(url: &str) -> RpcReport {
+    let result = async {
+        let provider = ProviderBuilder::new().on_builtin(url).await?;
+        let started = Instant::now();
+        let block_number =
+            tokio::time::timeout(BENCH_REQUEST_TIMEOUT, provider.get_block_number()).await??;
+        eyre::Ok((started.elapsed(), block_number))
+    }
+    .await;
+
+    match result {
+        Ok((latency, block_number)) => RpcReport {
+            url: url.to_string(),
+            latency: Some(latency),
+            block_number: Some(block_number),
+            error: None,
+        },
+        Err(e) => RpcReport {
+            url: url.to_string(),
+            latency: None,
+            block_number: None,
+            error: Some(e.to_string()),
+        },
+    }
+}
+
+async fn bench_proxy(proxy: &str) -> ProxyReport {
+    let result = async {
+        let client = Client::builder()
+            .proxy(Proxy::all(proxy)?)
+            .timeout(BENCH_REQUEST_TIMEOUT)
+            .build()?;
+        let started = Instant::now();
+        let response = client.get(&campaign().proof_url).send().await?;
+        eyre::Ok((started.elapsed(), response.status()))
+    }
+    .await;
+
+    let (latency, status) = match result {
+        Ok((latency, status)) if status.is_success() => (Some(latency), String::from("ok")),
+        Ok((_, StatusCode::FORBIDDEN | StatusCode::TOO_MANY_REQUESTS)) => {
+            (None, String::from("banned"))
+        }
+        Ok((_, status)) => (None, format!("unexpected status {status}")),
+        Err(e) => (None, format!("error: {e}")),
+    };
+
+    ProxyReport {
+        proxy: proxy.to_string(),
+        latency,
+        status,
+    }
+}
+
+pub async fn bench(config: Config, rewrite_config: bool) -> eyre::Result<()> {
+    let mut rpc_reports = vec![];
+    for url in &config.rpc_urls {
+        rpc_reports.push(bench_rpc(url).await);
+    }
+
+    let head = rpc_reports
+        .iter()
+        .filter_map(|report| report.block_number)
+        .max()
+        .unwrap_or_default();
+
+    let is_at_head = |report: &RpcReport| {
+        report
+            .block_number
+            .is_some_and(|n| n + MAX_HEAD_LAG >= head)
+    };
+
+    rpc_reports
+        .sort_by_key(|report| (!is_at_head(report), report.latency.unwrap_or(Duration::MAX)));
+
+    tracing::info!("RPC ranking (head block {head}):");
+    for (rank, report) in rpc_reports.iter().enumerate() {
+        match (&report.error, report.latency, report.block_number) {
+            (None, Some(latency), Some(block_number)) => tracing::info!(
+                "{}. {} | {}ms | block {block_number} | {}",
+                rank + 1,
+                report.url,
+                latency.as_millis(),
+                if is_at_head(report) {
+                    "at head"
+                } else {
+                    "lagging"
+                }
+            ),
+            (error, ..) => tracing::info!(
+                "{}. {} | failed: {}",
+                rank + 1,
+                report.url,
+                error.as_deref().unwrap_or_default()
+            ),
+        }
+    }
+
+    let mut proxy_reports = vec![];
+    for proxy in &config.proxies {
+        proxy_reports.push(bench_proxy(proxy).await);
+    }
+
+    proxy_reports.sort_by_key(|report| report.latency.unwrap_or(Duration::MAX));
+
+    tracing::info!("Proxy ranking against {}:", campaign().proof_url);
+    for (rank, report) in proxy_reports.iter().enumerate() {
+        match report.latency {
+            Some(latency) => tracing::info!(
+                "{}. {} | {}ms | {}",
+                rank + 1,
+                report.proxy,
+                latency.as_millis(),
+                report.status
+            ),
+            None => tracing::info!("{}. {} | {}", rank + 1, report.proxy, report.status),
+        }
+    }
+
+    if rewrite_config {
+        let rpc_urls: Vec<_> = rpc_reports.into_iter().map(|report| report.url).collect();
+        let proxies: Vec<_> = proxy_reports
+            .into_iter()
+            .map(|report| report.proxy)
+            .collect();
+
+        write_infra_ordering(&rpc_urls, &proxies).await?;
+        tracing::info!("Config rewritten in ranked order");
+    }
+
+    Ok(())
+}
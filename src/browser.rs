@@ -0,0 +1,96 @@
+use std::time::Duration;
+
+use alloy::primitives::Address;
+use chromiumoxide::{Browser, BrowserConfig};
+use futures::StreamExt;
+use reqwest::{header::HeaderMap, Url};
+
+use crate::proof::ProofRequest;
+
+const BROWSER_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+fn proxy_server(proxy_url: &str) -> eyre::Result<String> {
+    let url = Url::parse(proxy_url)?;
+
+    if !url.username().is_empty() {
+        eyre::bail!("Chromium does not accept proxy credentials on the command line: {proxy_url}");
+    }
+
+    let host = url
+        .host_str()
+        .ok_or_else(|| eyre::eyre!("Proxy url without a host: {proxy_url}"))?;
+    let port = url
+        .port_or_known_default()
+        .ok_or_else(|| eyre::eyre!("Proxy url without a port: {proxy_url}"))?;
+
+    Ok(format!("{}://{host}:{port}", url.scheme()))
+}
+
+fn fetch_script(headers: &HeaderMap, proof_request: &ProofRequest) -> eyre::Result<String> {
+    let headers: serde_json::Map<_, _> = headers
+        .iter()
+        .filter_map(|(name, value)| {
+            Some((
+                name.to_string(),
+                serde_json::Value::from(value.to_str().ok()?),
+            ))
+        })
+        .collect();
+    let headers = serde_json::to_string(&headers)?;
+    let url = serde_json::to_string(
+        Url::parse_with_params(&proof_request.url, &proof_request.query)?.as_str(),
+    )?;
+    let method = serde_json::to_string(proof_request.method.as_str())?;
+    let body = match &proof_request.body {
+        Some(body) => serde_json::to_string(&serde_json::to_string(body)?)?,
+        None => String::from("undefined"),
+    };
+
+    Ok(format!(
+        "(async () => {{ const response = await fetch({url}, {{ method: {method}, headers: {headers}, body: {body} }}); return await response.text(); }})()"
+    ))
+}
+
+pub async fn get_proof(
+    address: Address,
+    proxy_url: &str,
+    headers: &HeaderMap,
+    proof_request: &ProofRequest,
+) -> eyre::Result<String> {
+    tracing::info!("Getting proof and allocation for {address} through the browser");
+
+    let browser_config = BrowserConfig::builder()
+        .arg(("proxy-server", proxy_server(proxy_url)?.as_str()))
+        .request_timeout(BROWSER_REQUEST_TIMEOUT)
+        .build()
+        .map_err(|e| eyre::eyre!("Failed to configure the browser: {e}"))?;
+
+    let (mut browser, mut handler) = Browser::launch(browser_config).await?;
+    let handler_task = tokio::spawn(async move {
+        while let Some(event) = handler.next().await {
+            if event.is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = async {
+        let page = browser.new_page(proof_request.url.as_str()).await?;
+        page.wait_for_navigation().await?;
+
+        let response: String = page
+            .evaluate_expression(fetch_script(headers, proof_request)?)
+            .await?
+            .into_value()?;
+
+        eyre::Ok(response)
+    }
+    .await;
+
+    if let Err(e) = browser.close().await {
+        tracing::warn!("Failed to close the browser: {e}");
+    }
+    handler_task.abort();
+
+    result
+}
@@ -0,0 +1,111 @@
+use std::collections::BTreeMap;
+
+use alloy::{
+    dyn_abi::{DynSolValue, JsonAbiExt, Specifier},
+    hex,
+    json_abi::Function,
+    primitives::{Address, Bytes, FixedBytes, U256},
+};
+
+use crate::config::ClaimCallConfig;
+
+fn render_arg(template: &str, placeholders: &BTreeMap<String, String>) -> String {
+    placeholders
+        .iter()
+        .fold(template.to_string(), |rendered, (name, value)| {
+            rendered.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+pub fn encode_call(
+    signature: &str,
+    args: &[String],
+    placeholders: &BTreeMap<String, String>,
+) -> eyre::Result<Bytes> {
+    let function = Function::parse(signature)?;
+
+    let values = function
+        .inputs
+        .iter()
+        .zip(args)
+        .map(|(param, template)| {
+            let rendered = render_arg(template, placeholders);
+            if rendered.contains('{') {
+                eyre::bail!(
+                    "Argument {} of {signature} has unresolved placeholders: {rendered}",
+                    param.name
+                );
+            }
+            Ok(param.resolve()?.coerce_str(&rendered)?)
+        })
+        .collect::<eyre::Result<Vec<DynSolValue>>>()?;
+
+    Ok(function.abi_encode_input(&values)?.into())
+}
+
+pub fn encode_claim(
+    claim_call: &ClaimCallConfig,
+    address: Address,
+    amount: U256,
+    proof: &[FixedBytes<32>],
+    fields: &BTreeMap<String, String>,
+) -> eyre::Result<Bytes> {
+    let proof = proof
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(",");
+
+    let mut placeholders = fields.clone();
+    placeholders.insert(String::from("address"), address.to_string());
+    placeholders.insert(String::from("amount"), amount.to_string());
+    placeholders.insert(String::from("proof"), format!("[{proof}]"));
+
+    encode_call(&claim_call.signature, &claim_call.args, &placeholders)
+}
+
+fn describe_value(value: &DynSolValue) -> String {
+    match value {
+        DynSolValue::Address(address) => address.to_string(),
+        DynSolValue::Bool(value) => value.to_string(),
+        DynSolValue::Int(value, _) => value.to_string(),
+        DynSolValue::Uint(value, _) => value.to_string(),
+        DynSolValue::FixedBytes(word, size) => hex::encode_prefixed(&word[..*size]),
+        DynSolValue::Bytes(bytes) => format!("{} bytes", bytes.len()),
+        DynSolValue::String(value) => format!("{value:?}"),
+        // Proofs and signatures are long, their length is what matters when eyeballing
+        DynSolValue::Array(values) | DynSolValue::FixedArray(values) => {
+            format!("[{} items]", values.len())
+        }
+        DynSolValue::Tuple(values) => format!(
+            "({})",
+            values
+                .iter()
+                .map(describe_value)
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+        _ => format!("{value:?}"),
+    }
+}
+
+pub fn describe_call(signature: &str, input: &[u8]) -> eyre::Result<String> {
+    let function = Function::parse(signature)?;
+    if input.len() < 4 || input[..4] != function.selector()[..] {
+        eyre::bail!("Calldata does not start with the selector of {signature}");
+    }
+
+    let values = function.abi_decode_input(&input[4..], true)?;
+    let args = function
+        .inputs
+        .iter()
+        .zip(&values)
+        .map(|(param, value)| match param.name.is_empty() {
+            true => describe_value(value),
+            false => format!("{}={}", param.name, describe_value(value)),
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Ok(format!("{}({args})", function.name))
+}
@@ -0,0 +1,134 @@
+use std::{collections::BTreeMap, path::Path, sync::OnceLock, time::Duration};
+
+use alloy::primitives::{Address, TxHash};
+use alloy_chains::NamedChain;
+use serde::Deserialize;
+
+use crate::constants::CAMPAIGNS_FILE_PATH;
+
+const BUNDLED_CAMPAIGNS: &str = include_str!("campaigns.toml");
+
+static CAMPAIGN: OnceLock<Campaign> = OnceLock::new();
+
+// Which side decides eligibility first, the other one only confirms it
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum EligibilitySource {
+    #[default]
+    Chain,
+    Api,
+}
+
+// What differs between the L2s a campaign can run on, read from the campaign's registry entry
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ChainSpec {
+    pub chain_id: u64,
+    pub explorer_url: String,
+    pub explorer_api_url: String,
+    #[serde(default = "default_native_symbol")]
+    pub native_symbol: String,
+    #[serde(default = "default_native_decimals")]
+    pub native_decimals: u8,
+    #[serde(default)]
+    pub block_time_ms: Option<u64>,
+    #[serde(default)]
+    pub legacy_fees: bool,
+}
+
+fn default_native_symbol() -> String {
+    String::from("ETH")
+}
+
+fn default_native_decimals() -> u8 {
+    18
+}
+
+impl ChainSpec {
+    pub fn tx_url(&self, tx_hash: TxHash) -> String {
+        format!("{}/tx/{tx_hash}", self.explorer_url)
+    }
+
+    pub fn address_url(&self, address: Address) -> String {
+        format!("{}/address/{address}", self.explorer_url)
+    }
+
+    // Receipts are polled at 60% of the block time, chains alloy doesn't know need BLOCK_TIME_MS
+    pub fn poll_interval(&self) -> Option<Duration> {
+        let block_time = match self.block_time_ms {
+            Some(block_time_ms) => Duration::from_millis(block_time_ms),
+            None => NamedChain::try_from(self.chain_id)
+                .ok()?
+                .average_blocktime_hint()?,
+        };
+
+        Some(block_time.mul_f32(0.6))
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct Campaign {
+    #[serde(flatten)]
+    pub chain: ChainSpec,
+    pub distributor: Address,
+    pub token: Address,
+    #[serde(default)]
+    pub token_symbol: Option<String>,
+    #[serde(default)]
+    pub token_decimals: Option<u8>,
+    pub proof_url: String,
+    #[serde(default)]
+    pub eligibility_source: EligibilitySource,
+    #[serde(default)]
+    pub cookie: String,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+}
+
+fn merge(base: &mut toml::Table, overrides: toml::Table) {
+    for (key, value) in overrides {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(base)), toml::Value::Table(overrides)) => {
+                merge(base, overrides)
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn read_registry() -> eyre::Result<toml::Table> {
+    let mut registry: toml::Table = toml::from_str(BUNDLED_CAMPAIGNS)?;
+
+    if Path::new(CAMPAIGNS_FILE_PATH).exists() {
+        let overrides = std::fs::read_to_string(CAMPAIGNS_FILE_PATH)?;
+        merge(&mut registry, toml::from_str(&overrides)?);
+    }
+
+    Ok(registry)
+}
+
+pub fn init_campaign(name: &str) -> eyre::Result<()> {
+    let mut registry = read_registry()?;
+    let entry = registry.remove(name).ok_or_else(|| {
+        let known: Vec<_> = registry.keys().collect();
+        eyre::eyre!("Unknown campaign {name}, known campaigns: {known:?}")
+    })?;
+    let campaign: Campaign = entry.try_into()?;
+
+    CAMPAIGN
+        .set(campaign)
+        .map_err(|_| eyre::eyre!("Campaign is already initialized"))?;
+
+    Ok(())
+}
+
+pub fn campaign() -> &'static Campaign {
+    CAMPAIGN.get().expect("Campaign to be initialized")
+}
+
+pub fn chain() -> &'static ChainSpec {
+    &campaign().chain
+}
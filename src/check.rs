@@ -0,0 +1,96 @@
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::U256,
+};
+use rand::thread_rng;
+
+use crate::{
+    config::Config,
+    multicall::{self, read_claim_statuses, ClaimStatus},
+    pool::init_provider_pool,
+    proof::{fetch_proof, ProofResponse},
+    state,
+    token::format_token,
+    utils::read_private_keys,
+    watchlist::{balance_calls, format_balances, resolve_watchlist},
+};
+
+pub async fn check_eligibility(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let mut rng = thread_rng();
+
+    let watchlist = resolve_watchlist(&providers.choose(&mut rng), &config).await?;
+    let wallets = read_private_keys().await?;
+    let mut eligible = 0;
+    let mut claimed = 0;
+    let mut total = U256::ZERO;
+
+    let addresses: Vec<_> = wallets
+        .iter()
+        .map(|wallet| <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet))
+        .collect();
+    let statuses = read_claim_statuses(&providers.choose(&mut rng), &addresses).await?;
+
+    for (address, status) in addresses.into_iter().zip(statuses) {
+        let Some(ClaimStatus {
+            claimed: has_claimed,
+            balance,
+        }) = status
+        else {
+            tracing::error!("{address} | failed to read claim status");
+            continue;
+        };
+
+        let proxy = config.proxy_for(address, &mut rng);
+        match fetch_proof(address, &proxy, &config).await {
+            Ok(ProofResponse::Ineligible) => tracing::info!("{address} | not eligible"),
+            Ok(ProofResponse::Eligible { amount, .. }) => {
+                eligible += 1;
+                total += amount;
+                if has_claimed {
+                    claimed += 1;
+                }
+
+                let status = if has_claimed {
+                    format!(" | already claimed, holds {}", format_token(balance))
+                } else {
+                    String::new()
+                };
+                tracing::info!("{address} | eligible for {}{status}", format_token(amount));
+            }
+            Err(e) => tracing::error!("{address} | could not get proof: {e}"),
+        }
+
+        match state::get(address).await {
+            Ok(Some(wallet)) if !wallet.notes.is_empty() => {
+                tracing::info!("{address} | notes: {}", wallet.notes_summary())
+            }
+            Ok(_) => {}
+            Err(e) => tracing::error!("{address} | failed to read notes: {e}"),
+        }
+
+        if !watchlist.is_empty() {
+            let provider = providers.choose(&mut rng);
+            match multicall::aggregate(&provider, balance_calls(&watchlist, address)).await {
+                Ok(results) => {
+                    let holdings = watchlist
+                        .iter()
+                        .zip(format_balances(&watchlist, &results))
+                        .map(|(token, balance)| format!("{balance} {}", token.symbol))
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    tracing::info!("{address} | holds {holdings}");
+                }
+                Err(e) => tracing::error!("{address} | failed to read watchlist balances: {e}"),
+            }
+        }
+    }
+
+    tracing::info!(
+        "{eligible}/{} wallets are eligible for {} in total, {claimed} already claimed",
+        wallets.len(),
+        format_token(total)
+    );
+
+    Ok(())
+}
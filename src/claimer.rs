@@ -1,33 +1,93 @@
-use std::{sync::Arc, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
 
 use alloy::{
-    network::{Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder},
-    primitives::{Address, Bytes, FixedBytes, U256},
-    providers::{Provider, ProviderBuilder, RootProvider},
-    rpc::{client::ClientBuilder, types::TransactionRequest},
+    network::{Ethereum, EthereumWallet, NetworkWallet, ReceiptResponse, TransactionBuilder},
+    primitives::{Address, Bytes, FixedBytes, TxHash, U256},
+    providers::{PendingTransactionBuilder, PendingTransactionError, Provider, WatchTxError},
+    rpc::types::{TransactionReceipt, TransactionRequest},
     sol,
     sol_types::SolCall,
-    transports::{http::Http, layers::RetryBackoffLayer, Transport},
+    transports::Transport,
+};
+use rand::thread_rng;
+use tokio::{
+    sync::{oneshot, Semaphore},
+    task::JoinSet,
 };
-use alloy_chains::NamedChain;
-use rand::{seq::SliceRandom, thread_rng};
-use tokio::task::JoinSet;
+use tracing::Instrument;
 
 use TokenDistributor::claimCall;
 use IERC20::transferCall;
 
 use crate::{
-    config::Config,
-    constants::{CLAIMER_CONTRACT_ADDRESS, SCROLL_CHAIN_ID, TOKEN_CONTRACT_ADDRESS},
-    proof::{extract_proof_and_amount, get_proof},
-    utils::{read_private_keys, read_recipients},
+    approval::ensure_approved,
+    calldata::{describe_call, encode_claim},
+    campaign::{campaign, chain, EligibilitySource},
+    config::{Config, DelegationStep, PayoutMode},
+    confirm,
+    constants::FAILED_FILE_PATH,
+    dashboard::{self, Tally},
+    delegation::delegate,
+    dust::sweep_eth,
+    errors::{
+        classify_node_error, classify_revert, classify_task_error, NodeError, PermanentRevert,
+        RevertKind, TaskErrorKind,
+    },
+    explorer::enrich_results,
+    failed::write_failed,
+    fee_breaker,
+    fees::{estimate_fees, FeeQuote},
+    fund::fund_recipient,
+    gas_token::GasToken,
+    inflight::{self, settle_crashed},
+    journal::{self, Event},
+    ledger,
+    logger::wallet_span,
+    merkle::local_tree,
+    metrics::{self, Stage, Timing},
+    monitor,
+    multicall::read_claim_statuses,
+    nonces,
+    notifier::{notify, notify_event},
+    payout::pay_out_stablecoin,
+    plan::{review_plan, Plan},
+    pool::init_provider_pool,
+    prefetch::{self, prefetch_proofs},
+    price,
+    priority::priority_of,
+    proof::{self, fetch_proof, ProofResponse},
+    race::race,
+    relayer::relayer,
+    report::export_report,
+    retry_budget, safety,
+    schedule::{wait_for_start, wait_until_claimable},
+    script,
+    sell::sell,
+    stake::{kept_amount, stake},
+    state, throttle,
+    timeline::export_timeline,
+    token::{format_token, parse_token},
+    tranches::{self, TrancheStatus},
+    utils::{check_whitelist, mark_ineligible, read_targets},
+    wallets,
+    webhook::{self, WebhookEvent},
 };
 
+const CLAIM_SIGNATURE: &str = "claim(address _account, uint256 _amount, bytes32[] _merkleProof)";
+
 sol! {
     #[sol(rpc)]
     contract TokenDistributor {
         function claim(address _account, uint256 _amount, bytes32[] calldata _merkleProof) external;
         mapping(address user => bool claimed) public hasClaimed;
+        function merkleRoot() external view returns (bytes32);
     }
 
     #[sol(rpc)]
@@ -39,9 +99,13 @@ sol! {
         }
 
         function delegate(PartialDelegation[] calldata _partialDelegations) public virtual;
+        function delegates(address _delegator) external view returns (PartialDelegation[] memory);
+        function DENOMINATOR() external view returns (uint96);
 
         mapping(address account => uint256) public balanceOf;
 
+        function symbol() external view returns (string);
+        function decimals() external view returns (uint8);
         function transfer(address to, uint256 amount) external returns (bool);
         function allowance(address owner, address spender) external view returns (uint256);
         function approve(address spender, uint256 amount) external returns (bool);
@@ -49,7 +113,115 @@ sol! {
     }
 }
 
-const SCROLL_EXPLORER_URL: &str = "https://scrollscan.com";
+const MAX_NONCE_REFRESHES: u64 = 3;
+const REORG_POLL_INTERVAL: Duration = Duration::from_secs(3);
+pub const MIN_REPLACEMENT_MULTIPLIER_PERCENT: u128 = 110;
+const MAX_BACKOFF_DOUBLINGS: u32 = 6;
+pub const CLAIM_GAS_ESTIMATE: u64 = 150_000;
+pub const TRANSFER_GAS_ESTIMATE: u64 = 65_000;
+
+static DRY_RUN: AtomicBool = AtomicBool::new(false);
+
+pub fn enable_dry_run() {
+    DRY_RUN.store(true, Ordering::Relaxed);
+}
+
+pub fn is_dry_run() -> bool {
+    DRY_RUN.load(Ordering::Relaxed)
+}
+
+async fn simulate_transaction<P, T>(
+    provider: &P,
+    tx_request: &TransactionRequest,
+    stage: Stage,
+    config: &Config,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let from = tx_request.from.unwrap_or_default();
+
+    if let Err(e) = provider.call(tx_request).await {
+        tracing::warn!("DRY RUN | {from} | {stage} would revert: {e}");
+        return Ok(false);
+    }
+
+    let gas_limit = provider.estimate_gas(tx_request).await?;
+    let max_fee_per_gas = FeeQuote::of(tx_request).max_fee_per_gas();
+    let gas_token = GasToken::from_config(config);
+    let cost = gas_token.price_wei(U256::from(gas_limit) * U256::from(max_fee_per_gas))?;
+
+    tracing::info!(
+        "DRY RUN | {from} | {stage} would succeed using {gas_limit} gas, costing up to {}",
+        gas_token.format(cost)
+    );
+
+    Ok(true)
+}
+
+// Keeps the access list only when it lowers the estimate, a failed lookup just sends without one
+async fn attach_access_list<P, T>(
+    provider: &P,
+    tx_request: &mut TransactionRequest,
+    gas_limit: u64,
+    stage: Stage,
+) -> u64
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let access_list = match provider.create_access_list(tx_request).await {
+        Ok(result) if result.error.is_none() => result.access_list,
+        Ok(result) => {
+            tracing::debug!("eth_createAccessList reverted: {:?}", result.error);
+            return gas_limit;
+        }
+        Err(e) => {
+            tracing::debug!("eth_createAccessList failed: {e}");
+            return gas_limit;
+        }
+    };
+
+    let with_list = tx_request.clone().with_access_list(access_list);
+    match provider.estimate_gas(&with_list).await {
+        Ok(listed_gas) if listed_gas < gas_limit => {
+            metrics::record_access_list_savings(stage, gas_limit - listed_gas);
+            *tx_request = with_list;
+            listed_gas
+        }
+        _ => gas_limit,
+    }
+}
+
+// A paused distributor or a node without the proof yet makes estimation revert, the stage's
+// GAS_LIMITS entry then stands in so the transaction is still sent
+async fn estimate_gas_limit<P, T>(
+    provider: &P,
+    tx_request: &TransactionRequest,
+    stage: Stage,
+    config: &Config,
+) -> eyre::Result<u64>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let e = match provider.estimate_gas(tx_request).await {
+        Ok(gas_limit) => return Ok(gas_limit),
+        Err(e) => e,
+    };
+    let Some(fallback) = config.gas_limits.get(&stage) else {
+        return Err(e.into());
+    };
+
+    let gas_limit = fallback * (100 + config.gas_limit_margin_percent) / 100;
+    tracing::warn!(
+        "Gas estimation of the {stage} transaction from {} failed, sending with {gas_limit} gas: {e}",
+        tx_request.from.unwrap_or_default()
+    );
+
+    Ok(gas_limit)
+}
 
 pub async fn send_transaction<P, T, W>(
     provider: P,
@@ -57,38 +229,247 @@ pub async fn send_transaction<P, T, W>(
     to: Address,
     input: Option<Bytes>,
     value: U256,
+    stage: Stage,
+    config: &Config,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let from = wallet.default_signer_address();
+    send_transaction_for(provider, wallet, from, to, input, value, stage, config).await
+}
+
+// Retries are charged to `budget_owner`, the wallet a relayer sends on behalf of
+#[allow(clippy::too_many_arguments)]
+pub async fn send_transaction_for<P, T, W>(
+    provider: P,
+    wallet: Arc<W>,
+    budget_owner: Address,
+    to: Address,
+    input: Option<Bytes>,
+    value: U256,
+    stage: Stage,
+    config: &Config,
 ) -> eyre::Result<bool>
 where
     P: Provider<T, Ethereum>,
     T: Transport + Clone,
     W: NetworkWallet<Ethereum>,
 {
-    let eip1559_fees = provider.estimate_eip1559_fees(None).await?;
     let from = wallet.default_signer_address();
+    retry_budget::ensure_available(budget_owner)?;
+
+    if !is_dry_run() {
+        fee_breaker::wait_for_normal_fees(&provider, config).await?;
+        fee_breaker::wait_for_gas_below_cap(&provider, config).await?;
+    }
+
+    let fees = estimate_fees(&provider, config).await?;
 
-    let nonce = provider.get_transaction_count(from).await?;
+    let mut nonce_lease = nonces::lease(&provider, from).await?;
+    let nonce = nonce_lease.nonce();
 
     let mut tx_request = TransactionRequest::default()
-        .with_max_fee_per_gas(eip1559_fees.max_fee_per_gas)
-        .with_max_priority_fee_per_gas(eip1559_fees.max_priority_fee_per_gas)
         .with_to(to)
         .with_value(value)
         .with_nonce(nonce)
-        .with_chain_id(SCROLL_CHAIN_ID)
+        .with_chain_id(chain().chain_id)
         .with_from(from);
+    fees.apply(&mut tx_request);
 
     if let Some(data) = input {
         tx_request.set_input(data);
     }
 
-    let gas_limit = provider.estimate_gas(&tx_request).await?;
+    if is_dry_run() {
+        return simulate_transaction(&provider, &tx_request, stage, config).await;
+    }
+
+    let mut gas_limit = estimate_gas_limit(&provider, &tx_request, stage, config).await?;
+    if config.access_lists {
+        gas_limit = attach_access_list(&provider, &mut tx_request, gas_limit, stage).await;
+    }
     tx_request.set_gas_limit(gas_limit);
 
-    let signed_transaction = tx_request.build(&wallet).await?;
-    let pending_tx = provider.send_tx_envelope(signed_transaction).await?;
-    let receipt = pending_tx.get_receipt().await?;
+    journal::record(Event::TxBuilt {
+        from,
+        to,
+        nonce,
+        gas_limit,
+    });
+
+    let deadline = Instant::now() + Duration::from_secs(config.tx_deadline);
+    let inclusion_timeout = Duration::from_secs(config.tx_inclusion_timeout);
+    let mut sent_hashes = vec![];
+    let mut first_sent = None;
+    let mut bump = 0;
+    let mut nonce_refreshes = 0;
+
+    let priority_fee_percent = config.priority_fee_percent(priority_of(from));
+
+    let receipt = loop {
+        let multiplier = config.fee_multiplier_percent(bump) * priority_fee_percent / 100;
+        fees.scaled(multiplier).apply(&mut tx_request);
+
+        let can_bump = bump < config.max_fee_bumps;
+
+        let signed_transaction = tx_request.clone().build(&wallet).await?;
+        let tx_hash = *signed_transaction.tx_hash();
+
+        throttle::acquire().await;
+        let broadcast_started = Instant::now();
+        let broadcast = provider.send_tx_envelope(signed_transaction).await;
+        metrics::record_duration(Timing::Broadcast, broadcast_started.elapsed());
+
+        let pending_tx = match broadcast {
+            Ok(pending_tx) => pending_tx,
+            Err(e) => match classify_node_error(&e.to_string()) {
+                Some(NodeError::AlreadyKnown) => {
+                    tracing::warn!("Transaction {tx_hash} is already known, waiting for it");
+                    PendingTransactionBuilder::new(provider.root(), tx_hash)
+                }
+                Some(NodeError::Underpriced) if can_bump => {
+                    tracing::warn!("Transaction from {from} is underpriced, bumping fees: {e}");
+                    journal::record(Event::Retry {
+                        address: from,
+                        error: e.to_string(),
+                    });
+                    retry_budget::spend(budget_owner);
+                    bump += 1;
+                    continue;
+                }
+                Some(NodeError::NonceTooLow) if nonce_refreshes < MAX_NONCE_REFRESHES => {
+                    if let Some(receipt) = find_receipt(&provider, &sent_hashes).await? {
+                        break receipt;
+                    }
+
+                    let nonce = nonce_lease.refresh(&provider, from).await?;
+                    tracing::warn!("Nonce too low for {from}, retrying with nonce {nonce}");
+                    journal::record(Event::Retry {
+                        address: from,
+                        error: e.to_string(),
+                    });
+                    retry_budget::spend(budget_owner);
+                    tx_request.set_nonce(nonce);
+                    nonce_refreshes += 1;
+                    continue;
+                }
+                _ => return Err(e.into()),
+            },
+        };
+
+        nonce_lease.broadcast();
+        sent_hashes.push(tx_hash);
+        first_sent.get_or_insert_with(Instant::now);
+        journal::record(Event::TxSent {
+            from,
+            tx_hash,
+            stage,
+        });
+        inflight::sent(from, stage, tx_hash);
+        monitor::record_own_nonce(from, tx_request.nonce.unwrap_or_default());
+        state::update(from, |wallet| wallet.tx_hashes.push(tx_hash)).await;
+
+        let timeout = inclusion_timeout.min(deadline.saturating_duration_since(Instant::now()));
+
+        match pending_tx.with_timeout(Some(timeout)).get_receipt().await {
+            Ok(receipt) => break receipt,
+            Err(PendingTransactionError::TxWatcher(WatchTxError::Timeout))
+                if can_bump && Instant::now() < deadline =>
+            {
+                tracing::warn!(
+                    "Transaction {tx_hash} was not included within {}s, bumping fees",
+                    config.tx_inclusion_timeout
+                );
+                journal::record(Event::Retry {
+                    address: from,
+                    error: format!("transaction {tx_hash} not included"),
+                });
+                retry_budget::spend(budget_owner);
+                bump += 1;
+            }
+            Err(PendingTransactionError::TxWatcher(WatchTxError::Timeout)) => {
+                // Out of bumps, the last fee level keeps its chance until the deadline
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if !remaining.is_zero() {
+                    tracing::warn!(
+                        "Transaction {tx_hash} is at its last fee level, waiting {}s for it before cancelling",
+                        remaining.as_secs()
+                    );
+                    match PendingTransactionBuilder::new(provider.root(), tx_hash)
+                        .with_timeout(Some(remaining))
+                        .get_receipt()
+                        .await
+                    {
+                        Ok(receipt) => break receipt,
+                        Err(PendingTransactionError::TxWatcher(WatchTxError::Timeout)) => {}
+                        Err(e) => return Err(e.into()),
+                    }
+                }
+
+                if let Some(receipt) = find_receipt(&provider, &sent_hashes).await? {
+                    break receipt;
+                }
+
+                cancel_transaction(&provider, wallet.clone(), &tx_request, config).await;
+                nonce_lease.abandon();
+                eyre::bail!("Transaction from {from} abandoned after missing its deadline");
+            }
+            Err(e) => return Err(e.into()),
+        }
+    };
+
+    inflight::settled(from);
+    journal::record(Event::Receipt {
+        from,
+        tx_hash: receipt.transaction_hash,
+        stage,
+        status: receipt.status(),
+        gas_used: receipt.gas_used(),
+        effective_gas_price: receipt.effective_gas_price(),
+    });
+
+    ledger::record(&provider, budget_owner, stage, value, &receipt).await;
+    if receipt.status() {
+        let tx_hash = receipt.transaction_hash;
+        match stage {
+            Stage::Claim => webhook::emit(WebhookEvent::ClaimConfirmed {
+                address: budget_owner,
+                tx_hash,
+            }),
+            Stage::Transfer | Stage::Sell => webhook::emit(WebhookEvent::TransferConfirmed {
+                address: budget_owner,
+                tx_hash,
+                stage,
+            }),
+            _ => {}
+        }
+    }
+
+    metrics::record_gas(stage, u128::from(gas_limit), receipt.gas_used());
+    metrics::record_gas_cost(receipt.gas_used() * receipt.effective_gas_price());
+    if let Some(first_sent) = first_sent {
+        metrics::record_duration(Timing::Inclusion(stage), first_sent.elapsed());
+    }
+
+    // Failing here re-queues the wallet, whose pipeline then re-checks what the chain says
+    if let Some(confirmations) = config.reorg_confirmations {
+        if !is_canonical(&provider, &receipt, confirmations).await? {
+            journal::record(Event::Reorged {
+                from,
+                tx_hash: receipt.transaction_hash,
+                stage,
+            });
+            eyre::bail!(
+                "{stage} transaction {} from {from} was reorged out",
+                receipt.transaction_hash
+            );
+        }
+    }
 
-    let url = format!("{SCROLL_EXPLORER_URL}/tx/{}", receipt.transaction_hash);
+    let url = chain().tx_url(receipt.transaction_hash);
 
     if receipt.status() {
         tracing::info!("Transaction successful: {}", url);
@@ -99,11 +480,106 @@ where
     Ok(receipt.status())
 }
 
+pub async fn cancel_transaction<P, T, W>(
+    provider: &P,
+    wallet: Arc<W>,
+    tx_request: &TransactionRequest,
+    config: &Config,
+) where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let from = wallet.default_signer_address();
+    let nonce = tx_request.nonce.unwrap_or_default();
+    let multiplier = config
+        .fee_multiplier_percent(1)
+        .max(MIN_REPLACEMENT_MULTIPLIER_PERCENT);
+
+    let mut cancel_request = TransactionRequest::default()
+        .with_to(from)
+        .with_value(U256::ZERO)
+        .with_nonce(nonce)
+        .with_gas_limit(21_000)
+        .with_chain_id(chain().chain_id)
+        .with_from(from);
+    FeeQuote::of(tx_request)
+        .scaled(multiplier)
+        .apply(&mut cancel_request);
+
+    let result = async {
+        let signed_transaction = cancel_request.build(&wallet).await?;
+        throttle::acquire().await;
+        let pending_tx = provider.send_tx_envelope(signed_transaction).await?;
+        monitor::record_own_nonce(from, nonce);
+        eyre::Ok(*pending_tx.tx_hash())
+    }
+    .await;
+
+    let cancel_tx_hash = match result {
+        Ok(tx_hash) => {
+            tracing::warn!("Sent cancel transaction {tx_hash} for {from} at nonce {nonce}");
+            Some(tx_hash)
+        }
+        Err(e) => {
+            tracing::error!("Failed to cancel transaction for {from} at nonce {nonce}: {e}");
+            None
+        }
+    };
+
+    journal::record(Event::Abandoned {
+        from,
+        nonce,
+        cancel_tx_hash,
+    });
+}
+
+pub async fn find_receipt<P, T>(
+    provider: &P,
+    tx_hashes: &[TxHash],
+) -> eyre::Result<Option<TransactionReceipt>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    for tx_hash in tx_hashes {
+        if let Some(receipt) = provider.get_transaction_receipt(*tx_hash).await? {
+            return Ok(Some(receipt));
+        }
+    }
+
+    Ok(None)
+}
+
+async fn is_canonical<P, T>(
+    provider: &P,
+    receipt: &TransactionReceipt,
+    confirmations: u64,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(block_number) = receipt.block_number else {
+        return Ok(true);
+    };
+
+    while provider.get_block_number().await? < block_number + confirmations {
+        tokio::time::sleep(REORG_POLL_INTERVAL).await;
+    }
+
+    Ok(provider
+        .get_transaction_receipt(receipt.transaction_hash)
+        .await?
+        .is_some_and(|canonical| canonical.status() == receipt.status()))
+}
+
 pub async fn transfer<P, T, W>(
     provider: Arc<P>,
     wallet: Arc<W>,
     to: Address,
     value: U256,
+    config: &Config,
 ) -> eyre::Result<bool>
 where
     P: Provider<T, Ethereum>,
@@ -111,24 +587,70 @@ where
     W: NetworkWallet<Ethereum>,
 {
     let address = wallet.default_signer_address();
-    tracing::info!("Sending {value} $SCR from {address} to {to}",);
+    tracing::info!("Sending {} from {address} to {to}", format_token(value));
+
+    transfer_token(provider, wallet, campaign().token, to, value, config).await
+}
+
+pub async fn transfer_token<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    token: Address,
+    to: Address,
+    value: U256,
+    config: &Config,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
     let input = transferCall { to, amount: value }.abi_encode();
 
     send_transaction(
         provider,
         wallet,
-        TOKEN_CONTRACT_ADDRESS,
+        token,
         Some(input.into()),
         U256::from(0),
+        Stage::Transfer,
+        config,
     )
     .await
 }
 
+pub fn claim_call_for(
+    address: Address,
+    amount: U256,
+    proof: Vec<FixedBytes<32>>,
+    fields: &BTreeMap<String, String>,
+    config: &Config,
+) -> eyre::Result<(Address, Bytes)> {
+    match &config.claim_call {
+        Some(claim_call) => Ok((
+            claim_call.contract.unwrap_or(campaign().distributor),
+            encode_claim(claim_call, address, amount, &proof, fields)?,
+        )),
+        None => Ok((
+            campaign().distributor,
+            claimCall {
+                _account: address,
+                _amount: amount,
+                _merkleProof: proof,
+            }
+            .abi_encode()
+            .into(),
+        )),
+    }
+}
+
 pub async fn claim<P, T, W>(
     provider: Arc<P>,
     wallet: Arc<W>,
     amount: U256,
     proof: Vec<FixedBytes<32>>,
+    fields: &BTreeMap<String, String>,
+    config: &Config,
 ) -> eyre::Result<bool>
 where
     P: Provider<T, Ethereum>,
@@ -138,21 +660,65 @@ where
     let address = wallet.default_signer_address();
     tracing::info!("Claiming {amount} for {address}");
 
-    let input = claimCall {
-        _account: address,
-        _amount: amount,
-        _merkleProof: proof,
+    let (to, input) = claim_call_for(address, amount, proof, fields, config)?;
+
+    let signature = config
+        .claim_call
+        .as_ref()
+        .map_or(CLAIM_SIGNATURE, |claim_call| claim_call.signature.as_str());
+    match describe_call(signature, &input) {
+        Ok(call) if is_dry_run() => tracing::info!("DRY RUN | {address} | claim calldata {call}"),
+        Ok(call) => tracing::debug!("Claim calldata for {address}: {call}"),
+        Err(e) => tracing::warn!("Failed to decode claim calldata for {address}: {e}"),
     }
-    .abi_encode();
 
-    send_transaction(
-        provider,
-        wallet,
-        CLAIMER_CONTRACT_ADDRESS,
-        Some(input.into()),
-        U256::from(0),
-    )
-    .await
+    let (succeeded, sender) = match relayer() {
+        Some(relayer) => {
+            let succeeded = send_transaction_for(
+                provider.clone(),
+                relayer.wallet.clone(),
+                address,
+                to,
+                Some(input.clone()),
+                U256::from(0),
+                Stage::Claim,
+                config,
+            )
+            .await?;
+            (succeeded, relayer.address)
+        }
+        None => {
+            let succeeded = send_transaction(
+                provider.clone(),
+                wallet,
+                to,
+                Some(input.clone()),
+                U256::from(0),
+                Stage::Claim,
+                config,
+            )
+            .await?;
+            (succeeded, address)
+        }
+    };
+    if succeeded {
+        return Ok(true);
+    }
+
+    // Replaying the call against the current state tells why it reverted, or that it no longer would
+    let replay = TransactionRequest::default()
+        .with_from(sender)
+        .with_to(to)
+        .with_input(input);
+    let reason = match provider.call(&replay).await {
+        Ok(_) => String::from("the claim no longer reverts"),
+        Err(e) => e.to_string(),
+    };
+
+    match classify_revert(&reason) {
+        RevertKind::Permanent => Err(PermanentRevert { reason }.into()),
+        RevertKind::Transient => Ok(false),
+    }
 }
 
 pub async fn get_token_balance<P, T>(
@@ -170,33 +736,272 @@ where
     Ok(balance)
 }
 
+pub enum WalletOutcome {
+    Completed,
+    Partial { failed: Vec<Stage> },
+    Ineligible,
+    InsufficientEth { shortfall: U256 },
+    // Not worth a claim under MIN_ALLOCATION
+    BelowMinimum { allocation: U256 },
+    // Left on the wallet for a later run, e.g. while ENABLE_CLAIM or ENABLE_TRANSFER is off
+    Held { reason: String },
+}
+
+// Returns whether the stage succeeded, failures of stages listed in OPTIONAL_STAGES are
+// recorded and swallowed instead of failing the wallet
+fn soft_fail(
+    stage: Stage,
+    result: eyre::Result<()>,
+    address: Address,
+    config: &Config,
+    failed: &mut Vec<Stage>,
+) -> eyre::Result<bool> {
+    match result {
+        Ok(()) => Ok(true),
+        Err(e) if config.optional_stages.contains(&stage) => {
+            tracing::warn!("{address} | optional {stage} stage failed, continuing: {e}");
+            journal::record(Event::StageFailed {
+                address,
+                stage,
+                error: e.to_string(),
+            });
+            failed.push(stage);
+            Ok(false)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+async fn sweep_token<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    token: Address,
+    symbol: &str,
+    recipient: Address,
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let address = wallet.default_signer_address();
+    let balance = get_token_balance(provider.clone(), address, token).await?;
+    if balance.is_zero() {
+        return Ok(());
+    }
+
+    tracing::info!("Sweeping {balance} {symbol} from {address} to {recipient}");
+    match transfer_token(provider, wallet, token, recipient, balance, config).await? {
+        true => Ok(()),
+        false => eyre::bail!("Sweep of {symbol} from {address} reverted"),
+    }
+}
+
+pub async fn missing_gas_funds<P, T>(
+    provider: &P,
+    address: Address,
+    gas_units: u64,
+    gas_token: &GasToken,
+    config: &Config,
+) -> eyre::Result<Option<U256>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let max_fee_per_gas = estimate_fees(provider, config).await?.max_fee_per_gas();
+    let required = gas_token.price_wei(U256::from(gas_units) * U256::from(max_fee_per_gas))?;
+    let balance = gas_token.balance(provider, address).await?;
+
+    Ok((balance < required).then(|| required - balance))
+}
+
+fn insufficient_eth(address: Address, shortfall: U256, gas_token: &GasToken) -> WalletOutcome {
+    let reason = format!("needs {}", gas_token.format(shortfall));
+    tracing::warn!("Skipping {address}: {reason}");
+    journal::record(Event::Skip { address, reason });
+
+    WalletOutcome::InsufficientEth { shortfall }
+}
+
+async fn skip_ineligible(address: Address) -> eyre::Result<WalletOutcome> {
+    tracing::warn!("{address} is not eligible for the airdrop");
+    journal::record(Event::Skip {
+        address,
+        reason: String::from("not eligible"),
+    });
+    if !is_dry_run() {
+        mark_ineligible(address).await?;
+    }
+
+    Ok(WalletOutcome::Ineligible)
+}
+
+async fn take_or_fetch_proof(
+    address: Address,
+    proxy: &str,
+    config: &Config,
+) -> eyre::Result<ProofResponse> {
+    match prefetch::take(address) {
+        Some(proof_response) => Ok(proof_response),
+        None => fetch_proof(address, proxy, config).await,
+    }
+}
+
 pub async fn claim_and_transfer<P, T, W>(
     wallet: Arc<W>,
     provider: Arc<P>,
     recipient: Address,
-    proxy: reqwest::Proxy,
-) -> eyre::Result<()>
+    proxy: String,
+    config: Arc<Config>,
+) -> eyre::Result<WalletOutcome>
 where
     P: Provider<T, Ethereum>,
     T: Transport + Clone,
     W: NetworkWallet<Ethereum>,
 {
     let distributor_contract_instance =
-        TokenDistributor::new(CLAIMER_CONTRACT_ADDRESS, provider.clone());
+        TokenDistributor::new(campaign().distributor, provider.clone());
 
     let wallet_address = wallet.default_signer_address();
-    let has_claimed = distributor_contract_instance
-        .hasClaimed(wallet_address)
+    let gas_token = GasToken::from_config(&config);
+    let mut failed = vec![];
+    let mut held = None;
+    let merkle_root = distributor_contract_instance
+        .merkleRoot()
         .call()
-        .await?
-        .claimed;
+        .await
+        .map(|root| root._0)
+        .ok();
+
+    if let (Some(tree), Some(root)) = (local_tree(), merkle_root) {
+        if tree.root() != root {
+            eyre::bail!(
+                "Local merkle root {} does not match the distributor root {root}",
+                tree.root()
+            );
+        }
+    }
+
+    let tranche = match merkle_root {
+        Some(root) => {
+            proof::set_merkle_root(root);
+            tranches::status(root, wallet_address).await?
+        }
+        None => TrancheStatus::First,
+    };
+
+    // API-first campaigns settle ineligible wallets without a single chain read
+    let mut proof_response = None;
+    if campaign().eligibility_source == EligibilitySource::Api
+        && tranche != TrancheStatus::Processed
+    {
+        match take_or_fetch_proof(wallet_address, &proxy, &config).await? {
+            ProofResponse::Ineligible => return skip_ineligible(wallet_address).await,
+            eligible => proof_response = Some(eligible),
+        }
+    }
+
+    // A new root is claimed again with its own proof, hasClaimed only covers the first one
+    let has_claimed = match tranche {
+        TrancheStatus::Processed => true,
+        TrancheStatus::New => {
+            tracing::info!("{wallet_address} | new merkle root, claiming its tranche");
+            // The flags describe the current tranche, not the one paid out before
+            state::update(wallet_address, |wallet| {
+                wallet.proof_fetched = false;
+                wallet.claimed = false;
+                wallet.transferred = false;
+            })
+            .await;
+            false
+        }
+        TrancheStatus::First => {
+            distributor_contract_instance
+                .hasClaimed(wallet_address)
+                .call()
+                .await?
+                .claimed
+        }
+    };
 
     let allocation = match has_claimed {
-        true => get_token_balance(provider.clone(), wallet_address, TOKEN_CONTRACT_ADDRESS).await?,
+        true => get_token_balance(provider.clone(), wallet_address, campaign().token).await?,
+        false if !config.enable_claim => {
+            tracing::info!("{wallet_address} is not claimed yet and ENABLE_CLAIM is off");
+            return Ok(WalletOutcome::Held {
+                reason: String::from("claim disabled"),
+            });
+        }
         false => {
-            let response = get_proof(wallet_address, proxy).await?; // TODO: request proof and allocation from the API
-            let (proof, allocation) = extract_proof_and_amount(&response)?;
-            claim(provider.clone(), wallet.clone(), allocation, proof).await?;
+            // A relayed claim leaves the wallet only its payout to pay for
+            let gas_units = match relayer() {
+                Some(_) => TRANSFER_GAS_ESTIMATE,
+                None => CLAIM_GAS_ESTIMATE + TRANSFER_GAS_ESTIMATE,
+            };
+            if let Some(shortfall) =
+                missing_gas_funds(&provider, wallet_address, gas_units, &gas_token, &config).await?
+            {
+                return Ok(insufficient_eth(wallet_address, shortfall, &gas_token));
+            }
+
+            let proof_response = match proof_response {
+                Some(proof_response) => proof_response,
+                None => take_or_fetch_proof(wallet_address, &proxy, &config).await?,
+            };
+
+            let (proof, allocation, fields) = match proof_response {
+                ProofResponse::Eligible {
+                    proof,
+                    amount,
+                    fields,
+                } => (proof, amount, fields),
+                ProofResponse::Ineligible => return skip_ineligible(wallet_address).await,
+            };
+
+            if let Some(minimum) = config
+                .min_allocation
+                .map(parse_token)
+                .transpose()?
+                .filter(|minimum| allocation < *minimum)
+            {
+                tracing::warn!(
+                    "Skipping {wallet_address}: its {} is below the MIN_ALLOCATION of {}",
+                    format_token(allocation),
+                    format_token(minimum)
+                );
+                return Ok(WalletOutcome::BelowMinimum { allocation });
+            }
+
+            journal::record(Event::ProofFetched {
+                address: wallet_address,
+                amount: allocation,
+                merkle_root,
+            });
+            state::update(wallet_address, |wallet| wallet.proof_fetched = true).await;
+
+            if !claim(
+                provider.clone(),
+                wallet.clone(),
+                allocation,
+                proof,
+                &fields,
+                &config,
+            )
+            .await?
+            {
+                eyre::bail!("Claim transaction for {wallet_address} reverted");
+            }
+            state::update(wallet_address, |wallet| wallet.claimed = true).await;
+
+            // The simulated claim leaves no tokens on the wallet, so the payout can't be simulated
+            if is_dry_run() {
+                tracing::info!(
+                    "DRY RUN | {wallet_address} | would then pay out {} to {recipient}",
+                    format_token(allocation)
+                );
+                return Ok(WalletOutcome::Completed);
+            }
 
             tokio::time::sleep(Duration::from_millis(500)).await;
 
@@ -204,72 +1009,852 @@ where
         }
     };
 
+    if let Some(root) = merkle_root.filter(|_| !is_dry_run()) {
+        tranches::mark_processed(root, wallet_address).await?;
+    }
+
+    let decision = script::decide(wallet_address, recipient, allocation)?;
+    if let Some(reason) = decision.hold {
+        tracing::info!(
+            "{wallet_address} keeps its {}, held by the wallet rules: {reason}",
+            format_token(allocation)
+        );
+        return Ok(WalletOutcome::Held { reason });
+    }
+    let recipient = match decision.recipient {
+        Some(routed) if routed != recipient => {
+            check_whitelist(&[routed]).await?;
+            safety::check_recipients(provider.as_ref(), &config, [routed]).await?;
+            tracing::info!("{wallet_address} | wallet rules route the payout to {routed}");
+            routed
+        }
+        _ => recipient,
+    };
+    let stake_config = config.stake.as_ref().filter(|_| !decision.skip_stake);
+    let sell_config = config
+        .sell
+        .as_ref()
+        .filter(|sell| sell.enabled && !decision.skip_sell);
+    let payout_mode = match config.payout_mode() {
+        PayoutMode::Stablecoin if decision.skip_sell => PayoutMode::Token,
+        payout_mode => payout_mode,
+    };
+
+    if allocation != U256::ZERO
+        && !price::wait_for_min_price(provider.as_ref(), &config, wallet_address).await?
+    {
+        return Ok(WalletOutcome::Held {
+            reason: String::from("price below MIN_TOKEN_PRICE_USD"),
+        });
+    }
+
     if allocation != U256::ZERO {
-        transfer(provider, wallet, recipient, allocation).await?;
+        if has_claimed {
+            if let Some(shortfall) = missing_gas_funds(
+                &provider,
+                wallet_address,
+                TRANSFER_GAS_ESTIMATE,
+                &gas_token,
+                &config,
+            )
+            .await?
+            {
+                return Ok(insufficient_eth(wallet_address, shortfall, &gas_token));
+            }
+        }
+
+        // Stake, sell and stablecoin payouts chain approvals the simulation can't carry over
+        let plain_transfer = stake_config.is_none()
+            && payout_mode != PayoutMode::Stablecoin
+            && sell_config.is_none();
+        if is_dry_run() && !plain_transfer {
+            tracing::info!(
+                "DRY RUN | {wallet_address} | would pay out {} to {recipient}",
+                format_token(allocation)
+            );
+            return Ok(WalletOutcome::Completed);
+        }
+
+        let allocation = match stake_config {
+            Some(stake_config) => {
+                let kept = kept_amount(stake_config, wallet_address, allocation)?;
+                if kept == U256::ZERO {
+                    allocation
+                } else {
+                    let staked = stake(
+                        provider.clone(),
+                        wallet.clone(),
+                        kept,
+                        stake_config,
+                        &config,
+                    )
+                    .await
+                    .and_then(|staked| match staked {
+                        true => Ok(()),
+                        false => Err(eyre::eyre!("Stake deposit for {wallet_address} reverted")),
+                    });
+
+                    // An optional stake that failed leaves the whole allocation to the payout
+                    match soft_fail(Stage::Stake, staked, wallet_address, &config, &mut failed)? {
+                        true => allocation - kept,
+                        false => allocation,
+                    }
+                }
+            }
+            None => allocation,
+        };
+
+        if let Some(delegation) = config
+            .delegation
+            .as_ref()
+            .filter(|delegation| delegation.after_claim != DelegationStep::Off)
+        {
+            let delegated = delegate(provider.clone(), wallet.clone(), delegation, &config)
+                .await
+                .and_then(|delegated| match delegated {
+                    true => Ok(()),
+                    false => Err(eyre::eyre!("Delegation of {wallet_address} reverted")),
+                });
+            soft_fail(
+                Stage::Delegate,
+                delegated,
+                wallet_address,
+                &config,
+                &mut failed,
+            )?;
+
+            if delegation.after_claim == DelegationStep::InsteadOfTransfer {
+                tracing::info!(
+                    "{wallet_address} keeps its {} delegated",
+                    format_token(allocation)
+                );
+                return match failed.is_empty() {
+                    true => Ok(WalletOutcome::Completed),
+                    false => Ok(WalletOutcome::Partial { failed }),
+                };
+            }
+        }
+
+        if let Some(approval_config) = config.approval.as_ref().filter(|_| !is_dry_run()) {
+            let payout = match (payout_mode, sell_config) {
+                (PayoutMode::Stablecoin, _) => "stablecoin",
+                (_, Some(_)) => "sell",
+                _ => "transfer",
+            };
+            ensure_approved(
+                approval_config,
+                wallet_address,
+                recipient,
+                allocation,
+                payout,
+            )
+            .await?;
+        }
+
+        let swapped = if payout_mode == PayoutMode::Stablecoin {
+            let paid_out = pay_out_stablecoin(
+                provider.clone(),
+                wallet.clone(),
+                allocation,
+                recipient,
+                &config,
+            )
+            .await;
+            soft_fail(Stage::Sell, paid_out, wallet_address, &config, &mut failed)?
+        } else if let Some(sell_config) = sell_config {
+            let token_out = sell_config.token_out;
+            let sold = sell(
+                provider.clone(),
+                wallet.clone(),
+                allocation,
+                token_out,
+                recipient,
+                &config,
+            )
+            .await
+            .and_then(|sold| match sold {
+                true => Ok(()),
+                false => Err(eyre::eyre!("Sell for {wallet_address} reverted")),
+            });
+            soft_fail(Stage::Sell, sold, wallet_address, &config, &mut failed)?
+        } else {
+            false
+        };
+
+        // A failed optional swap still leaves the tokens on the wallet, so they're transferred as is
+        let below_minimum = config
+            .min_transfer
+            .map(parse_token)
+            .transpose()?
+            .filter(|minimum| allocation < *minimum);
+        if !swapped && !config.enable_transfer {
+            tracing::info!(
+                "{wallet_address} keeps its {}, ENABLE_TRANSFER is off",
+                format_token(allocation)
+            );
+            held = Some("transfer disabled");
+        } else if let Some(minimum) = below_minimum.filter(|_| !swapped) {
+            tracing::warn!(
+                "{wallet_address} keeps its {}, below the MIN_TRANSFER of {}",
+                format_token(allocation),
+                format_token(minimum)
+            );
+            held = Some("below MIN_TRANSFER");
+        } else if !swapped {
+            let transferred = transfer(
+                provider.clone(),
+                wallet.clone(),
+                recipient,
+                allocation,
+                &config,
+            )
+            .await
+            .and_then(|transferred| match transferred {
+                true => Ok(()),
+                false => Err(eyre::eyre!("Transfer from {wallet_address} reverted")),
+            });
+            soft_fail(
+                Stage::Transfer,
+                transferred,
+                wallet_address,
+                &config,
+                &mut failed,
+            )?;
+        }
+
+        if let Some(funding) = &config.recipient_funding {
+            let funded = fund_recipient(
+                provider.clone(),
+                wallet.clone(),
+                recipient,
+                funding,
+                &config,
+            )
+            .await;
+            soft_fail(Stage::Fund, funded, wallet_address, &config, &mut failed)?;
+        }
+    } else {
+        journal::record(Event::Skip {
+            address: wallet_address,
+            reason: String::from("zero allocation"),
+        });
+    }
+
+    // Wallets claimed in an earlier pass are emptied of the other tokens in the same visit
+    if has_claimed {
+        for symbol in &config.sweep_tokens {
+            let swept = sweep_token(
+                provider.clone(),
+                wallet.clone(),
+                config.watchlist[symbol],
+                symbol,
+                recipient,
+                &config,
+            )
+            .await;
+            soft_fail(Stage::Transfer, swept, wallet_address, &config, &mut failed)?;
+        }
+    }
+
+    // Last, so every transaction above has already paid its gas
+    if config.sweep_eth {
+        let to = config.gas_collector.unwrap_or(recipient);
+        let swept = sweep_eth(provider.clone(), wallet.clone(), to, &config).await;
+        soft_fail(Stage::Dust, swept, wallet_address, &config, &mut failed)?;
+    }
+
+    if let Some(reason) = held {
+        return Ok(WalletOutcome::Held {
+            reason: reason.to_owned(),
+        });
+    }
+
+    match failed.is_empty() {
+        true => Ok(WalletOutcome::Completed),
+        false => Ok(WalletOutcome::Partial { failed }),
     }
+}
+
+async fn run_pipeline<P, T, W>(
+    wallet: Arc<W>,
+    provider: Arc<P>,
+    recipient: Address,
+    proxy: String,
+    config: Arc<Config>,
+) -> eyre::Result<WalletOutcome>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let Some(task_timeout) = config.task_timeout else {
+        return claim_and_transfer(wallet, provider, recipient, proxy, config).await;
+    };
 
-    Ok(())
+    let pipeline = claim_and_transfer(wallet, provider, recipient, proxy, config);
+    tokio::time::timeout(Duration::from_secs(task_timeout), pipeline)
+        .await
+        .map_err(|_| eyre::eyre!("Wallet pipeline timed out after {task_timeout}s"))?
 }
 
-pub async fn claim_for_all(config: Config) {
+fn retry_backoff(base_secs: u64, retry: u32) -> Duration {
+    Duration::from_secs(base_secs << (retry - 1).min(MAX_BACKOFF_DOUBLINGS))
+}
+
+/// What a claim run did with its wallets, wallets it didn't get to are in none of the lists
+#[derive(Debug, Clone, Default)]
+pub struct RunSummary {
+    pub wallets: usize,
+    pub completed: Vec<Address>,
+    /// Wallets given up on with their last error, as listed in data/failed.json
+    pub failed: Vec<(Address, String)>,
+    /// Wallets left alone with the reason, e.g. a wallet rule, missing gas or MIN_ALLOCATION
+    pub held: Vec<(Address, String)>,
+    pub ineligible: Vec<Address>,
+    pub claimed: Option<U256>,
+}
+
+pub async fn claim_for_all(config: Config, resume: bool) -> eyre::Result<RunSummary> {
+    let targets = read_targets(&config).await?;
+    claim_targets(config, targets, resume).await
+}
+
+pub async fn claim_targets(
+    config: Config,
+    mut targets: Vec<(Arc<EthereumWallet>, Address)>,
+    resume: bool,
+) -> eyre::Result<RunSummary> {
+    let started = Instant::now();
     let mut rng = thread_rng();
+    let config = Arc::new(config);
 
-    let init_providers = |rpc_urls: Vec<String>| -> Vec<_> {
-        let retry_layer = RetryBackoffLayer::new(10, 2, 500);
-
-        rpc_urls
-            .into_iter()
-            .map(|rpc_url| {
-                let client = ClientBuilder::default()
-                    .layer(retry_layer.clone())
-                    .transport(Http::new(rpc_url.parse().unwrap()), false);
-
-                Arc::new(
-                    ProviderBuilder::new()
-                        .with_recommended_fillers()
-                        .with_chain(NamedChain::Scroll)
-                        .on_provider(RootProvider::new(client)),
-                )
-            })
-            .collect()
+    let providers = init_provider_pool(&config).await?;
+    if let Some(gas_collector) = config.gas_collector.filter(|_| config.sweep_eth) {
+        check_whitelist(&[gas_collector]).await?;
+    }
+    safety::check_recipients(
+        &providers.choose(&mut rng),
+        &config,
+        targets.iter().map(|(_, recipient)| *recipient),
+    )
+    .await?;
+
+    let merkle_root = TokenDistributor::new(campaign().distributor, providers.choose(&mut rng))
+        .merkleRoot()
+        .call()
+        .await
+        .map(|root| root._0)
+        .ok();
+    if let Some(root) = merkle_root {
+        proof::set_merkle_root(root);
+    }
+    let mut new_tranche = HashSet::new();
+    if let Some(root) = merkle_root {
+        for (wallet, _) in &targets {
+            let address =
+                <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+            if tranches::status(root, address).await.ok() == Some(TrancheStatus::New) {
+                new_tranche.insert(address);
+            }
+        }
+        if !new_tranche.is_empty() {
+            tracing::info!(
+                "Merkle root {root} is new to {} wallets, their tranche is claimed again",
+                new_tranche.len()
+            );
+        }
+    }
+
+    // Claimed wallets only need their payout, so their proofs aren't requested again
+    let mut claimed = HashSet::new();
+    if resume {
+        let inclusion_timeout = Duration::from_secs(config.tx_inclusion_timeout);
+        if let Err(e) = settle_crashed(&providers.choose(&mut rng), inclusion_timeout).await {
+            tracing::warn!("Failed to settle the wallets of a crashed run: {e}");
+        }
+
+        let mut remaining = vec![];
+        for (wallet, recipient) in targets {
+            let address =
+                <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+            match state::get(address).await {
+                _ if new_tranche.contains(&address) => {}
+                Ok(Some(state)) if state.transferred => {
+                    tracing::info!("Skipping {address}: completed in an earlier run");
+                    continue;
+                }
+                Ok(Some(state)) if state.claimed => {
+                    claimed.insert(address);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Failed to read the state of {address}: {e}"),
+            }
+            remaining.push((wallet, recipient));
+        }
+        targets = remaining;
+    }
+
+    // One batched read spares every claimed wallet its proof request and readiness probe
+    let addresses: Vec<_> = targets
+        .iter()
+        .map(|(wallet, _)| {
+            <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(wallet)
+        })
+        .collect();
+    let pre_flight = match campaign().eligibility_source {
+        EligibilitySource::Chain => {
+            Some(read_claim_statuses(&providers.choose(&mut rng), &addresses).await)
+        }
+        EligibilitySource::Api => {
+            tracing::info!("Eligibility is API-first, skipping the pre-flight claim status read");
+            None
+        }
     };
+    let mut held = U256::ZERO;
+    match pre_flight {
+        None => {}
+        Some(Ok(statuses)) => {
+            let (mut already_claimed, mut holding) = (0, 0);
+            for (address, status) in addresses.iter().zip(statuses) {
+                if let Some(status) = status.filter(|status| status.claimed) {
+                    if !new_tranche.contains(address) {
+                        claimed.insert(*address);
+                    }
+                    already_claimed += 1;
+                    held += status.balance;
+                    if !status.balance.is_zero() {
+                        holding += 1;
+                    }
+                }
+            }
+            tracing::info!(
+                "Pre-flight: {already_claimed}/{} wallets already claimed, {holding} of them still hold tokens",
+                addresses.len()
+            );
+        }
+        Some(Err(e)) => tracing::warn!("Pre-flight claim status check failed: {e}"),
+    }
+
+    let plan = Plan::build(&targets, &config).await;
+    review_plan(&plan).await?;
+    let recipients: BTreeMap<_, _> = plan
+        .entries
+        .iter()
+        .map(|entry| (entry.wallet, entry.recipient))
+        .collect();
+
+    if let Some(start_at) = &config.start_at {
+        wait_for_start(start_at).await?;
+    }
+
+    let addresses: Vec<_> = targets
+        .iter()
+        .map(|(wallet, _)| {
+            <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(wallet)
+        })
+        .filter(|address| !claimed.contains(address))
+        .collect();
+    prefetch_proofs(addresses.clone(), config.clone()).await;
+
+    if confirm::is_enabled() {
+        let provider = providers.choose(&mut rng);
+        match confirm::confirm_run(&provider, targets.len(), &addresses, held).await {
+            Ok(true) => {}
+            Ok(false) => {
+                tracing::info!("Run cancelled before broadcasting anything");
+                return Ok(RunSummary {
+                    wallets: targets.len(),
+                    ..Default::default()
+                });
+            }
+            Err(e) => return Err(e.wrap_err("Failed to confirm the run")),
+        }
+    }
+
+    if let Some(race_config) = &config.race {
+        let racers: Vec<_> = targets
+            .iter()
+            .map(|(wallet, _)| wallet.clone())
+            .filter(|wallet| {
+                let address =
+                    <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(
+                        wallet,
+                    );
+                addresses.contains(&address) && priority_of(address) == race_config.priority
+            })
+            .collect();
+        if let Err(e) = race(&providers, &racers, race_config, &config).await {
+            tracing::error!("Race failed, claiming the usual way: {e}");
+        }
+    }
 
-    let providers = init_providers(config.rpc_urls.clone());
-    let wallets = read_private_keys().await;
-    let recipients = read_recipients().await;
+    if config.readiness_probe {
+        wait_until_claimable(providers.choose(&mut rng), &addresses, &config)
+            .await
+            .map_err(|e| e.wrap_err("Readiness probe failed"))?;
+    }
 
+    let permits = Arc::new(Semaphore::new(
+        config
+            .max_concurrent_tasks
+            .unwrap_or(Semaphore::MAX_PERMITS),
+    ));
     let mut handles = JoinSet::new();
+    let mut funding_todo = vec![];
+    let mut ineligible = vec![];
+    let mut below_minimum = vec![];
+    let mut exhausted = vec![];
+    let mut partial = vec![];
+    let mut dead_letters = vec![];
+    let mut task_retries = HashMap::new();
+    let mut completed = vec![];
+    let mut held_wallets = vec![];
 
-    for (wallet, recipient) in wallets.into_iter().zip(recipients.into_iter()) {
+    dashboard::start(providers.choose(&mut rng), targets.len());
+    for (wallet, recipient) in targets {
         tokio::time::sleep(Duration::from_millis(config.spawn_task_delay)).await;
-        let provider = providers.choose(&mut rng).unwrap().clone();
-        let proxy = config.get_random_proxy(&mut rng);
+        let provider = providers.choose(&mut rng);
+        let address =
+            <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        let proxy = config.proxy_for(address, &mut rng);
+        let config = config.clone();
+        let permits = permits.clone();
+        let (done_tx, done_rx) = match confirm::wants_confirmation() {
+            true => {
+                let (done_tx, done_rx) = oneshot::channel();
+                (Some(done_tx), Some(done_rx))
+            }
+            false => (None, None),
+        };
 
         handles.spawn(async move {
-            let task_result =
-                claim_and_transfer(wallet.clone(), provider, recipient, proxy.clone()).await;
-            (wallet, recipient, proxy, task_result)
+            let _permit = permits.acquire_owned().await.unwrap();
+            inflight::begin(address);
+            webhook::emit(WebhookEvent::WalletStarted { address });
+            let task_result = run_pipeline(
+                wallet.clone(),
+                provider.clone(),
+                recipient,
+                proxy.clone(),
+                config,
+            )
+            .instrument(wallet_span(address))
+            .await;
+            inflight::finish(address);
+            if let Some(done_tx) = done_tx {
+                let summary = match &task_result {
+                    Ok(_) => String::from("is done"),
+                    Err(e) => format!("failed: {e}"),
+                };
+                let _ = done_tx.send(summary);
+            }
+            (wallet, recipient, proxy, provider, task_result)
         });
+
+        // The wallet runs alone so its explorer results can be checked before the next one starts
+        if let Some(done_rx) = done_rx {
+            let summary = done_rx.await.unwrap_or_else(|_| String::from("stopped"));
+            match confirm::confirm_wallet(address, &summary).await {
+                Ok(true) => {}
+                Ok(false) => {
+                    tracing::info!("Stopped spawning wallets after {address}");
+                    break;
+                }
+                Err(e) => {
+                    tracing::error!("Failed to confirm {address}, stopping here: {e}");
+                    break;
+                }
+            }
+        }
     }
 
     while let Some(res) = handles.join_next().await {
-        let (wallet, recipient, proxy, task_result) = res.unwrap();
+        let (wallet, recipient, proxy, failed_provider, task_result) = res.unwrap();
         let address =
             <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
 
         match task_result {
-            Ok(_) => tracing::info!("Claimed and transferred: {address}",),
+            Ok(WalletOutcome::Completed) => {
+                tracing::info!("Claimed and transferred: {}", wallets::display(address));
+                notify_event(
+                    &config,
+                    format!("Claimed and transferred: {}", wallets::display(address)),
+                )
+                .await;
+                completed.push(address);
+                dashboard::record(Tally::Completed);
+                journal::record(Event::Completed { address });
+                state::update(address, |wallet| {
+                    wallet.claimed = true;
+                    wallet.transferred = true;
+                    wallet.failed_stages.clear();
+                    wallet.last_error = None;
+                })
+                .await;
+            }
+            Ok(WalletOutcome::Partial { failed }) => {
+                tracing::warn!(
+                    "Claimed and transferred with failed optional stages {failed:?}: {address}"
+                );
+                notify_event(
+                    &config,
+                    format!(
+                        "Claimed and transferred with failed optional stages {failed:?}: {address}"
+                    ),
+                )
+                .await;
+                completed.push(address);
+                dashboard::record(Tally::Completed);
+                partial.push(address);
+                journal::record(Event::Completed { address });
+                // A failed optional transfer leaves the tokens on the wallet for --resume to pick up
+                let transferred = !failed.contains(&Stage::Transfer);
+                state::update(address, |wallet| {
+                    wallet.claimed = true;
+                    wallet.transferred = transferred;
+                    wallet.failed_stages = failed;
+                    wallet.last_error = None;
+                })
+                .await;
+            }
+            Ok(WalletOutcome::Ineligible) => {
+                dashboard::record(Tally::Skipped);
+                ineligible.push(address)
+            }
+            Ok(WalletOutcome::Held { reason }) => {
+                dashboard::record(Tally::Skipped);
+                held_wallets.push((address, reason.clone()));
+                journal::record(Event::Skip { address, reason })
+            }
+            Ok(WalletOutcome::InsufficientEth { shortfall }) => {
+                dashboard::record(Tally::Skipped);
+                held_wallets.push((address, String::from("needs funding")));
+                funding_todo.push((address, shortfall))
+            }
+            Ok(WalletOutcome::BelowMinimum { allocation }) => {
+                dashboard::record(Tally::Skipped);
+                held_wallets.push((address, String::from("below MIN_ALLOCATION")));
+                journal::record(Event::Skip {
+                    address,
+                    reason: String::from("below MIN_ALLOCATION"),
+                });
+                below_minimum.push((address, allocation))
+            }
             Err(e) => {
-                tracing::error!("Claim or transfer failed with error {e}. Address: {address}");
-                let provider = providers.choose(&mut rng).unwrap().clone();
+                tracing::error!(
+                    "Claim or transfer failed with error {e}. Address: {}",
+                    wallets::display(address)
+                );
+                state::update(address, |wallet| wallet.last_error = Some(e.to_string())).await;
+                if classify_task_error(&e) == TaskErrorKind::Fatal {
+                    tracing::error!("Giving up on {address}: {e}");
+                    notify_event(&config, format!("Giving up on {address}: {e}")).await;
+                    journal::record(Event::Skip {
+                        address,
+                        reason: String::from("fatal error"),
+                    });
+                    webhook::emit(WebhookEvent::WalletFailed {
+                        address,
+                        error: e.to_string(),
+                    });
+                    dead_letters.push((address, e.to_string()));
+                    dashboard::record(Tally::Failed);
+                    continue;
+                }
+
+                let retries = task_retries.entry(address).or_insert(0u32);
+                *retries += 1;
+                let out_of_retries = if !retry_budget::spend(address) {
+                    Some("retry budget exhausted")
+                } else if config.max_task_retries.is_some_and(|max| *retries > max) {
+                    Some("task retries exhausted")
+                } else {
+                    None
+                };
+                if let Some(reason) = out_of_retries {
+                    tracing::error!("Giving up on {address}: {reason}");
+                    notify_event(
+                        &config,
+                        format!("Giving up on {address}: {reason}, last error {e}"),
+                    )
+                    .await;
+                    journal::record(Event::Skip {
+                        address,
+                        reason: String::from(reason),
+                    });
+                    webhook::emit(WebhookEvent::WalletFailed {
+                        address,
+                        error: format!("{reason}, last error {e}"),
+                    });
+                    exhausted.push((address, e.to_string()));
+                    dashboard::record(Tally::Failed);
+                    continue;
+                }
+
+                journal::record(Event::Retry {
+                    address,
+                    error: e.to_string(),
+                });
+                dashboard::record(Tally::Retried);
+                let backoff = retry_backoff(config.retry_backoff, *retries);
+                let provider = providers.choose_other(&mut rng, &failed_provider);
+                let config = config.clone();
+                let permits = permits.clone();
 
                 handles.spawn(async move {
-                    let task_result =
-                        claim_and_transfer(wallet.clone(), provider, recipient, proxy.clone())
-                            .await;
-                    (wallet, recipient, proxy, task_result)
+                    tokio::time::sleep(backoff).await;
+                    let _permit = permits.acquire_owned().await.unwrap();
+                    inflight::begin(address);
+                    webhook::emit(WebhookEvent::WalletStarted { address });
+                    let task_result = run_pipeline(
+                        wallet.clone(),
+                        provider.clone(),
+                        recipient,
+                        proxy.clone(),
+                        config,
+                    )
+                    .instrument(wallet_span(address))
+                    .await;
+                    inflight::finish(address);
+                    (wallet, recipient, proxy, provider, task_result)
                 });
             }
         }
     }
+
+    dashboard::stop();
+    metrics::log_gas_summary();
+    metrics::log_timing_summary(completed.len(), started.elapsed());
+
+    let usd_price = match price::usd_price(&providers.choose(&mut rng), &config).await {
+        Ok(usd_price) => usd_price,
+        Err(e) => {
+            tracing::warn!("Failed to fetch the token price: {e}");
+            None
+        }
+    };
+
+    let mut claimed_total = None;
+    if let Some(journal_path) = journal::journal_path() {
+        if let Err(e) = export_timeline(journal_path).await {
+            tracing::warn!("Failed to export the wallet timeline: {e}");
+        }
+
+        match export_report(journal_path, &recipients, usd_price).await {
+            Ok(total) => claimed_total = Some(total),
+            Err(e) => tracing::warn!("Failed to write the run report: {e}"),
+        }
+
+        if let Some(explorer_config) = &config.explorer {
+            if let Err(e) = enrich_results(journal_path, explorer_config).await {
+                tracing::warn!("Failed to enrich results from the explorer: {e}");
+            }
+        }
+    }
+
+    if !exhausted.is_empty() {
+        tracing::warn!(
+            "{} wallets ran out of retries: {:?}",
+            exhausted.len(),
+            exhausted
+                .iter()
+                .map(|(address, _)| address)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    if !dead_letters.is_empty() {
+        tracing::error!(
+            "{} wallets hit fatal errors: {:?}",
+            dead_letters.len(),
+            dead_letters
+                .iter()
+                .map(|(address, _)| address)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    if let Err(e) = write_failed(&dead_letters, &exhausted).await {
+        tracing::warn!("Failed to write {FAILED_FILE_PATH}: {e}");
+    }
+
+    if !partial.is_empty() {
+        tracing::warn!(
+            "{} wallets completed with failed optional stages: {partial:?}",
+            partial.len()
+        );
+    }
+
+    if !ineligible.is_empty() {
+        tracing::info!(
+            "{} wallets are not eligible: {ineligible:?}",
+            ineligible.len()
+        );
+    }
+
+    if !below_minimum.is_empty() {
+        let total = below_minimum
+            .iter()
+            .fold(U256::ZERO, |total, (_, allocation)| total + allocation);
+        tracing::info!(
+            "{} wallets left unclaimed below MIN_ALLOCATION, {} in total: {:?}",
+            below_minimum.len(),
+            format_token(total),
+            below_minimum
+                .iter()
+                .map(|(address, _)| address)
+                .collect::<Vec<_>>()
+        );
+    }
+
+    let mut summary = format!(
+        "Run finished: {}/{} wallets completed",
+        completed.len(),
+        recipients.len()
+    );
+    if let Some(total) = claimed_total {
+        summary += &format!(", {} claimed", format_token(total));
+        if let Some(usd_price) = usd_price {
+            summary += &format!(" (about ${:.2})", price::usd_value(total, usd_price));
+        }
+    }
+    let failed: Vec<_> = exhausted
+        .iter()
+        .chain(&dead_letters)
+        .map(|(address, _)| address)
+        .collect();
+    if !failed.is_empty() {
+        summary += &format!("\nFailed wallets: {failed:?}");
+    }
+    if !funding_todo.is_empty() {
+        summary += &format!("\n{} wallets need funding", funding_todo.len());
+    }
+    webhook::emit(WebhookEvent::RunFinished {
+        wallets: recipients.len(),
+        completed: completed.len(),
+        failed: failed.len(),
+        claimed: claimed_total,
+    });
+    notify(&config, summary).await;
+    webhook::flush().await;
+
+    if !funding_todo.is_empty() {
+        tracing::warn!(
+            "{} wallets need funding before they can claim:",
+            funding_todo.len()
+        );
+        let gas_token = GasToken::from_config(&config);
+        for (address, shortfall) in funding_todo {
+            tracing::warn!("{address} needs {}", gas_token.format(shortfall));
+        }
+    }
+
+    Ok(RunSummary {
+        wallets: recipients.len(),
+        completed,
+        failed: exhausted.into_iter().chain(dead_letters).collect(),
+        held: held_wallets,
+        ineligible,
+        claimed: claimed_total,
+    })
 }
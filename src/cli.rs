@@ -0,0 +1,164 @@
+use std::path::PathBuf;
+
+use alloy::primitives::Address;
+use clap::{Parser, Subcommand};
+
+use crate::{failed::FailureCategory, ledger::LedgerFormat};
+
+#[derive(Parser, Debug)]
+#[command(version, about = "Scroll airdrop claimer")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    /// Run as a service: never prompt, write a pid file and report readiness and liveness
+    #[arg(long, global = true)]
+    pub non_interactive: bool,
+    /// Print the pre-flight summary and ask before broadcasting anything
+    #[arg(long, global = true, conflicts_with_all = ["non_interactive", "dashboard"])]
+    pub interactive: bool,
+    /// With --interactive, run the first N wallets one at a time and ask before moving on from each
+    #[arg(
+        long,
+        global = true,
+        value_name = "N",
+        default_value_t = 0,
+        requires = "interactive"
+    )]
+    pub confirm_first: usize,
+    /// Simulate every transaction with eth_call and estimate_gas instead of broadcasting it
+    #[arg(long, global = true)]
+    pub dry_run: bool,
+    /// Run against a local anvil fork of this RPC, funding every wallet and pointing the distributor at MERKLE_SNAPSHOT if set, data/state is left untouched
+    #[arg(long, global = true, value_name = "RPC")]
+    pub fork: Option<String>,
+    /// Accept an execution plan that changed since the previous run, which `claim` and `plan` otherwise stop on
+    #[arg(long, global = true)]
+    pub accept_plan: bool,
+    /// Skip wallets that data/state marks as claimed and transferred by an earlier run and settle what a crashed run left in flight
+    #[arg(long, global = true)]
+    pub resume: bool,
+    /// Run against profiles/<NAME>/data, each profile keeping its own config, inputs, state and logs
+    #[arg(long, global = true, value_name = "NAME")]
+    pub profile: Option<String>,
+    /// Config file to read instead of data/config.toml
+    #[arg(long, global = true, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+    /// Private keys file to read instead of data/private_keys.txt
+    #[arg(long, global = true, value_name = "PATH")]
+    pub private_keys: Option<PathBuf>,
+    /// Recipients file to read instead of data/recipients.txt
+    #[arg(long, global = true, value_name = "PATH")]
+    pub recipients: Option<PathBuf>,
+    /// Wallets CSV to read instead of data/wallets.csv, used over the keys and recipients files when present
+    #[arg(long, global = true, value_name = "PATH")]
+    pub wallets: Option<PathBuf>,
+    /// Draw a live progress dashboard with a rolling log pane instead of the full log output, ignored with --non-interactive
+    #[arg(long, global = true)]
+    pub dashboard: bool,
+    /// Attach a free-form note to this run, kept in data/state/runs.json and the run journal
+    #[arg(long, global = true, value_name = "TEXT")]
+    pub note: Option<String>,
+}
+
+#[derive(Subcommand, Debug, Default)]
+pub enum Command {
+    /// Claim the allocation of every wallet and transfer it to its recipient
+    #[default]
+    Claim,
+    /// Run the claim again for the wallets a previous run gave up on, as listed in data/failed.json
+    RetryFailed {
+        /// Only retry wallets that failed with this kind of error
+        #[arg(long, value_enum)]
+        category: Option<FailureCategory>,
+    },
+    /// Transfer the $SCR balance of every wallet to its recipient
+    Transfer,
+    /// Move the $SCR and SWEEP_TOKENS balances of every wallet to SWEEP_TO or its recipient, without claiming
+    Sweep,
+    /// Report the eligibility, allocation and claim status of every wallet
+    Check,
+    /// Delegate every wallet's $SCR according to the [delegation] section
+    Delegate,
+    /// Print the ETH and $SCR balance of every wallet
+    Balances,
+    /// Estimate every wallet's allocation and gas cost at current prices, with totals
+    Estimate,
+    /// Check config, RPCs, proxies, inputs, contracts, disk and clock, printing a pass/fail checklist
+    Doctor,
+    /// Validate the config and input files
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+    /// Measure RPC and proxy latency and print a ranked report
+    Bench {
+        /// Rewrite RPC_URLS and PROXIES in the config file in ranked order
+        #[arg(long)]
+        rewrite_config: bool,
+    },
+    /// Build the execution plan and show how it differs from the previous one, saved once accepted
+    Plan,
+    /// Fetch and store every wallet's proof ahead of the claim, PREFETCH_CONCURRENCY requests at a time
+    FetchProofs,
+    /// Fetch every wallet's proof and verify it against the on-chain merkle root
+    VerifyProofs,
+    /// Re-check every wallet on chain after a run: claimed, emptied, and its recipient paid its allocation according to the receipts in data/ledger.jsonl
+    Verify,
+    /// Report the current delegation of every wallet
+    AuditDelegation,
+    /// Parse a captured proof API response and print the outcome
+    ParseFixture {
+        /// Path to the captured response body
+        path: PathBuf,
+    },
+    /// Export the per-wallet timeline of a past run from its journal
+    Timeline {
+        /// Path to the run journal
+        journal: PathBuf,
+    },
+    /// Export every transaction of data/ledger.jsonl as one CSV per wallet for tax tools, in data/ledger_export
+    Export {
+        /// CSV layout to write
+        #[arg(long, value_enum, default_value_t)]
+        format: LedgerFormat,
+    },
+    /// Diff success rates, gas, durations and failures between two run journals
+    CompareRuns {
+        /// Journal of the baseline run
+        a: PathBuf,
+        /// Journal of the run to compare against the baseline
+        b: PathBuf,
+    },
+    /// Send randomized ETH amounts from the funder wallet to every claim wallet
+    Fund,
+    /// Send ETH from the funder wallet only to wallets that can't pay for their claim and transfer
+    GasTopup,
+    /// Keep running and re-queue wallets that claimed but never paid out
+    Watch,
+    /// Dump balances, nonce, claim and delegation state of every wallet to a CSV
+    Snapshot,
+    /// Record SHA-256 hashes of the keys, recipients and config files
+    Manifest,
+    /// Restore data/state from its newest backup, or from the named one
+    Restore {
+        /// Backup folder name in data/state/backups
+        backup: Option<String>,
+    },
+    /// Attach a free-form note to a wallet, shown by check and in run reports
+    Note {
+        /// Wallet the note is about
+        address: Address,
+        /// Text of the note
+        text: String,
+    },
+    /// Encrypt the private keys file and wallets CSV in place, unlocked with KEYSTORE_PASSWORD or a prompt
+    EncryptKeys,
+    /// Write the private keys of the [recipient_mnemonic] recipients to data/recipient_keys.txt, encrypted like `encrypt-keys`
+    ExportKeys,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigAction {
+    /// Check the config with its SBB_ overrides, input files, key and recipient formats, proxy URLs and RPC connectivity, without the startup panics of other commands
+    Check,
+}
@@ -0,0 +1,177 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use alloy::primitives::{utils::format_ether, Address, U256};
+use serde::Deserialize;
+
+use crate::{metrics::Stage, utils::read_file_lines};
+
+#[derive(Deserialize)]
+struct Entry {
+    timestamp: u64,
+    event: String,
+    address: Option<Address>,
+    from: Option<Address>,
+    status: Option<bool>,
+    gas_used: Option<u128>,
+    effective_gas_price: Option<u128>,
+    reason: Option<String>,
+    stage: Option<Stage>,
+}
+
+#[derive(Default)]
+struct RunSummary {
+    wallets: BTreeSet<Address>,
+    completed: usize,
+    retries: usize,
+    first_event: Option<u64>,
+    last_event: Option<u64>,
+    gas_used: u128,
+    gas_cost: U256,
+    failures: BTreeMap<String, usize>,
+}
+
+impl RunSummary {
+    async fn read(journal_path: &Path) -> eyre::Result<Self> {
+        let mut summary = Self::default();
+
+        for line in read_file_lines(journal_path).await? {
+            let entry: Entry = serde_json::from_str(&line)?;
+            if let Some(address) = entry.address.or(entry.from) {
+                summary.wallets.insert(address);
+            }
+            summary.first_event.get_or_insert(entry.timestamp);
+            summary.last_event = Some(entry.timestamp);
+
+            match entry.event.as_str() {
+                "completed" => summary.completed += 1,
+                "retry" => summary.retries += 1,
+                "skip" => {
+                    let reason = entry.reason.unwrap_or_default();
+                    *summary.failures.entry(reason).or_default() += 1;
+                }
+                "stage_failed" => {
+                    let stage = entry
+                        .stage
+                        .map(|stage| stage.to_string())
+                        .unwrap_or_default();
+                    *summary
+                        .failures
+                        .entry(format!("optional {stage} failed"))
+                        .or_default() += 1;
+                }
+                "receipt" => {
+                    let gas_used = entry.gas_used.unwrap_or_default();
+                    summary.gas_used += gas_used;
+                    summary.gas_cost += U256::from(gas_used)
+                        * U256::from(entry.effective_gas_price.unwrap_or_default());
+                    if entry.status == Some(false) {
+                        *summary
+                            .failures
+                            .entry(String::from("reverted"))
+                            .or_default() += 1;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Ok(summary)
+    }
+
+    fn success_rate(&self) -> f64 {
+        if self.wallets.is_empty() {
+            return 0.0;
+        }
+        self.completed as f64 * 100.0 / self.wallets.len() as f64
+    }
+
+    fn duration(&self) -> u64 {
+        match (self.first_event, self.last_event) {
+            (Some(first), Some(last)) => last - first,
+            _ => 0,
+        }
+    }
+
+    fn gas_per_wallet(&self) -> u128 {
+        match self.completed {
+            0 => 0,
+            completed => self.gas_used / completed as u128,
+        }
+    }
+}
+
+fn log_row(metric: &str, a: impl ToString, b: impl ToString, delta: impl ToString) {
+    tracing::info!(
+        "{metric:<24} | {:>20} | {:>20} | {:>12}",
+        a.to_string(),
+        b.to_string(),
+        delta.to_string()
+    );
+}
+
+fn log_count_row(metric: &str, a: u128, b: u128) {
+    log_row(metric, a, b, format!("{:+}", b as i128 - a as i128));
+}
+
+fn log_numeric_row(metric: &str, a: f64, b: f64) {
+    log_row(
+        metric,
+        format!("{a:.2}"),
+        format!("{b:.2}"),
+        format!("{:+.2}", b - a),
+    );
+}
+
+pub async fn compare_runs(a: impl AsRef<Path>, b: impl AsRef<Path>) -> eyre::Result<()> {
+    let (a, b) = (a.as_ref(), b.as_ref());
+    let run_a = RunSummary::read(a).await?;
+    let run_b = RunSummary::read(b).await?;
+
+    tracing::info!("Comparing {} (a) against {} (b)", a.display(), b.display());
+    log_row("metric", "a", "b", "delta");
+    log_count_row(
+        "wallets",
+        run_a.wallets.len() as u128,
+        run_b.wallets.len() as u128,
+    );
+    log_count_row(
+        "completed",
+        run_a.completed as u128,
+        run_b.completed as u128,
+    );
+    log_numeric_row("success rate %", run_a.success_rate(), run_b.success_rate());
+    log_count_row("retries", run_a.retries as u128, run_b.retries as u128);
+    log_count_row(
+        "duration s",
+        run_a.duration() as u128,
+        run_b.duration() as u128,
+    );
+    log_count_row("gas used", run_a.gas_used, run_b.gas_used);
+    log_count_row(
+        "gas per completed wallet",
+        run_a.gas_per_wallet(),
+        run_b.gas_per_wallet(),
+    );
+    log_row(
+        "gas cost ETH",
+        format_ether(run_a.gas_cost),
+        format_ether(run_b.gas_cost),
+        "",
+    );
+
+    let categories: BTreeSet<_> = run_a.failures.keys().chain(run_b.failures.keys()).collect();
+    for category in categories {
+        let count_a = run_a.failures.get(category).copied().unwrap_or_default();
+        let count_b = run_b.failures.get(category).copied().unwrap_or_default();
+        log_count_row(
+            &format!("failed: {category}"),
+            count_a as u128,
+            count_b as u128,
+        );
+    }
+
+    Ok(())
+}
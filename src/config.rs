@@ -1,33 +1,1416 @@
-use rand::{rngs::ThreadRng, seq::SliceRandom};
-use reqwest::Proxy;
-use serde::Deserialize;
-use std::path::Path;
+use alloy::{
+    json_abi::Function,
+    primitives::{keccak256, Address},
+    signers::local::PrivateKeySigner,
+};
+use rand::rngs::ThreadRng;
+use reqwest::{Method, Url};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-const CONFIG_FILE_PATH: &str = "data/config.toml";
+use crate::{
+    campaign::{campaign, chain},
+    merkle::LeafEncoding,
+    metrics::Stage,
+    mnemonic::expand_paths,
+    paths::{config_path, wallets_path},
+    pool::is_websocket,
+    priority::Priority,
+    proxy_health,
+    schedule::StartAt,
+    wallets::bound_proxy,
+};
 
 #[derive(Deserialize, Clone, Debug)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub struct Config {
+    #[serde(default = "default_campaign")]
+    pub campaign: String,
+    #[serde(default)]
+    pub merkle_snapshot: Option<String>,
+    #[serde(default)]
+    pub optional_stages: Vec<Stage>,
+    #[serde(default = "default_enabled")]
+    pub enable_claim: bool,
+    #[serde(default = "default_enabled")]
+    pub enable_transfer: bool,
+    #[serde(default)]
+    pub min_transfer: Option<f64>,
+    #[serde(default)]
+    pub min_allocation: Option<f64>,
+    #[serde(default)]
+    pub watchlist: BTreeMap<String, Address>,
+    #[serde(default)]
+    pub sweep_tokens: Vec<String>,
+    #[serde(default)]
+    pub sweep_to: Option<Address>,
+    #[serde(default)]
+    pub sweep_eth: bool,
+    #[serde(default)]
+    pub gas_collector: Option<Address>,
+    #[serde(default)]
+    pub merkle_leaf_encoding: LeafEncoding,
+    #[serde(default)]
+    pub start_at: Option<StartAt>,
+    #[serde(default)]
+    pub readiness_probe: bool,
+    #[serde(default = "default_readiness_poll_interval")]
+    pub readiness_poll_interval: u64,
     pub rpc_urls: Vec<String>,
     pub spawn_task_delay: u64,
     pub proxies: Vec<String>,
+    #[serde(default = "default_proxy_ban_threshold")]
+    pub proxy_ban_threshold: u32,
+    #[serde(default)]
+    pub proxy_policy: ProxyPolicy,
+    #[serde(default = "default_proxy_retries")]
+    pub proxy_retries: usize,
+    #[serde(default = "default_proxy_check")]
+    pub proxy_check: bool,
+    #[serde(default)]
+    pub proxy_check_url: Option<String>,
+    #[serde(default = "default_fee_bump_percent")]
+    pub fee_bump_percent: u64,
+    #[serde(default = "default_max_fee_bumps")]
+    pub max_fee_bumps: u64,
+    #[serde(default = "default_tx_inclusion_timeout")]
+    pub tx_inclusion_timeout: u64,
+    #[serde(default = "default_tx_deadline")]
+    pub tx_deadline: u64,
+    #[serde(default)]
+    pub access_lists: bool,
+    #[serde(default)]
+    pub gas_limits: HashMap<Stage, u64>,
+    #[serde(default = "default_gas_limit_margin_percent")]
+    pub gas_limit_margin_percent: u64,
+    #[serde(default)]
+    pub max_tps: Option<f64>,
+    #[serde(default)]
+    pub task_timeout: Option<u64>,
+    #[serde(default)]
+    pub max_concurrent_tasks: Option<usize>,
+    #[serde(default)]
+    pub reorg_confirmations: Option<u64>,
+    #[serde(default)]
+    pub retry_budget_attempts: Option<u64>,
+    #[serde(default)]
+    pub retry_budget_secs: Option<u64>,
+    #[serde(default)]
+    pub max_task_retries: Option<u32>,
+    #[serde(default)]
+    pub retry_backoff: u64,
+    #[serde(default)]
+    pub fee_spike_percent: Option<u64>,
+    #[serde(default = "default_fee_spike_window")]
+    pub fee_spike_window: u64,
+    #[serde(default = "default_fee_spike_poll_interval")]
+    pub fee_spike_poll_interval: u64,
+    #[serde(default)]
+    pub max_base_fee_gwei: Option<f64>,
+    #[serde(default = "default_gas_poll_interval")]
+    pub gas_poll_interval: u64,
+    #[serde(default = "default_state_backups")]
+    pub state_backups: usize,
+    #[serde(default)]
+    pub encrypt_state: bool,
+    #[serde(default)]
+    pub script: Option<PathBuf>,
+    #[serde(default)]
+    pub wipe_keys: bool,
+    #[serde(default)]
+    pub key_backups: Vec<PathBuf>,
+    #[serde(default)]
+    pub rpc_discovery: bool,
+    #[serde(default = "default_max_head_lag")]
+    pub max_head_lag: u64,
+    #[serde(default = "default_head_check_interval")]
+    pub head_check_interval: u64,
+    #[serde(default = "default_max_rpc_latency")]
+    pub max_rpc_latency: u64,
+    #[serde(default = "default_watch_interval")]
+    pub watch_interval: u64,
+    #[serde(default = "default_idle_requeue_hours")]
+    pub idle_requeue_hours: u64,
+    #[serde(default)]
+    pub compromise_sweep: bool,
+    #[serde(default = "default_distributor_outflow_alert_percent")]
+    pub distributor_outflow_alert_percent: u64,
+    #[serde(default = "default_http2")]
+    pub http2: bool,
+    #[serde(default = "default_http_pool_idle_timeout")]
+    pub http_pool_idle_timeout: u64,
+    #[serde(default)]
+    pub http_pool_max_idle: Option<usize>,
+    #[serde(default)]
+    pub http_tcp_keepalive: Option<u64>,
+    #[serde(default)]
+    pub browser_fallback: bool,
+    #[serde(default)]
+    pub prefetch_concurrency: Option<usize>,
+    #[serde(default = "default_prefetch_max_concurrency")]
+    pub prefetch_max_concurrency: usize,
+    #[serde(default)]
+    pub locales: Vec<String>,
+    #[serde(default)]
+    pub proxy_locales: HashMap<String, String>,
+    #[serde(default)]
+    pub rpc_proxy: Option<String>,
+    #[serde(default)]
+    pub rpc_proxies: HashMap<String, String>,
+    #[serde(default)]
+    pub rpc_rate_limit: Option<f64>,
+    #[serde(default)]
+    pub rpc_rate_limits: HashMap<String, f64>,
+    #[serde(default, rename = "fees")]
+    pub fees: Option<FeesConfig>,
+    #[serde(default, rename = "fund")]
+    pub fund: Option<FundConfig>,
+    #[serde(default, rename = "relayer")]
+    pub relayer: Option<RelayerConfig>,
+    #[serde(default, rename = "price")]
+    pub price: Option<PriceConfig>,
+    #[serde(default, rename = "webhook")]
+    pub webhook: Option<WebhookConfig>,
+    #[serde(default, rename = "safety")]
+    pub safety: Option<SafetyConfig>,
+    #[serde(default, rename = "sell")]
+    pub sell: Option<SellConfig>,
+    #[serde(default, rename = "payout")]
+    pub payout: Option<PayoutConfig>,
+    #[serde(default, rename = "delegation")]
+    pub delegation: Option<DelegationConfig>,
+    #[serde(default, rename = "proof_auth")]
+    pub proof_auth: Option<ProofAuthConfig>,
+    #[serde(default, rename = "proof_request")]
+    pub proof_request: Option<ProofRequestConfig>,
+    #[serde(default, rename = "proof_api")]
+    pub proof_api: Option<ProofApiConfig>,
+    #[serde(default, rename = "claim_call")]
+    pub claim_call: Option<ClaimCallConfig>,
+    #[serde(default, rename = "stake")]
+    pub stake: Option<StakeConfig>,
+    #[serde(default, rename = "gas_token")]
+    pub gas_token: Option<GasTokenConfig>,
+    #[serde(default, rename = "explorer")]
+    pub explorer: Option<ExplorerConfig>,
+    #[serde(default, rename = "recipient_funding")]
+    pub recipient_funding: Option<RecipientFundingConfig>,
+    #[serde(default, rename = "notify")]
+    pub notify: Option<NotifyConfig>,
+    #[serde(default, rename = "mnemonic")]
+    pub mnemonic: Option<MnemonicConfig>,
+    #[serde(default, rename = "recipient_mnemonic")]
+    pub recipient_mnemonic: Option<MnemonicConfig>,
+    #[serde(default, rename = "signer")]
+    pub signer: Option<SignerConfig>,
+    #[serde(default, rename = "approval")]
+    pub approval: Option<ApprovalConfig>,
+    #[serde(default, rename = "race")]
+    pub race: Option<RaceConfig>,
+    #[serde(default, rename = "priority")]
+    pub priority: HashMap<Priority, PriorityClassConfig>,
 }
 
-impl Config {
-    async fn read_from_file(path: impl AsRef<Path>) -> eyre::Result<Self> {
-        let cfg_str = tokio::fs::read_to_string(path).await?;
-        Ok(toml::from_str(&cfg_str)?)
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct PriorityClassConfig {
+    #[serde(default = "default_class_fee_percent")]
+    pub fee_percent: u64,
+    #[serde(default)]
+    pub retry_budget_attempts: Option<u64>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct FeesConfig {
+    #[serde(default = "default_base_fee_multiplier")]
+    pub base_fee_multiplier: f64,
+    #[serde(default)]
+    pub priority_fee_min_gwei: Option<f64>,
+    #[serde(default)]
+    pub priority_fee_max_gwei: Option<f64>,
+    #[serde(default)]
+    pub legacy: bool,
+}
+
+impl FeesConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if self.base_fee_multiplier < 1.0 {
+            eyre::bail!("[fees] BASE_FEE_MULTIPLIER must be at least 1.0");
+        }
+
+        if let (Some(floor), Some(ceiling)) =
+            (self.priority_fee_min_gwei, self.priority_fee_max_gwei)
+        {
+            if floor > ceiling {
+                eyre::bail!("[fees] PRIORITY_FEE_MIN_GWEI is above PRIORITY_FEE_MAX_GWEI");
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ExplorerConfig {
+    pub api_key: String,
+    #[serde(default)]
+    pub api_url: Option<String>,
+}
+
+impl ExplorerConfig {
+    pub fn api_url(&self) -> &str {
+        self.api_url.as_deref().unwrap_or(&chain().explorer_api_url)
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ProxyPolicy {
+    #[default]
+    Rotate,
+    Sticky,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalFallback {
+    #[default]
+    Deny,
+    Approve,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ApprovalConfig {
+    pub url: String,
+    #[serde(default = "default_approval_timeout")]
+    pub timeout: u64,
+    #[serde(default)]
+    pub fallback: ApprovalFallback,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct NotifyConfig {
+    #[serde(default)]
+    pub telegram_bot_token: Option<String>,
+    #[serde(default)]
+    pub telegram_chat_id: Option<String>,
+    #[serde(default)]
+    pub discord_webhook_url: Option<String>,
+    #[serde(default)]
+    pub telegram_digest_hours: Option<u64>,
+    #[serde(default)]
+    pub discord_digest_hours: Option<u64>,
+}
+
+impl NotifyConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if self.telegram_bot_token.is_some() != self.telegram_chat_id.is_some() {
+            eyre::bail!("[notify] TELEGRAM_BOT_TOKEN and TELEGRAM_CHAT_ID must be set together");
+        }
+
+        if self.telegram_bot_token.is_none() && self.discord_webhook_url.is_none() {
+            eyre::bail!("[notify] needs a Telegram bot or a DISCORD_WEBHOOK_URL");
+        }
+
+        if self.telegram_digest_hours == Some(0) || self.discord_digest_hours == Some(0) {
+            eyre::bail!("[notify] digest intervals must be at least one hour");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct MnemonicConfig {
+    #[serde(default)]
+    pub phrase: Option<String>,
+    #[serde(default)]
+    pub passphrase: String,
+    pub path: String,
+}
+
+impl MnemonicConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        expand_paths(&self.path)?;
+
+        if wallets_path().exists() {
+            eyre::bail!(
+                "[mnemonic] can't be combined with {}, derived wallets have no recipients there",
+                wallets_path().display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum SignerBackendKind {
+    Ledger,
+    AwsKms,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct SignerConfig {
+    pub backend: SignerBackendKind,
+    #[serde(default)]
+    pub ledger_indexes: Vec<usize>,
+    #[serde(default)]
+    pub ledger_legacy_path: bool,
+    #[serde(default)]
+    pub kms_key_ids: Vec<String>,
+}
+
+impl SignerConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        let (empty, built, feature) = match self.backend {
+            SignerBackendKind::Ledger => (
+                self.ledger_indexes.is_empty(),
+                cfg!(feature = "ledger"),
+                "ledger",
+            ),
+            SignerBackendKind::AwsKms => (
+                self.kms_key_ids.is_empty(),
+                cfg!(feature = "aws-kms"),
+                "aws-kms",
+            ),
+        };
+        if empty {
+            eyre::bail!("[signer] lists no LEDGER_INDEXES or KMS_KEY_IDS for its backend");
+        }
+        if !built {
+            eyre::bail!("[signer] needs the claimer built with the `{feature}` feature");
+        }
+
+        if wallets_path().exists() {
+            eyre::bail!(
+                "[signer] can't be combined with {}, its wallets have no recipients there",
+                wallets_path().display()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct GasTokenConfig {
+    pub token: Address,
+    pub symbol: String,
+    pub decimals: u8,
+    pub tokens_per_eth: f64,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct RaceConfig {
+    #[serde(default = "default_race_priority")]
+    pub priority: Priority,
+    pub fee_multiplier_percent: u64,
+    pub gas_limit: u64,
+    #[serde(default = "default_head_poll_interval")]
+    pub head_poll_interval: u64,
+    #[serde(default = "default_open_window")]
+    pub open_window: u64,
+}
+
+fn default_race_priority() -> Priority {
+    Priority::High
+}
+
+fn default_head_poll_interval() -> u64 {
+    50
+}
+
+fn default_open_window() -> u64 {
+    600
+}
+
+impl RaceConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if self.fee_multiplier_percent < 100 {
+            eyre::bail!("[race] FEE_MULTIPLIER_PERCENT must be at least 100");
+        }
+        if self.gas_limit == 0 {
+            eyre::bail!("[race] GAS_LIMIT must be set, claims can't be estimated before they open");
+        }
+        if self.open_window == 0 {
+            eyre::bail!("[race] OPEN_WINDOW must be above 0");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct StakeConfig {
+    pub keep_percent: f64,
+    #[serde(default)]
+    pub keep_amount: Option<f64>,
+    #[serde(default)]
+    pub overrides: HashMap<Address, KeepOverride>,
+    #[serde(default)]
+    pub contract: Option<Address>,
+    #[serde(default = "default_deposit_signature")]
+    pub deposit_signature: String,
+    #[serde(default = "default_deposit_args")]
+    pub deposit_args: Vec<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct KeepOverride {
+    #[serde(default)]
+    pub keep_percent: Option<f64>,
+    #[serde(default)]
+    pub keep_amount: Option<f64>,
+}
+
+impl StakeConfig {
+    // A wallet override replaces both global values, KEEP_AMOUNT wins over KEEP_PERCENT
+    pub fn keep_for(&self, wallet: Address) -> (f64, Option<f64>) {
+        match self.overrides.get(&wallet) {
+            Some(keep) => (keep.keep_percent.unwrap_or(0.0), keep.keep_amount),
+            None => (self.keep_percent, self.keep_amount),
+        }
+    }
+
+    fn validate(&self) -> eyre::Result<()> {
+        let percents = std::iter::once(("KEEP_PERCENT".to_string(), self.keep_percent)).chain(
+            self.overrides.iter().filter_map(|(wallet, keep)| {
+                Some((
+                    format!("OVERRIDES.{wallet}.KEEP_PERCENT"),
+                    keep.keep_percent?,
+                ))
+            }),
+        );
+        for (name, percent) in percents {
+            if !(0.0..=100.0).contains(&percent) {
+                eyre::bail!("[stake] {name} must be between 0 and 100, got {percent}");
+            }
+        }
+
+        let amounts = self
+            .keep_amount
+            .into_iter()
+            .chain(self.overrides.values().filter_map(|keep| keep.keep_amount));
+        for amount in amounts {
+            if amount < 0.0 {
+                eyre::bail!("[stake] KEEP_AMOUNT must not be negative, got {amount}");
+            }
+        }
+
+        let function = Function::parse(&self.deposit_signature)?;
+        if function.inputs.len() != self.deposit_args.len() {
+            eyre::bail!(
+                "[stake] {} takes {} arguments but DEPOSIT_ARGS has {}",
+                self.deposit_signature,
+                function.inputs.len(),
+                self.deposit_args.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ClaimCallConfig {
+    #[serde(default)]
+    pub contract: Option<Address>,
+    pub signature: String,
+    pub args: Vec<String>,
+}
+
+impl ClaimCallConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        let function = Function::parse(&self.signature)?;
+        if function.inputs.len() != self.args.len() {
+            eyre::bail!(
+                "[claim_call] {} takes {} arguments but ARGS has {}",
+                self.signature,
+                function.inputs.len(),
+                self.args.len()
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ProofApiConfig {
+    #[serde(default)]
+    pub next_action: Option<String>,
+    #[serde(default)]
+    pub user_agent: Option<String>,
+    #[serde(default)]
+    pub cookie: Option<String>,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    #[serde(default = "default_proof_api_bootstrap")]
+    pub bootstrap: bool,
+    #[serde(default)]
+    pub bootstrap_url: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ProofRequestConfig {
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default = "default_proof_request_method")]
+    pub method: String,
+    #[serde(default)]
+    pub body: Option<String>,
+    #[serde(default)]
+    pub query: BTreeMap<String, String>,
+}
+
+impl Default for ProofRequestConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            method: default_proof_request_method(),
+            body: Some(String::from(r#"["{address}"]"#)),
+            query: BTreeMap::from([(String::from("step"), String::from("4"))]),
+        }
+    }
+}
+
+impl ProofRequestConfig {
+    pub fn url(&self) -> &str {
+        self.url.as_deref().unwrap_or(&campaign().proof_url)
+    }
+
+    pub fn method(&self) -> eyre::Result<Method> {
+        Ok(Method::from_bytes(self.method.to_uppercase().as_bytes())?)
+    }
+
+    fn validate(&self) -> eyre::Result<()> {
+        let method = self.method()?;
+        if method == Method::GET && self.body.is_some() {
+            eyre::bail!("[proof_request] BODY must be removed for GET requests");
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct ProofAuthConfig {
+    pub message: String,
+    #[serde(default = "default_signature_header")]
+    pub signature_header: String,
+    #[serde(default)]
+    pub message_header: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct DelegateeSplit {
+    pub delegatee: Address,
+    pub numerator: u64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum DelegationStep {
+    #[default]
+    Off,
+    BeforeTransfer,
+    InsteadOfTransfer,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct DelegationConfig {
+    #[serde(default = "default_delegation_denominator")]
+    pub denominator: u64,
+    #[serde(default)]
+    pub after_claim: DelegationStep,
+    pub delegatees: Vec<DelegateeSplit>,
+    #[serde(default)]
+    pub overrides: HashMap<Address, Vec<DelegateeSplit>>,
+}
+
+impl DelegationConfig {
+    pub fn splits_for(&self, wallet: Address) -> &[DelegateeSplit] {
+        self.overrides.get(&wallet).unwrap_or(&self.delegatees)
+    }
+
+    fn validate_splits(&self, owner: &str, splits: &[DelegateeSplit]) -> eyre::Result<()> {
+        if splits.is_empty() {
+            eyre::bail!("No delegatees configured for {owner}");
+        }
+
+        let mut seen = HashSet::new();
+        for split in splits {
+            if split.numerator == 0 {
+                eyre::bail!(
+                    "Delegatee {} of {owner} has a zero numerator",
+                    split.delegatee
+                );
+            }
+            if !seen.insert(split.delegatee) {
+                eyre::bail!("Delegatee {} is listed twice for {owner}", split.delegatee);
+            }
+        }
+
+        let total: u64 = splits.iter().map(|split| split.numerator).sum();
+        if total > self.denominator {
+            eyre::bail!(
+                "Delegation numerators of {owner} sum to {total}, more than {}",
+                self.denominator
+            );
+        }
+        if total < self.denominator {
+            tracing::warn!(
+                "Delegation numerators of {owner} sum to {total} out of {}, the rest stays undelegated",
+                self.denominator
+            );
+        }
+
+        Ok(())
+    }
+
+    fn validate(&self) -> eyre::Result<()> {
+        self.validate_splits("the default split", &self.delegatees)?;
+        for (wallet, splits) in &self.overrides {
+            self.validate_splits(&wallet.to_string(), splits)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PayoutMode {
+    #[default]
+    Token,
+    Stablecoin,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct PayoutConfig {
+    pub mode: PayoutMode,
+    pub stablecoin: Address,
+    pub min_amount: f64,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FundingSource {
+    #[default]
+    Wallet,
+    Funder,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct RecipientFundingConfig {
+    #[serde(default)]
+    pub source: FundingSource,
+    pub min_amount: f64,
+    pub max_amount: f64,
+}
+
+impl RecipientFundingConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if self.min_amount < 0.0 {
+            eyre::bail!(
+                "[recipient_funding] MIN_AMOUNT must not be negative, got {}",
+                self.min_amount
+            );
+        }
+        if self.min_amount > self.max_amount {
+            eyre::bail!(
+                "[recipient_funding] MIN_AMOUNT {} is above MAX_AMOUNT {}, swap them or set them equal for a fixed amount",
+                self.min_amount,
+                self.max_amount
+            );
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ContractRecipientAction {
+    #[default]
+    Warn,
+    Abort,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct SafetyConfig {
+    #[serde(default)]
+    pub contract_recipients: ContractRecipientAction,
+    #[serde(default)]
+    pub contract_allowlist: Vec<Address>,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEventKind {
+    WalletStarted,
+    ClaimConfirmed,
+    TransferConfirmed,
+    WalletFailed,
+    RunFinished,
+    SubmissionsPaused,
+    SubmissionsResumed,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default)]
+    pub secret: Option<String>,
+    #[serde(default)]
+    pub events: Vec<WebhookEventKind>,
+}
+
+impl WebhookConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        Url::parse(&self.url).map_err(|e| eyre::eyre!("[webhook] URL is invalid: {e}"))?;
+        if self.secret.as_deref().is_some_and(str::is_empty) {
+            eyre::bail!("[webhook] SECRET is empty, remove it to send unsigned events");
+        }
+
+        Ok(())
     }
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceSource {
+    #[default]
+    Coingecko,
+    Dex,
+}
 
-    pub async fn read_default() -> Self {
-        Self::read_from_file(CONFIG_FILE_PATH)
+#[derive(Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PriceGateAction {
+    #[default]
+    Wait,
+    Skip,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct PriceConfig {
+    #[serde(default)]
+    pub source: PriceSource,
+    #[serde(default = "default_coingecko_id")]
+    pub coingecko_id: String,
+    #[serde(default)]
+    pub coingecko_url: Option<String>,
+    #[serde(default)]
+    pub usd_token: Option<Address>,
+    #[serde(default)]
+    pub min_token_price_usd: Option<f64>,
+    #[serde(default)]
+    pub below_min: PriceGateAction,
+    #[serde(default = "default_price_poll_interval")]
+    pub poll_interval: u64,
+    #[serde(default = "default_price_cache_secs")]
+    pub cache_secs: u64,
+}
+
+fn default_coingecko_id() -> String {
+    String::from("scroll")
+}
+
+fn default_price_poll_interval() -> u64 {
+    300
+}
+
+fn default_price_cache_secs() -> u64 {
+    60
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct RelayerConfig {
+    pub private_key: String,
+}
+
+impl RelayerConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        PrivateKeySigner::from_str(self.private_key.trim())
+            .map_err(|e| eyre::eyre!("[relayer] PRIVATE_KEY is invalid: {e}"))?;
+
+        Ok(())
+    }
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct FundConfig {
+    pub funder_private_key: String,
+    #[serde(default)]
+    pub funder_pool: Vec<String>,
+    pub min_amount: f64,
+    pub max_amount: f64,
+    pub min_delay: u64,
+    pub max_delay: u64,
+    pub disperse_contract: Option<Address>,
+    #[serde(default = "default_fund_batch_size")]
+    pub batch_size: usize,
+    #[serde(default = "default_fund_max_batch_gas")]
+    pub max_batch_gas: u64,
+}
+
+impl FundConfig {
+    fn validate(&self) -> eyre::Result<()> {
+        if self.min_amount < 0.0 {
+            eyre::bail!(
+                "[fund] MIN_AMOUNT must not be negative, got {}",
+                self.min_amount
+            );
+        }
+        if self.min_amount > self.max_amount {
+            eyre::bail!(
+                "[fund] MIN_AMOUNT {} is above MAX_AMOUNT {}, swap them or set them equal for a fixed amount",
+                self.min_amount,
+                self.max_amount
+            );
+        }
+        if self.min_delay > self.max_delay {
+            eyre::bail!(
+                "[fund] MIN_DELAY {} is above MAX_DELAY {}, swap them or set them equal for a fixed delay",
+                self.min_delay,
+                self.max_delay
+            );
+        }
+
+        Ok(())
+    }
+}
+
+fn default_base_fee_multiplier() -> f64 {
+    2.0
+}
+
+fn default_proxy_ban_threshold() -> u32 {
+    3
+}
+
+fn default_proof_api_bootstrap() -> bool {
+    true
+}
+
+fn default_proxy_retries() -> usize {
+    2
+}
+
+fn default_proxy_check() -> bool {
+    true
+}
+
+fn default_fee_bump_percent() -> u64 {
+    15
+}
+
+fn default_max_fee_bumps() -> u64 {
+    3
+}
+
+fn default_gas_limit_margin_percent() -> u64 {
+    20
+}
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub struct SellConfig {
+    pub enabled: bool,
+    pub router: Address,
+    pub quoter: Address,
+    pub token_out: Address,
+    pub pool_fee: u32,
+    pub max_price_impact_percent: f64,
+    #[serde(default = "default_slippage_percent")]
+    pub slippage_percent: f64,
+    #[serde(default)]
+    pub unwrap_weth: bool,
+    pub min_sell_interval: u64,
+    pub poll_interval: u64,
+}
+
+fn default_slippage_percent() -> f64 {
+    0.5
+}
+
+fn default_delegation_denominator() -> u64 {
+    10_000
+}
+
+fn default_tx_inclusion_timeout() -> u64 {
+    120
+}
+
+fn default_tx_deadline() -> u64 {
+    900
+}
+
+fn default_fee_spike_window() -> u64 {
+    300
+}
+
+fn default_gas_poll_interval() -> u64 {
+    30
+}
+
+fn default_approval_timeout() -> u64 {
+    60
+}
+
+fn default_readiness_poll_interval() -> u64 {
+    1000
+}
+
+fn default_state_backups() -> usize {
+    5
+}
+
+fn default_fee_spike_poll_interval() -> u64 {
+    15
+}
+
+fn default_max_head_lag() -> u64 {
+    5
+}
+
+fn default_max_rpc_latency() -> u64 {
+    5000
+}
+
+fn default_head_check_interval() -> u64 {
+    10
+}
+
+fn default_watch_interval() -> u64 {
+    600
+}
+
+fn default_distributor_outflow_alert_percent() -> u64 {
+    20
+}
+
+fn default_idle_requeue_hours() -> u64 {
+    2
+}
+
+fn default_fund_batch_size() -> usize {
+    50
+}
+
+fn default_fund_max_batch_gas() -> u64 {
+    7_000_000
+}
+
+fn default_class_fee_percent() -> u64 {
+    100
+}
+
+fn default_campaign() -> String {
+    String::from("scroll")
+}
+
+fn default_proof_request_method() -> String {
+    String::from("POST")
+}
+
+fn default_deposit_signature() -> String {
+    String::from("deposit(uint256)")
+}
+
+fn default_deposit_args() -> Vec<String> {
+    vec![String::from("{amount}")]
+}
+
+fn default_signature_header() -> String {
+    String::from("x-signature")
+}
+
+fn default_prefetch_max_concurrency() -> usize {
+    16
+}
+
+fn default_http2() -> bool {
+    true
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_http_pool_idle_timeout() -> u64 {
+    90
+}
+
+const ENV_OVERRIDE_PREFIX: &str = "SBB_";
+
+// Values are read as TOML so lists, numbers and bools work, anything else is taken as a string.
+// TOML reads 0x as a hex integer, but here it's an address or a key
+fn parse_env_value(raw: &str) -> toml::Value {
+    if raw.starts_with("0x") {
+        return toml::Value::String(raw.to_string());
+    }
+
+    toml::from_str::<toml::Table>(&format!("value = {raw}"))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(raw.to_string()))
+}
+
+// `SBB_<KEY>` replaces a top level key and `SBB_<SECTION>__<KEY>` a key of a section, returns the
+// variables that were applied
+pub(crate) fn apply_env_overrides(table: &mut toml::Table) -> eyre::Result<Vec<String>> {
+    let mut applied = vec![];
+    for (name, raw) in std::env::vars() {
+        let Some(key) = name.strip_prefix(ENV_OVERRIDE_PREFIX) else {
+            continue;
+        };
+
+        let value = parse_env_value(&raw);
+        match key.split_once("__") {
+            Some((section, key)) => {
+                let section = section.to_lowercase();
+                let entry = table
+                    .entry(section.clone())
+                    .or_insert_with(|| toml::Value::Table(toml::Table::new()));
+                let toml::Value::Table(section_table) = entry else {
+                    eyre::bail!("{name} overrides a key of [{section}], which is not a section");
+                };
+                section_table.insert(key.to_string(), value);
+            }
+            None => {
+                table.insert(key.to_string(), value);
+            }
+        }
+        applied.push(name);
+    }
+    applied.sort();
+
+    Ok(applied)
+}
+
+pub async fn write_infra_ordering(rpc_urls: &[String], proxies: &[String]) -> eyre::Result<()> {
+    let cfg_str = tokio::fs::read_to_string(config_path()).await?;
+    let mut document = cfg_str.parse::<toml_edit::DocumentMut>()?;
+
+    document["RPC_URLS"] = toml_edit::value(rpc_urls.iter().collect::<toml_edit::Array>());
+    document["PROXIES"] = toml_edit::value(proxies.iter().collect::<toml_edit::Array>());
+
+    tokio::fs::write(config_path(), document.to_string()).await?;
+
+    Ok(())
+}
+
+impl Config {
+    pub async fn read_from_file(path: impl AsRef<Path>) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let cfg_str = tokio::fs::read_to_string(path)
             .await
-            .expect("Default config to be valid")
+            .map_err(|e| eyre::eyre!("Failed to read {}: {e}", path.display()))?;
+        let mut table: toml::Table = toml::from_str(&cfg_str)
+            .map_err(|e| eyre::eyre!("{} is not valid TOML: {e}", path.display()))?;
+        let overrides = apply_env_overrides(&mut table)?;
+        for name in &overrides {
+            tracing::info!("Config overridden by {name}");
+        }
+
+        // Parsing text keeps the key and line of a bad value in the error, the overridden table is
+        // written back out for that
+        let config: Self = match overrides.is_empty() {
+            true => toml::from_str(&cfg_str)
+                .map_err(|e| eyre::eyre!("{} is invalid: {e}", path.display()))?,
+            false => toml::from_str(&toml::to_string(&table)?).map_err(|e| {
+                eyre::eyre!(
+                    "{} with {} applied is invalid: {e}",
+                    path.display(),
+                    overrides.join(", ")
+                )
+            })?,
+        };
+        config.validate()?;
+
+        Ok(config)
     }
 
-    pub fn get_random_proxy(&self, rng: &mut ThreadRng) -> reqwest::Proxy {
-        let proxy = self.proxies.choose(rng).unwrap().clone();
+    fn validate(&self) -> eyre::Result<()> {
+        if let Some(fees) = &self.fees {
+            fees.validate()?;
+        }
+
+        if let Some(delegation) = &self.delegation {
+            delegation.validate()?;
+        }
+
+        if let Some(proof_request) = &self.proof_request {
+            proof_request.validate()?;
+        }
+
+        if let Some(claim_call) = &self.claim_call {
+            claim_call.validate()?;
+        }
+
+        if let Some(stake) = &self.stake {
+            stake.validate()?;
+        }
+
+        if let Some(notify) = &self.notify {
+            notify.validate()?;
+        }
+
+        if let Some(fund) = &self.fund {
+            fund.validate()?;
+        }
+
+        if let Some(recipient_funding) = &self.recipient_funding {
+            recipient_funding.validate()?;
+        }
+
+        if let Some(symbol) = self
+            .sweep_tokens
+            .iter()
+            .find(|symbol| !self.watchlist.contains_key(*symbol))
+        {
+            eyre::bail!("SWEEP_TOKENS entry {symbol} is not in the WATCHLIST");
+        }
+
+        if let Some(mnemonic) = &self.mnemonic {
+            mnemonic.validate()?;
+        }
+
+        if let Some(recipient_mnemonic) = &self.recipient_mnemonic {
+            expand_paths(&recipient_mnemonic.path)?;
+        }
+
+        if let Some(race) = &self.race {
+            race.validate()?;
+        }
+
+        if let Some(webhook) = &self.webhook {
+            webhook.validate()?;
+        }
+
+        if let Some(price) = &self.price {
+            if price
+                .min_token_price_usd
+                .is_some_and(|minimum| minimum <= 0.0)
+            {
+                eyre::bail!("[price] MIN_TOKEN_PRICE_USD must be above zero");
+            }
+            if price.source == PriceSource::Dex && self.sell.is_none() {
+                eyre::bail!(
+                    "[price] SOURCE = \"dex\" quotes through the [sell] pool, set up [sell]"
+                );
+            }
+            if price.source == PriceSource::Dex
+                && price.usd_token.is_none()
+                && self.payout.is_none()
+            {
+                eyre::bail!("[price] SOURCE = \"dex\" needs USD_TOKEN or a [payout] STABLECOIN");
+            }
+        }
+
+        if let Some(relayer) = &self.relayer {
+            relayer.validate()?;
+            // Race claims are pre-signed by the wallets themselves
+            if self.race.is_some() {
+                eyre::bail!("[relayer] can't be combined with [race]");
+            }
+        }
+
+        if let Some(signer) = &self.signer {
+            signer.validate()?;
+            // Hardware and cloud keys only sign transactions, proof requests need a local key
+            if self.proof_auth.is_some() {
+                eyre::bail!("[signer] can't be combined with [proof_auth]");
+            }
+        }
+
+        if self.sweep_eth && self.gas_token.is_some() {
+            eyre::bail!("SWEEP_ETH moves native ETH and can't be combined with [gas_token]");
+        }
+
+        if let Some(rpc_url) = self
+            .rpc_urls
+            .iter()
+            .find(|rpc_url| is_websocket(rpc_url) && self.rpc_proxy_for(rpc_url).is_some())
+        {
+            eyre::bail!(
+                "{rpc_url} is a WebSocket RPC, RPC_PROXY and RPC_PROXIES only route HTTP RPCs"
+            );
+        }
+
+        if let Some(start_at) = &self.start_at {
+            start_at.unix_timestamp()?;
+        }
+
+        if self.browser_fallback && !cfg!(feature = "browser") {
+            tracing::warn!(
+                "BROWSER_FALLBACK is set but the claimer was built without the `browser` feature"
+            );
+        }
+
+        Ok(())
+    }
+
+    pub async fn read_default() -> eyre::Result<Self> {
+        Self::read_from_file(config_path()).await
+    }
+
+    pub fn fee_multiplier_percent(&self, bump: u64) -> u128 {
+        (100 + self.fee_bump_percent * bump) as u128
+    }
+
+    pub fn priority_fee_percent(&self, priority: Priority) -> u128 {
+        self.priority
+            .get(&priority)
+            .map_or(100, |class| class.fee_percent) as u128
+    }
+
+    pub fn priority_retry_attempts(&self) -> HashMap<Priority, u64> {
+        self.priority
+            .iter()
+            .filter_map(|(priority, class)| Some((*priority, class.retry_budget_attempts?)))
+            .collect()
+    }
+
+    pub fn payout_mode(&self) -> PayoutMode {
+        self.payout
+            .as_ref()
+            .map(|payout| payout.mode)
+            .unwrap_or_default()
+    }
+
+    pub fn proof_request(&self) -> ProofRequestConfig {
+        self.proof_request.clone().unwrap_or_default()
+    }
+
+    // Seeds the cookie jars of proxies without a stored session
+    pub fn proof_cookie(&self) -> &str {
+        self.proof_api
+            .as_ref()
+            .and_then(|proof_api| proof_api.cookie.as_deref())
+            .unwrap_or(&campaign().cookie)
+    }
+
+    pub fn locale_for(&self, wallet: Address, proxy: &str) -> Option<&str> {
+        if let Some(locale) = self.proxy_locales.get(proxy) {
+            return Some(locale);
+        }
+
+        if self.locales.is_empty() {
+            return None;
+        }
+
+        let seed = keccak256([wallet.as_slice(), proxy.as_bytes()].concat());
+        let index = u64::from_be_bytes(seed[..8].try_into().unwrap()) as usize % self.locales.len();
+
+        Some(&self.locales[index])
+    }
+
+    pub fn rpc_proxy_for(&self, rpc_url: &str) -> Option<&str> {
+        self.rpc_proxies
+            .get(rpc_url)
+            .or(self.rpc_proxy.as_ref())
+            .map(String::as_str)
+    }
+
+    pub fn rpc_rate_limit_for(&self, rpc_url: &str) -> Option<f64> {
+        self.rpc_rate_limits
+            .get(rpc_url)
+            .or(self.rpc_rate_limit.as_ref())
+            .copied()
+    }
+
+    pub fn get_random_proxy(&self, rng: &mut ThreadRng) -> String {
+        proxy_health::choose(self, rng)
+    }
+
+    pub fn proxy_for(&self, address: Address, rng: &mut ThreadRng) -> String {
+        bound_proxy(address).unwrap_or_else(|| proxy_health::choose_for(self, address, rng))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The overrides read the whole environment, so the cases share one test instead of racing
+    #[test]
+    fn env_overrides_replace_top_level_and_section_keys() {
+        let mut table: toml::Table = toml::from_str(
+            "SPAWN_TASK_DELAY = 5\nRPC_URLS = [\"http://a\"]\n[fund]\nMIN_AMOUNT = 0.1\n",
+        )
+        .unwrap();
+
+        let vars = [
+            ("SBB_SPAWN_TASK_DELAY", "0"),
+            ("SBB_RPC_URLS", "[\"http://b\", \"http://c\"]"),
+            ("SBB_FUND__MIN_AMOUNT", "0.2"),
+            ("SBB_WEBHOOK__URL", "https://hooks.example.com/claims"),
+            (
+                "SBB_IN_WALLET_BALANCE_ADDRESS",
+                "0x000000000000000000000000000000000000dEaD",
+            ),
+        ];
+        for (name, value) in vars {
+            std::env::set_var(name, value);
+        }
+        let applied = apply_env_overrides(&mut table);
+        for (name, _) in vars {
+            std::env::remove_var(name);
+        }
+
+        let mut expected: Vec<_> = vars.iter().map(|(name, _)| name.to_string()).collect();
+        expected.sort();
+        assert_eq!(applied.unwrap(), expected);
 
-        Proxy::all(proxy).unwrap()
+        assert_eq!(table["SPAWN_TASK_DELAY"], toml::Value::Integer(0));
+        assert_eq!(
+            table["RPC_URLS"],
+            toml::Value::Array(vec![
+                toml::Value::String(String::from("http://b")),
+                toml::Value::String(String::from("http://c")),
+            ])
+        );
+        assert_eq!(table["fund"]["MIN_AMOUNT"], toml::Value::Float(0.2));
+        // Values that aren't TOML are kept as strings, and missing sections are created
+        assert_eq!(
+            table["webhook"]["URL"],
+            toml::Value::String(String::from("https://hooks.example.com/claims"))
+        );
+        assert_eq!(
+            table["IN_WALLET_BALANCE_ADDRESS"],
+            toml::Value::String(String::from("0x000000000000000000000000000000000000dEaD"))
+        );
     }
 }
@@ -0,0 +1,94 @@
+use std::{
+    io::Write,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        OnceLock,
+    },
+};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{utils::format_ether, Address, U256},
+    providers::Provider,
+    transports::Transport,
+};
+
+use crate::{
+    campaign::chain,
+    claimer::{CLAIM_GAS_ESTIMATE, TRANSFER_GAS_ESTIMATE},
+    prefetch,
+    proof::ProofResponse,
+    token::format_token,
+};
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CONFIRM_FIRST: OnceLock<usize> = OnceLock::new();
+static CONFIRMED: AtomicUsize = AtomicUsize::new(0);
+
+pub fn enable(confirm_first: usize) {
+    ENABLED.store(true, Ordering::Relaxed);
+    let _ = CONFIRM_FIRST.set(confirm_first);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub async fn ask(question: String) -> eyre::Result<bool> {
+    tokio::task::spawn_blocking(move || {
+        print!("{question} [y/N] ");
+        std::io::stdout().flush()?;
+
+        let mut answer = String::new();
+        std::io::stdin().read_line(&mut answer)?;
+        Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+    })
+    .await?
+}
+
+// Allocations come from the prefetched proofs, wallets without one are counted as unknown
+pub async fn confirm_run<P, T>(
+    provider: &P,
+    wallets: usize,
+    unclaimed: &[Address],
+    held: U256,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let mut allocation = held;
+    let mut unknown = 0;
+    for address in unclaimed {
+        match prefetch::peek(*address) {
+            Some(ProofResponse::Eligible { amount, .. }) => allocation += amount,
+            Some(ProofResponse::Ineligible) => {}
+            None => unknown += 1,
+        }
+    }
+
+    let gas_price = U256::from(provider.get_gas_price().await?);
+    let gas_units =
+        unclaimed.len() as u64 * CLAIM_GAS_ESTIMATE + wallets as u64 * TRANSFER_GAS_ESTIMATE;
+
+    tracing::info!(
+        "About to run {wallets} wallets, {} of them unclaimed, moving {} (allocation of {unknown} unknown) for about {} ETH of gas",
+        unclaimed.len(),
+        format_token(allocation),
+        format_ether(gas_price * U256::from(gas_units))
+    );
+
+    ask(String::from("Broadcast?")).await
+}
+
+// Asked once each of the first CONFIRM_FIRST wallets is done, before the next one starts
+pub fn wants_confirmation() -> bool {
+    is_enabled() && CONFIRMED.load(Ordering::Relaxed) < CONFIRM_FIRST.get().copied().unwrap_or(0)
+}
+
+pub async fn confirm_wallet(address: Address, summary: &str) -> eyre::Result<bool> {
+    CONFIRMED.fetch_add(1, Ordering::Relaxed);
+    tracing::info!("{address} {summary}, see {}", chain().address_url(address));
+
+    ask(String::from("Continue with the next wallet?")).await
+}
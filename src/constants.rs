@@ -1,13 +1,39 @@
 use alloy::primitives::{address, Address};
 
-// 1f192c261D463cD1c4E0B2F5696452448DC47506 - ONE MORE POSSIBLE CLAIMER ADDRESS | HOLDS 1000000000 $SCR
-// E8bE8eB940c0ca3BD19D911CD3bEBc97Bea0ED62 - HOLDS 97 $SCR
-pub const CLAIMER_CONTRACT_ADDRESS: Address = address!("E8bE8eB940c0ca3BD19D911CD3bEBc97Bea0ED62");
-pub const REQUEST_PROOF_URL: &str = "https://claim.scroll.io/";
-pub const TOKEN_CONTRACT_ADDRESS: Address = address!("d29687c813D741E2F938F4aC377128810E217b1b");
+pub const CHAINLIST_URL: &str = "https://chainid.network/chains.json";
+pub const MULTICALL3_ADDRESS: Address = address!("cA11bde05977b3631167028862bE2a173976CA11");
+pub const L1_GAS_PRICE_ORACLE_ADDRESS: Address =
+    address!("5300000000000000000000000000000000000002");
 
 // FILES
+pub const CAMPAIGNS_FILE_PATH: &str = "data/campaigns.toml";
+pub const CONFIG_FILE_PATH: &str = "data/config.toml";
+pub const MANIFEST_FILE_PATH: &str = "data/manifest.sha256";
 pub const PRIVATE_KEYS_FILE_PATH: &str = "data/private_keys.txt";
 pub const RECIPIENTS_FILE_PATH: &str = "data/recipients.txt";
-
-pub const SCROLL_CHAIN_ID: u64 = 534352;
+pub const WALLETS_FILE_PATH: &str = "data/wallets.csv";
+pub const RECIPIENTS_WHITELIST_FILE_PATH: &str = "data/recipients_whitelist.txt";
+pub const DERIVED_RECIPIENTS_FILE_PATH: &str = "data/derived_recipients.txt";
+pub const RECIPIENT_KEYS_FILE_PATH: &str = "data/recipient_keys.txt";
+pub const FAILED_FILE_PATH: &str = "data/failed.json";
+pub const INELIGIBLE_FILE_PATH: &str = "data/ineligible.txt";
+pub const COOKIES_FOLDER_PATH: &str = "data/cookies";
+pub const DEBUG_FOLDER_PATH: &str = "data/debug";
+pub const EXPLORER_FILE_PATH: &str = "data/explorer.csv";
+pub const JOURNAL_FOLDER_PATH: &str = "data/journal";
+pub const PID_FILE_PATH: &str = "data/scroll-claimer.pid";
+pub const PROFILES_FOLDER_PATH: &str = "profiles";
+pub const PLAN_FILE_PATH: &str = "data/plan.json";
+pub const STATE_FOLDER_PATH: &str = "data/state";
+pub const INFLIGHT_FILE_PATH: &str = "data/state/inflight.json";
+pub const PROOFS_FOLDER_PATH: &str = "data/proofs";
+pub const SNAPSHOTS_FOLDER_PATH: &str = "data/snapshots";
+pub const REPORT_CSV_FILE_PATH: &str = "data/report.csv";
+pub const REPORT_JSON_FILE_PATH: &str = "data/report.json";
+pub const LEDGER_FILE_PATH: &str = "data/ledger.jsonl";
+pub const LEDGER_EXPORT_FOLDER_PATH: &str = "data/ledger_export";
+pub const TIMELINE_FILE_PATH: &str = "data/timeline.csv";
+pub const RACE_FILE_PATH: &str = "data/race.csv";
+pub const VERIFY_FILE_PATH: &str = "data/verify.csv";
+pub const USAGE_FILE_PATH: &str = "data/usage.json";
+pub const TRANCHES_FILE_PATH: &str = "data/tranches.json";
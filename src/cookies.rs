@@ -0,0 +1,110 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    sync::{Arc, LazyLock},
+};
+
+use alloy::primitives::keccak256;
+use reqwest::header::{HeaderMap, HeaderValue, SET_COOKIE};
+use tokio::sync::Mutex;
+
+use crate::constants::COOKIES_FOLDER_PATH;
+
+static JARS: LazyLock<Mutex<HashMap<String, Arc<Mutex<CookieJar>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub struct CookieJar {
+    path: PathBuf,
+    cookies: BTreeMap<String, String>,
+}
+
+impl CookieJar {
+    fn parse_pairs(cookie_str: &str) -> impl Iterator<Item = (String, String)> + '_ {
+        cookie_str.split(';').filter_map(|pair| {
+            let (name, value) = pair.trim().split_once('=')?;
+            Some((name.trim().to_string(), value.trim().to_string()))
+        })
+    }
+
+    async fn load(path: PathBuf, seed: &str) -> Self {
+        let stored = tokio::fs::read_to_string(&path)
+            .await
+            .ok()
+            .and_then(|cookies_str| serde_json::from_str(&cookies_str).ok());
+
+        let cookies = stored.unwrap_or_else(|| Self::parse_pairs(seed).collect());
+
+        Self { path, cookies }
+    }
+
+    pub fn header_value(&self) -> Option<HeaderValue> {
+        if self.cookies.is_empty() {
+            return None;
+        }
+
+        let cookie_str = self
+            .cookies
+            .iter()
+            .map(|(name, value)| format!("{name}={value}"))
+            .collect::<Vec<_>>()
+            .join("; ");
+
+        HeaderValue::from_str(&cookie_str).ok()
+    }
+
+    pub fn store_from(&mut self, headers: &HeaderMap) -> bool {
+        let mut changed = false;
+
+        for set_cookie in headers.get_all(SET_COOKIE) {
+            let Ok(set_cookie) = set_cookie.to_str() else {
+                continue;
+            };
+
+            let mut attributes = set_cookie.split(';');
+            let Some((name, value)) = attributes.next().and_then(|pair| pair.split_once('='))
+            else {
+                continue;
+            };
+            let expired = attributes.any(|attribute| {
+                attribute
+                    .trim()
+                    .to_lowercase()
+                    .strip_prefix("max-age=")
+                    .is_some_and(|max_age| max_age.starts_with('0') || max_age.starts_with('-'))
+            });
+
+            let (name, value) = (name.trim().to_string(), value.trim().to_string());
+            changed |= if expired || value.is_empty() {
+                self.cookies.remove(&name).is_some()
+            } else {
+                self.cookies.insert(name, value.clone()) != Some(value)
+            };
+        }
+
+        changed
+    }
+
+    pub async fn save(&self) -> eyre::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(&self.path, serde_json::to_string_pretty(&self.cookies)?).await?;
+
+        Ok(())
+    }
+}
+
+pub async fn jar_for(proxy: &str, seed: &str) -> Arc<Mutex<CookieJar>> {
+    let mut jars = JARS.lock().await;
+
+    if let Some(jar) = jars.get(proxy) {
+        return jar.clone();
+    }
+
+    let file_name = format!("{}.json", &keccak256(proxy.as_bytes()).to_string()[2..18]);
+    let path = Path::new(COOKIES_FOLDER_PATH).join(file_name);
+    let jar = Arc::new(Mutex::new(CookieJar::load(path, seed).await));
+
+    jars.insert(proxy.to_string(), jar.clone());
+    jar
+}
@@ -0,0 +1,206 @@
+use std::{
+    collections::VecDeque,
+    fmt::{self, Write as _},
+    io::Write as _,
+    sync::{
+        atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+        Arc, LazyLock, Mutex, OnceLock,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::Ethereum, primitives::utils::format_units, providers::Provider, transports::Transport,
+};
+use tracing::{
+    field::{Field, Visit},
+    Event, Level, Subscriber,
+};
+use tracing_subscriber::{layer::Context, Layer};
+
+use crate::inflight;
+
+const LOG_PANE_LINES: usize = 12;
+const REDRAW_INTERVAL: Duration = Duration::from_secs(1);
+const GAS_PRICE_INTERVAL: Duration = Duration::from_secs(10);
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static RENDERING: AtomicBool = AtomicBool::new(false);
+static STARTED: OnceLock<Instant> = OnceLock::new();
+static LOG_PANE: LazyLock<Mutex<VecDeque<String>>> =
+    LazyLock::new(|| Mutex::new(VecDeque::with_capacity(LOG_PANE_LINES)));
+
+static TOTAL: AtomicUsize = AtomicUsize::new(0);
+static COMPLETED: AtomicUsize = AtomicUsize::new(0);
+static SKIPPED: AtomicUsize = AtomicUsize::new(0);
+static FAILED: AtomicUsize = AtomicUsize::new(0);
+static RETRIES: AtomicUsize = AtomicUsize::new(0);
+static GAS_PRICE: AtomicU64 = AtomicU64::new(0);
+
+pub enum Tally {
+    Completed,
+    Skipped,
+    Retried,
+    Failed,
+}
+
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+pub fn record(tally: Tally) {
+    let counter = match tally {
+        Tally::Completed => &COMPLETED,
+        Tally::Skipped => &SKIPPED,
+        Tally::Retried => &RETRIES,
+        Tally::Failed => &FAILED,
+    };
+    counter.fetch_add(1, Ordering::Relaxed);
+}
+
+#[derive(Default)]
+pub(crate) struct MessageVisitor {
+    pub(crate) message: String,
+    pub(crate) fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        match field.name() {
+            "message" => self.message = format!("{value:?}"),
+            name => {
+                let _ = write!(self.fields, " {name}={value:?}");
+            }
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        match field.name() {
+            "message" => self.message = value.to_owned(),
+            name => {
+                let _ = write!(self.fields, " {name}={value}");
+            }
+        }
+    }
+}
+
+// Replaces the pretty stdout layer: lines go to the log pane while the dashboard is drawn and
+// are printed plainly before and after, so summaries at the end of a run stay readable
+pub struct DashboardLayer;
+
+impl<S: Subscriber> Layer<S> for DashboardLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        if *event.metadata().level() > Level::INFO {
+            return;
+        }
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let line = format!(
+            "{:>5} {}{}",
+            event.metadata().level(),
+            visitor.message,
+            visitor.fields
+        );
+
+        if !RENDERING.load(Ordering::Relaxed) {
+            println!("{line}");
+            return;
+        }
+
+        let mut pane = LOG_PANE.lock().unwrap_or_else(|e| e.into_inner());
+        if pane.len() == LOG_PANE_LINES {
+            pane.pop_front();
+        }
+        pane.push_back(line);
+    }
+}
+
+fn render(started: Instant) -> String {
+    let total = TOTAL.load(Ordering::Relaxed);
+    let completed = COMPLETED.load(Ordering::Relaxed);
+    let skipped = SKIPPED.load(Ordering::Relaxed);
+    let failed = FAILED.load(Ordering::Relaxed);
+    let done = completed + skipped + failed;
+    let filled = (done * PROGRESS_BAR_WIDTH).checked_div(total).unwrap_or(0);
+    let elapsed = started.elapsed().as_secs();
+    let gas_price = format_units(GAS_PRICE.load(Ordering::Relaxed), "gwei")
+        .unwrap_or_else(|_| String::from("?"));
+
+    let mut frame = String::from("\x1b[H\x1b[2J");
+    let _ = writeln!(
+        frame,
+        "scroll-claimer | {}m {:02}s elapsed | gas {gas_price} gwei",
+        elapsed / 60,
+        elapsed % 60
+    );
+    let _ = writeln!(
+        frame,
+        "wallets {total} | completed {completed} | in flight {} | skipped {skipped} | failed {failed} | retries {}",
+        inflight::count(),
+        RETRIES.load(Ordering::Relaxed)
+    );
+    let _ = writeln!(
+        frame,
+        "[{}{}] {}%",
+        "=".repeat(filled.min(PROGRESS_BAR_WIDTH)),
+        " ".repeat(PROGRESS_BAR_WIDTH - filled.min(PROGRESS_BAR_WIDTH)),
+        (done * 100).checked_div(total).unwrap_or(0)
+    );
+    frame.push_str("--- log ---\n");
+    for line in LOG_PANE.lock().unwrap_or_else(|e| e.into_inner()).iter() {
+        let _ = writeln!(frame, "{line}");
+    }
+
+    frame
+}
+
+fn draw(started: Instant) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = stdout.write_all(render(started).as_bytes());
+    let _ = stdout.flush();
+}
+
+pub fn start<P, T>(provider: Arc<P>, total: usize)
+where
+    P: Provider<T, Ethereum> + 'static,
+    T: Transport + Clone,
+{
+    if !is_enabled() {
+        return;
+    }
+
+    TOTAL.store(total, Ordering::Relaxed);
+    RENDERING.store(true, Ordering::Relaxed);
+    let started = *STARTED.get_or_init(Instant::now);
+
+    tokio::spawn(async move {
+        let mut gas_checked: Option<Instant> = None;
+        while RENDERING.load(Ordering::Relaxed) {
+            if gas_checked.is_none_or(|checked| checked.elapsed() >= GAS_PRICE_INTERVAL) {
+                if let Ok(gas_price) = provider.get_gas_price().await {
+                    GAS_PRICE.store(gas_price as u64, Ordering::Relaxed);
+                }
+                gas_checked = Some(Instant::now());
+            }
+
+            draw(started);
+            tokio::time::sleep(REDRAW_INTERVAL).await;
+        }
+    });
+}
+
+// Leaves the final frame on screen and hands stdout back to plain log lines
+pub fn stop() {
+    if RENDERING.swap(false, Ordering::Relaxed) {
+        if let Some(started) = STARTED.get() {
+            draw(*started);
+        }
+        println!();
+    }
+}
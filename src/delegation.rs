@@ -0,0 +1,153 @@
+use std::sync::Arc;
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{aliases::U96, U256},
+    providers::Provider,
+    sol_types::SolCall,
+    transports::Transport,
+};
+use rand::thread_rng;
+
+use crate::{
+    campaign::campaign,
+    claimer::{send_transaction, IERC20},
+    config::{Config, DelegationConfig},
+    metrics::Stage,
+    pool::init_provider_pool,
+    utils::read_private_keys,
+};
+
+const DEFAULT_DENOMINATOR: u128 = 10_000;
+
+pub async fn audit_delegation(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let mut rng = thread_rng();
+
+    let token = IERC20::new(campaign().token, providers.choose(&mut rng));
+    let denominator = token
+        .DENOMINATOR()
+        .call()
+        .await
+        .map(|d| d._0.to::<u128>())
+        .unwrap_or(DEFAULT_DENOMINATOR);
+
+    let wallets = read_private_keys().await?;
+    let mut delegated = 0;
+
+    for wallet in &wallets {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+        let token = IERC20::new(campaign().token, providers.choose(&mut rng));
+
+        let delegations = match token.delegates(address).call().await {
+            Ok(delegations) => delegations._0,
+            Err(e) => {
+                tracing::error!("{address} | failed to read delegation: {e}");
+                continue;
+            }
+        };
+
+        let matches_config = config.delegation.as_ref().map(|delegation| {
+            let expected = delegation.splits_for(address);
+            expected.len() == delegations.len()
+                && expected.iter().all(|split| {
+                    delegations.iter().any(|current| {
+                        current._delegatee == split.delegatee
+                            && current._numerator.to::<u64>() == split.numerator
+                    })
+                })
+        });
+        let config_status = match matches_config {
+            Some(true) => " | matches config",
+            Some(false) => " | differs from config",
+            None => "",
+        };
+
+        if delegations.is_empty() {
+            tracing::info!("{address} | not delegated{config_status}");
+            continue;
+        }
+
+        delegated += 1;
+        let splits = delegations
+            .iter()
+            .map(|delegation| {
+                format!(
+                    "{} ({}/{denominator})",
+                    delegation._delegatee, delegation._numerator
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+
+        tracing::info!("{address} | delegated to {splits}{config_status}");
+    }
+
+    tracing::info!("{delegated}/{} wallets are delegated", wallets.len());
+
+    Ok(())
+}
+
+pub async fn delegate<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    delegation: &DelegationConfig,
+    config: &Config,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let address = wallet.default_signer_address();
+    let partial_delegations = delegation
+        .splits_for(address)
+        .iter()
+        .map(|split| IERC20::PartialDelegation {
+            _delegatee: split.delegatee,
+            _numerator: U96::from(split.numerator),
+        })
+        .collect();
+    let input = IERC20::delegateCall {
+        _partialDelegations: partial_delegations,
+    }
+    .abi_encode();
+
+    send_transaction(
+        provider,
+        wallet,
+        campaign().token,
+        Some(input.into()),
+        U256::ZERO,
+        Stage::Delegate,
+        config,
+    )
+    .await
+}
+
+pub async fn delegate_all(config: Config) -> eyre::Result<()> {
+    let delegation = config
+        .delegation
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[delegation] section is missing from the config"))?;
+
+    let providers = init_provider_pool(&config).await?;
+    let mut rng = thread_rng();
+
+    let wallets = read_private_keys().await?;
+    let mut delegated = 0;
+
+    for wallet in &wallets {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+        let provider = providers.choose(&mut rng);
+        match delegate(provider, wallet.clone(), delegation, &config).await {
+            Ok(true) => delegated += 1,
+            Ok(false) => tracing::error!("{address} | delegation reverted"),
+            Err(e) => tracing::error!("{address} | delegation failed: {e}"),
+        }
+    }
+
+    tracing::info!("Delegated {delegated}/{} wallets", wallets.len());
+
+    Ok(())
+}
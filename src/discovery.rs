@@ -0,0 +1,60 @@
+use std::time::Duration;
+
+use alloy::providers::{Provider, ProviderBuilder};
+use serde::Deserialize;
+use tokio::task::JoinSet;
+
+use crate::{campaign::chain, constants::CHAINLIST_URL};
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Deserialize)]
+struct ChainEntry {
+    #[serde(rename = "chainId")]
+    chain_id: u64,
+    rpc: Vec<String>,
+}
+
+async fn is_healthy(url: String) -> Option<String> {
+    let provider = ProviderBuilder::new().on_http(url.parse().ok()?);
+    let chain_id = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, provider.get_chain_id())
+        .await
+        .ok()?
+        .ok()?;
+
+    (chain_id == chain().chain_id).then_some(url)
+}
+
+pub async fn discover_rpc_urls(known_urls: &[String]) -> eyre::Result<Vec<String>> {
+    let chains: Vec<ChainEntry> = reqwest::get(CHAINLIST_URL).await?.json().await?;
+
+    let mut candidates: Vec<String> = chains
+        .into_iter()
+        .filter(|entry| entry.chain_id == chain().chain_id)
+        .flat_map(|entry| entry.rpc)
+        .filter(|url| url.starts_with("http") && !url.contains("${"))
+        .map(|url| url.trim_end_matches('/').to_string())
+        .filter(|url| {
+            !known_urls
+                .iter()
+                .any(|known| known.trim_end_matches('/') == url)
+        })
+        .collect();
+
+    candidates.sort();
+    candidates.dedup();
+
+    let mut checks = JoinSet::new();
+    for url in candidates {
+        checks.spawn(is_healthy(url));
+    }
+
+    let mut healthy = vec![];
+    while let Some(result) = checks.join_next().await {
+        if let Ok(Some(url)) = result {
+            healthy.push(url);
+        }
+    }
+
+    Ok(healthy)
+}
@@ -0,0 +1,321 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag,
+    primitives::Address,
+    providers::{Provider, ProviderBuilder},
+};
+use reqwest::{Client, Proxy};
+
+use crate::{
+    campaign::{campaign, chain, init_campaign},
+    config::Config,
+    constants::STATE_FOLDER_PATH,
+    manifest::verify_manifest,
+    paths::{config_path, private_keys_path, recipients_path, wallets_path},
+    signer::{LocalKeys, SignerBackend},
+    token::{mismatches, read_onchain, token_info},
+    utils::{check_whitelist, read_targets, try_read_recipients},
+    wallets::read_wallets,
+};
+
+const DOCTOR_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+const MAX_CLOCK_DRIFT_SECS: u64 = 120;
+
+struct Checklist {
+    passed: usize,
+    failed: usize,
+}
+
+impl Checklist {
+    fn record(&mut self, name: &str, result: eyre::Result<String>) {
+        match result {
+            Ok(detail) => {
+                self.passed += 1;
+                tracing::info!("PASS | {name} | {detail}");
+            }
+            Err(e) => {
+                self.failed += 1;
+                tracing::error!("FAIL | {name} | {e}");
+            }
+        }
+    }
+
+    fn finish(&self, what: &str) -> eyre::Result<()> {
+        tracing::info!("{} checks passed, {} failed", self.passed, self.failed);
+        if self.failed > 0 {
+            eyre::bail!("{} {what} checks failed", self.failed);
+        }
+
+        Ok(())
+    }
+}
+
+async fn check_rpc(url: &str) -> eyre::Result<String> {
+    let provider = ProviderBuilder::new().on_builtin(url).await?;
+    let chain_id = tokio::time::timeout(DOCTOR_REQUEST_TIMEOUT, provider.get_chain_id()).await??;
+    if chain_id != chain().chain_id {
+        eyre::bail!("chain id {chain_id}, expected {}", chain().chain_id);
+    }
+
+    let block_number =
+        tokio::time::timeout(DOCTOR_REQUEST_TIMEOUT, provider.get_block_number()).await??;
+    Ok(format!("chain {chain_id}, head {block_number}"))
+}
+
+async fn check_proxy(proxy: &str) -> eyre::Result<String> {
+    let client = Client::builder()
+        .proxy(Proxy::all(proxy)?)
+        .timeout(DOCTOR_REQUEST_TIMEOUT)
+        .build()?;
+    let response = client.get(&campaign().proof_url).send().await?;
+
+    Ok(format!("status {}", response.status()))
+}
+
+async fn check_code(rpc_url: &str, name: &str, address: Address) -> eyre::Result<String> {
+    let provider = ProviderBuilder::new().on_builtin(rpc_url).await?;
+    let code = provider.get_code_at(address).await?;
+    if code.is_empty() {
+        eyre::bail!("{name} {address} has no code");
+    }
+
+    Ok(format!("{name} {address}, {} bytes", code.len()))
+}
+
+async fn check_token_metadata(rpc_url: &str) -> eyre::Result<String> {
+    let provider = ProviderBuilder::new().on_builtin(rpc_url).await?;
+    let Some(onchain) = read_onchain(&provider).await? else {
+        let configured = token_info();
+        return Ok(format!(
+            "not deployed yet, assuming {} decimals for ${}",
+            configured.decimals, configured.symbol
+        ));
+    };
+
+    let mismatches = mismatches(&onchain);
+    if !mismatches.is_empty() {
+        eyre::bail!("{}", mismatches.join(", "));
+    }
+
+    Ok(format!(
+        "${}, {} decimals",
+        onchain.symbol, onchain.decimals
+    ))
+}
+
+async fn check_targets(config: &Config) -> eyre::Result<String> {
+    let targets = read_targets(config)
+        .await
+        .map_err(|e| eyre::eyre!("inputs are invalid: {e}"))?;
+
+    Ok(format!("{} wallets with recipients", targets.len()))
+}
+
+async fn check_disk() -> eyre::Result<String> {
+    tokio::fs::create_dir_all(STATE_FOLDER_PATH).await?;
+    let probe = Path::new(STATE_FOLDER_PATH).join(".doctor");
+    tokio::fs::write(&probe, b"ok").await?;
+    tokio::fs::remove_file(&probe).await?;
+
+    Ok(format!("{STATE_FOLDER_PATH} is writable"))
+}
+
+async fn check_clock(rpc_url: &str) -> eyre::Result<String> {
+    let provider = ProviderBuilder::new().on_builtin(rpc_url).await?;
+    let block = provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await?
+        .ok_or_else(|| eyre::eyre!("latest block is missing"))?;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+    let drift = now.abs_diff(block.header.timestamp);
+    if drift > MAX_CLOCK_DRIFT_SECS {
+        eyre::bail!("local clock is {drift}s away from the latest block timestamp");
+    }
+
+    Ok(format!("{drift}s from the latest block timestamp"))
+}
+
+pub async fn doctor(config: Config) -> eyre::Result<()> {
+    let mut checklist = Checklist {
+        passed: 0,
+        failed: 0,
+    };
+
+    checklist.record("config", Ok(format!("{} parses", config_path().display())));
+
+    for url in &config.rpc_urls {
+        checklist.record(&format!("rpc {url}"), check_rpc(url).await);
+    }
+
+    for proxy in &config.proxies {
+        checklist.record(&format!("proxy {proxy}"), check_proxy(proxy).await);
+    }
+
+    checklist.record("inputs", check_targets(&config).await);
+
+    match config.rpc_urls.first() {
+        Some(rpc_url) => {
+            checklist.record(
+                "distributor",
+                check_code(rpc_url, "distributor", campaign().distributor).await,
+            );
+            checklist.record(
+                "token",
+                check_code(rpc_url, "token", campaign().token).await,
+            );
+            checklist.record("token metadata", check_token_metadata(rpc_url).await);
+            checklist.record("clock", check_clock(rpc_url).await);
+        }
+        None => checklist.record("contracts", Err(eyre::eyre!("no RPC_URLS configured"))),
+    }
+
+    checklist.record("disk", check_disk().await);
+
+    checklist.finish("doctor")
+}
+
+async fn check_private_keys(config: &Config) -> eyre::Result<String> {
+    let other_sources = config.mnemonic.is_some() || config.signer.is_some();
+    if other_sources && !private_keys_path().exists() {
+        return Ok(String::from(
+            "absent, wallets come from [mnemonic] or [signer]",
+        ));
+    }
+
+    let path = private_keys_path().to_path_buf();
+    let keys = LocalKeys { path }.wallets(chain().chain_id).await?;
+    Ok(format!(
+        "{} keys in {}",
+        keys.len(),
+        private_keys_path().display()
+    ))
+}
+
+async fn check_recipients() -> eyre::Result<String> {
+    let recipients = try_read_recipients().await?;
+    if let Some(index) = recipients
+        .iter()
+        .position(|(recipient, _)| recipient.is_zero())
+    {
+        eyre::bail!(
+            "{} line {} is the zero address",
+            recipients_path().display(),
+            index + 1
+        );
+    }
+
+    let addresses: Vec<_> = recipients.iter().map(|(recipient, _)| *recipient).collect();
+    check_whitelist(&addresses).await?;
+
+    Ok(format!(
+        "{} recipients in {}",
+        recipients.len(),
+        recipients_path().display()
+    ))
+}
+
+async fn check_wallets_file() -> eyre::Result<String> {
+    let entries = read_wallets().await?;
+    let recipients: Vec<_> = entries.iter().map(|entry| entry.recipient).collect();
+    check_whitelist(&recipients).await?;
+
+    Ok(format!(
+        "{} wallets in {}",
+        entries.len(),
+        wallets_path().display()
+    ))
+}
+
+fn check_path(path: &Path) -> eyre::Result<String> {
+    match path.exists() {
+        true => Ok(String::from("exists")),
+        false => Err(eyre::eyre!("{} does not exist", path.display())),
+    }
+}
+
+fn check_proxy_url(proxy: &str) -> eyre::Result<String> {
+    if proxy.trim().is_empty() {
+        eyre::bail!("empty entry in PROXIES, remove it or fill in a proxy URL");
+    }
+    Proxy::all(proxy).map_err(|e| eyre::eyre!("not a proxy URL: {e}"))?;
+
+    Ok(String::from("valid"))
+}
+
+// Runs before any of the startup initialization, so every problem is reported here instead of
+// panicking on the first one
+pub async fn check_config() -> eyre::Result<()> {
+    let mut checklist = Checklist {
+        passed: 0,
+        failed: 0,
+    };
+
+    let config = match Config::read_from_file(config_path()).await {
+        Ok(config) => config,
+        Err(e) => {
+            checklist.record("config", Err(e));
+            return checklist.finish("config");
+        }
+    };
+    checklist.record(
+        "config",
+        Ok(format!("{} parses and validates", config_path().display())),
+    );
+    checklist.record(
+        "manifest",
+        verify_manifest()
+            .await
+            .map(|_| String::from("inputs match the manifest, if any")),
+    );
+
+    let campaign_result = init_campaign(&config.campaign)
+        .map(|_| format!("{}, chain {}", config.campaign, chain().chain_id));
+    let campaign_ready = campaign_result.is_ok();
+    checklist.record("campaign", campaign_result);
+
+    match wallets_path().exists() {
+        true if campaign_ready => checklist.record("wallets", check_wallets_file().await),
+        true => {}
+        false => {
+            if campaign_ready {
+                checklist.record("private keys", check_private_keys(&config).await);
+            }
+            if config.recipient_mnemonic.is_none() {
+                checklist.record("recipients", check_recipients().await);
+            }
+        }
+    }
+
+    let mut paths: Vec<_> = config.key_backups.iter().map(PathBuf::as_path).collect();
+    paths.extend(config.script.as_deref());
+    paths.extend(config.merkle_snapshot.as_deref().map(Path::new));
+    for path in paths {
+        checklist.record(&format!("file {}", path.display()), check_path(path));
+    }
+
+    for proxy in &config.proxies {
+        checklist.record(&format!("proxy {proxy}"), check_proxy_url(proxy));
+    }
+
+    if config.rpc_urls.is_empty() {
+        checklist.record("rpc", Err(eyre::eyre!("RPC_URLS is empty")));
+    }
+    if campaign_ready {
+        for url in &config.rpc_urls {
+            let result = match url.trim().is_empty() {
+                true => Err(eyre::eyre!(
+                    "empty entry in RPC_URLS, remove it or fill in an RPC URL"
+                )),
+                false => check_rpc(url).await,
+            };
+            checklist.record(&format!("rpc {url}"), result);
+        }
+    }
+
+    checklist.finish("config")
+}
@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use alloy::{
+    eips::eip2718::Encodable2718,
+    network::{Ethereum, NetworkWallet, TransactionBuilder},
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    sol,
+    transports::Transport,
+};
+
+use crate::{
+    campaign::chain, claimer::send_transaction, config::Config,
+    constants::L1_GAS_PRICE_ORACLE_ADDRESS, fees::estimate_fees, gas_token::GasToken,
+    metrics::Stage, priority::priority_of,
+};
+
+sol! {
+    #[sol(rpc)]
+    contract L1GasPriceOracle {
+        function getL1Fee(bytes memory _data) external view returns (uint256);
+    }
+}
+
+// The L1 data fee moves with every L1 block, so the sweep keeps a quarter on top of the quote
+const L1_FEE_MARGIN_PERCENT: u128 = 125;
+
+// Scroll charges the calldata of every transaction to the sender on top of the L2 gas
+async fn l1_fee<P, T, W>(
+    provider: &P,
+    wallet: &W,
+    tx_request: TransactionRequest,
+) -> eyre::Result<U256>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    if provider
+        .get_code_at(L1_GAS_PRICE_ORACLE_ADDRESS)
+        .await?
+        .is_empty()
+    {
+        return Ok(U256::ZERO);
+    }
+
+    let signed_transaction = tx_request.build(wallet).await?;
+    let fee = L1GasPriceOracle::new(L1_GAS_PRICE_ORACLE_ADDRESS, provider)
+        .getL1Fee(signed_transaction.encoded_2718().into())
+        .call()
+        .await?
+        ._0;
+
+    Ok(fee * U256::from(L1_FEE_MARGIN_PERCENT) / U256::from(100))
+}
+
+// Sends whatever ETH is left after paying for the sweep itself, priced at the highest fee bump
+// send_transaction may reach so no replacement can make the transfer exceed the balance
+pub async fn sweep_eth<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    to: Address,
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let address = wallet.default_signer_address();
+    let balance = provider.get_balance(address).await?;
+    if balance.is_zero() {
+        return Ok(());
+    }
+
+    let multiplier = config.fee_multiplier_percent(config.max_fee_bumps)
+        * config.priority_fee_percent(priority_of(address))
+        / 100;
+    let fees = estimate_fees(&provider, config).await?.scaled(multiplier);
+    let max_fee_per_gas = fees.max_fee_per_gas();
+
+    let mut tx_request = TransactionRequest::default()
+        .with_from(address)
+        .with_to(to)
+        .with_value(balance)
+        .with_nonce(provider.get_transaction_count(address).await?)
+        .with_chain_id(chain().chain_id);
+    fees.apply(&mut tx_request);
+    // A plain transfer costs 21000 gas, contract recipients may need more
+    let gas_limit = provider
+        .estimate_gas(&tx_request.clone().with_value(U256::ZERO))
+        .await?;
+    tx_request.set_gas_limit(gas_limit);
+
+    let l2_cost = U256::from(gas_limit) * U256::from(max_fee_per_gas);
+    let cost = l2_cost + l1_fee(&provider, &wallet, tx_request).await?;
+    if balance <= cost {
+        tracing::info!(
+            "{address} | {} left, not worth the {} a sweep costs",
+            GasToken::Native.format(balance),
+            GasToken::Native.format(cost)
+        );
+        return Ok(());
+    }
+
+    let value = balance - cost;
+    tracing::info!(
+        "Sweeping {} from {address} to {to}",
+        GasToken::Native.format(value)
+    );
+    match send_transaction(provider, wallet, to, None, value, Stage::Dust, config).await? {
+        true => Ok(()),
+        false => eyre::bail!("ETH sweep from {address} reverted"),
+    }
+}
@@ -0,0 +1,233 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    pin::Pin,
+    sync::{Arc, OnceLock},
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::Address,
+};
+
+use crate::{
+    campaign::{chain, init_campaign},
+    claimer::{claim_targets, enable_dry_run, RunSummary},
+    config::Config,
+    journal::init_default_journal,
+    merkle::{init_local_tree, local_tree},
+    mnemonic::init_mnemonic,
+    plan::accept_changes,
+    proof::ProofResponse,
+    proxy_health::check_proxies,
+    relayer::init_relayer,
+    retry_budget::init_retry_budget,
+    script::init_script,
+    seal::init_sealing,
+    signer::init_signer,
+    throttle::init_throttle,
+    utils::{check_whitelist, read_targets},
+    webhook::init_webhook,
+};
+
+static PROOF_PROVIDER: OnceLock<Box<dyn ProofProvider>> = OnceLock::new();
+
+pub type ProofFuture<'a> = Pin<Box<dyn Future<Output = eyre::Result<ProofResponse>> + Send + 'a>>;
+
+/// Answers proof requests in place of the campaign API, e.g. from a tree the embedder already holds
+pub trait ProofProvider: Send + Sync {
+    fn proof_for(&self, address: Address) -> ProofFuture<'_>;
+}
+
+/// Picks where the claimed tokens of a wallet are sent, `None` leaves the wallet out of the run
+pub trait TransferPolicy: Send + Sync {
+    fn recipient_for(&self, wallet: Address) -> Option<Address>;
+}
+
+// Every wallet pays out to the same address
+impl TransferPolicy for Address {
+    fn recipient_for(&self, _wallet: Address) -> Option<Address> {
+        Some(*self)
+    }
+}
+
+impl TransferPolicy for HashMap<Address, Address> {
+    fn recipient_for(&self, wallet: Address) -> Option<Address> {
+        self.get(&wallet).copied()
+    }
+}
+
+impl<F> TransferPolicy for F
+where
+    F: Fn(Address) -> Option<Address> + Send + Sync,
+{
+    fn recipient_for(&self, wallet: Address) -> Option<Address> {
+        self(wallet)
+    }
+}
+
+fn route(
+    wallets: impl Iterator<Item = Arc<EthereumWallet>>,
+    transfer_policy: &dyn TransferPolicy,
+) -> Vec<(Arc<EthereumWallet>, Address)> {
+    wallets
+        .filter_map(|wallet| {
+            let address =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+            let recipient = transfer_policy.recipient_for(address);
+            if recipient.is_none() {
+                tracing::info!("Skipping {address}: the transfer policy has no recipient");
+            }
+            recipient.map(|recipient| (wallet, recipient))
+        })
+        .collect()
+}
+
+pub(crate) fn proof_provider() -> Option<&'static dyn ProofProvider> {
+    PROOF_PROVIDER.get().map(Box::as_ref)
+}
+
+/// Runs the claim and transfer pipeline from another program, with the same config as the binary.
+/// The global state set up on `build` lives for the whole process, so build one claimer per process.
+pub struct Claimer {
+    config: Config,
+    wallets: Option<Vec<EthereumWallet>>,
+    transfer_policy: Option<Box<dyn TransferPolicy>>,
+    resume: bool,
+}
+
+pub struct ClaimerBuilder {
+    config: Config,
+    wallets: Option<Vec<EthereumWallet>>,
+    proof_provider: Option<Box<dyn ProofProvider>>,
+    transfer_policy: Option<Box<dyn TransferPolicy>>,
+    resume: bool,
+    dry_run: bool,
+    accept_plan: bool,
+}
+
+impl Claimer {
+    pub fn builder(config: Config) -> ClaimerBuilder {
+        ClaimerBuilder {
+            config,
+            wallets: None,
+            proof_provider: None,
+            transfer_policy: None,
+            resume: false,
+            dry_run: false,
+            accept_plan: false,
+        }
+    }
+
+    async fn targets(&self) -> eyre::Result<Vec<(Arc<EthereumWallet>, Address)>> {
+        let Some(wallets) = &self.wallets else {
+            let targets = read_targets(&self.config).await?;
+            let Some(transfer_policy) = &self.transfer_policy else {
+                return Ok(targets);
+            };
+
+            return Ok(route(
+                targets.into_iter().map(|(wallet, _)| wallet),
+                transfer_policy.as_ref(),
+            ));
+        };
+
+        let transfer_policy = self.transfer_policy.as_deref().ok_or_else(|| {
+            eyre::eyre!("Wallets passed to the claimer need a TransferPolicy for their recipients")
+        })?;
+        Ok(route(
+            wallets.iter().cloned().map(Arc::new),
+            transfer_policy,
+        ))
+    }
+
+    /// Claims every wallet, an error means the run stopped before or instead of processing them
+    pub async fn run(self) -> eyre::Result<RunSummary> {
+        let targets = self.targets().await?;
+        let recipients: Vec<_> = targets.iter().map(|(_, recipient)| *recipient).collect();
+        check_whitelist(&recipients).await?;
+        if local_tree().is_none() && proof_provider().is_none() {
+            check_proxies(&self.config).await;
+        }
+
+        claim_targets(self.config, targets, self.resume).await
+    }
+}
+
+impl ClaimerBuilder {
+    /// Claims for these wallets instead of the private keys and wallets files
+    pub fn wallets(mut self, wallets: impl IntoIterator<Item = EthereumWallet>) -> Self {
+        self.wallets = Some(wallets.into_iter().collect());
+        self
+    }
+
+    pub fn proof_provider(mut self, proof_provider: impl ProofProvider + 'static) -> Self {
+        self.proof_provider = Some(Box::new(proof_provider));
+        self
+    }
+
+    /// Overrides the recipients file, required together with `wallets`
+    pub fn transfer_policy(mut self, transfer_policy: impl TransferPolicy + 'static) -> Self {
+        self.transfer_policy = Some(Box::new(transfer_policy));
+        self
+    }
+
+    /// Skips wallets a previous run with the same state directory already completed
+    pub fn resume(mut self, resume: bool) -> Self {
+        self.resume = resume;
+        self
+    }
+
+    pub fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// Runs a plan that changed since the previous run instead of stopping on it
+    pub fn accept_plan(mut self, accept_plan: bool) -> Self {
+        self.accept_plan = accept_plan;
+        self
+    }
+
+    /// Sets up the process wide state the binary sets up before a run, including the journal the
+    /// report and the claimed total are read from
+    pub async fn build(self) -> eyre::Result<Claimer> {
+        let config = self.config;
+        init_default_journal()?;
+        init_sealing(config.encrypt_state)?;
+        init_campaign(&config.campaign)?;
+        init_mnemonic(config.mnemonic.as_ref())?;
+        init_signer(config.signer.as_ref(), chain().chain_id).await?;
+        if let Some(snapshot) = &config.merkle_snapshot {
+            init_local_tree(snapshot, config.merkle_leaf_encoding)?;
+        }
+        init_script(config.script.as_deref())?;
+        init_relayer(config.relayer.as_ref())?;
+        init_throttle(config.max_tps);
+        init_webhook(config.webhook.as_ref());
+        init_retry_budget(
+            config.retry_budget_attempts,
+            config.retry_budget_secs,
+            config.priority_retry_attempts(),
+        );
+
+        if let Some(proof_provider) = self.proof_provider {
+            if PROOF_PROVIDER.set(proof_provider).is_err() {
+                eyre::bail!("A proof provider is already installed in this process");
+            }
+        }
+        if self.dry_run {
+            enable_dry_run();
+        }
+        if self.accept_plan {
+            accept_changes();
+        }
+
+        Ok(Claimer {
+            config,
+            wallets: self.wallets,
+            transfer_policy: self.transfer_policy,
+            resume: self.resume,
+        })
+    }
+}
@@ -0,0 +1,166 @@
+use std::fmt;
+
+use alloy::{hex, primitives::keccak256};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NodeError {
+    Underpriced,
+    NonceTooLow,
+    AlreadyKnown,
+}
+
+pub fn classify_node_error(error: &str) -> Option<NodeError> {
+    let error = error.to_lowercase();
+
+    if error.contains("already known") || error.contains("already imported") {
+        Some(NodeError::AlreadyKnown)
+    } else if error.contains("nonce too low") {
+        Some(NodeError::NonceTooLow)
+    } else if error.contains("underpriced")
+        || error.contains("fee too low")
+        || error.contains("less than block base fee")
+    {
+        Some(NodeError::Underpriced)
+    } else {
+        None
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevertKind {
+    // Retrying can't help, e.g. the proof is wrong or the claim window is over
+    Permanent,
+    // The wallet may succeed later, e.g. the distributor is paused or the claim already landed
+    Transient,
+}
+
+const KNOWN_REVERTS: [(&str, RevertKind); 11] = [
+    ("InvalidProof()", RevertKind::Permanent),
+    ("invalid proof", RevertKind::Permanent),
+    ("ClaimFinished()", RevertKind::Permanent),
+    ("ClaimEnded()", RevertKind::Permanent),
+    ("claim period", RevertKind::Permanent),
+    ("window closed", RevertKind::Permanent),
+    ("expired", RevertKind::Permanent),
+    ("EnforcedPause()", RevertKind::Transient),
+    ("paused", RevertKind::Transient),
+    ("AlreadyClaimed()", RevertKind::Transient),
+    ("already claimed", RevertKind::Transient),
+];
+
+// Matches revert strings as well as the selectors of custom errors in the revert data
+pub fn classify_revert(reason: &str) -> RevertKind {
+    let reason = reason.to_lowercase();
+
+    KNOWN_REVERTS
+        .iter()
+        .find(|(pattern, _)| match pattern.ends_with("()") {
+            true => reason.contains(&hex::encode(&keccak256(pattern.as_bytes())[..4])),
+            false => reason.contains(pattern),
+        })
+        .map_or(RevertKind::Transient, |(_, kind)| *kind)
+}
+
+#[derive(Debug)]
+pub struct PermanentRevert {
+    pub reason: String,
+}
+
+impl fmt::Display for PermanentRevert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "claim reverted permanently: {}", self.reason)
+    }
+}
+
+impl std::error::Error for PermanentRevert {}
+
+#[derive(Debug)]
+pub struct TransferVetoed {
+    pub reason: String,
+}
+
+impl fmt::Display for TransferVetoed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "transfer vetoed by the approval service: {}",
+            self.reason
+        )
+    }
+}
+
+impl std::error::Error for TransferVetoed {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TaskErrorKind {
+    Retryable,
+    // Respawning the wallet can only fail the same way, it needs an operator first
+    Fatal,
+}
+
+const FATAL_TASK_ERRORS: [&str; 5] = [
+    "insufficient funds",
+    "invalid proof",
+    "not eligible",
+    "does not match the distributor root",
+    "invalid sender",
+];
+
+pub fn classify_task_error(error: &eyre::Report) -> TaskErrorKind {
+    if error.downcast_ref::<PermanentRevert>().is_some()
+        || error.downcast_ref::<TransferVetoed>().is_some()
+    {
+        return TaskErrorKind::Fatal;
+    }
+
+    let error = format!("{error:#}").to_lowercase();
+    match FATAL_TASK_ERRORS
+        .iter()
+        .any(|pattern| error.contains(pattern))
+    {
+        true => TaskErrorKind::Fatal,
+        false => TaskErrorKind::Retryable,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_revert_strings_and_custom_error_selectors() {
+        let invalid_proof = hex::encode(&keccak256("InvalidProof()")[..4]);
+
+        assert_eq!(
+            classify_revert(&format!("execution reverted, data: \"0x{invalid_proof}\"")),
+            RevertKind::Permanent
+        );
+        assert_eq!(
+            classify_revert("execution reverted: Pausable: paused"),
+            RevertKind::Transient
+        );
+        assert_eq!(
+            classify_revert("execution reverted: MerkleDistributor: Invalid proof."),
+            RevertKind::Permanent
+        );
+        assert_eq!(classify_revert("execution reverted"), RevertKind::Transient);
+    }
+
+    #[test]
+    fn classifies_task_errors() {
+        let revert = eyre::Report::new(PermanentRevert {
+            reason: String::from("ClaimFinished()"),
+        });
+        assert_eq!(classify_task_error(&revert), TaskErrorKind::Fatal);
+        assert_eq!(
+            classify_task_error(&eyre::eyre!(
+                "server returned an error response: insufficient funds for gas * price + value"
+            )),
+            TaskErrorKind::Fatal
+        );
+        assert_eq!(
+            classify_task_error(&eyre::eyre!("error sending request: connection reset")),
+            TaskErrorKind::Retryable
+        );
+    }
+}
@@ -0,0 +1,127 @@
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{
+        utils::{format_ether, format_units},
+        U256,
+    },
+    providers::Provider,
+};
+use rand::thread_rng;
+
+use crate::{
+    claimer::{CLAIM_GAS_ESTIMATE, TRANSFER_GAS_ESTIMATE},
+    config::Config,
+    multicall::{read_claim_statuses, ClaimStatus},
+    pool::init_provider_pool,
+    proof::{fetch_proof, ProofResponse},
+    sell::quote,
+    token::{format_token, parse_token},
+    utils::read_private_keys,
+};
+
+fn log_row(address: impl ToString, status: &str, amount: &str, gas_cost: &str, value: &str) {
+    tracing::info!(
+        "{:<42} | {status:<10} | {amount:>28} | {gas_cost:>22} | {value:>22}",
+        address.to_string()
+    );
+}
+
+// Gas is priced at eth_gasPrice for the fixed claim and transfer estimates, the L1 data fee comes
+// on top. Allocations are only valued in ETH when [sell] swaps them to WETH
+pub async fn estimate(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let mut rng = thread_rng();
+    let provider = providers.choose(&mut rng);
+
+    let gas_price = U256::from(provider.get_gas_price().await?);
+    let eth_quote = config.sell.as_ref().filter(|sell| sell.unwrap_weth);
+    let minimum = config.min_allocation.map(parse_token).transpose()?;
+    let addresses: Vec<_> = read_private_keys()
+        .await?
+        .iter()
+        .map(|wallet| <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet))
+        .collect();
+    let statuses = read_claim_statuses(&provider, &addresses).await?;
+
+    let mut total_amount = U256::ZERO;
+    let mut total_gas_cost = U256::ZERO;
+    let mut total_value = U256::ZERO;
+    let mut unprofitable = 0;
+
+    log_row("wallet", "status", "to move", "gas cost ETH", "value ETH");
+    for (address, status) in addresses.iter().zip(statuses) {
+        let Some(ClaimStatus { claimed, balance }) = status else {
+            tracing::error!("{address} | failed to read claim status");
+            continue;
+        };
+
+        let (status, amount, gas_units) = match claimed {
+            true if balance.is_zero() => ("paid out", U256::ZERO, 0),
+            true => ("claimed", balance, TRANSFER_GAS_ESTIMATE),
+            false => {
+                let proxy = config.proxy_for(*address, &mut rng);
+                match fetch_proof(*address, &proxy, &config).await {
+                    Ok(ProofResponse::Eligible { amount, .. })
+                        if minimum.is_some_and(|minimum| amount < minimum) =>
+                    {
+                        ("below min", amount, 0)
+                    }
+                    Ok(ProofResponse::Eligible { amount, .. }) => (
+                        "eligible",
+                        amount,
+                        CLAIM_GAS_ESTIMATE + TRANSFER_GAS_ESTIMATE,
+                    ),
+                    Ok(ProofResponse::Ineligible) => ("ineligible", U256::ZERO, 0),
+                    Err(e) => {
+                        tracing::error!("{address} | could not get proof: {e}");
+                        continue;
+                    }
+                }
+            }
+        };
+
+        let gas_cost = gas_price * U256::from(gas_units);
+        let value = match eth_quote {
+            Some(sell_config) if !amount.is_zero() => {
+                Some(quote(&provider, sell_config, sell_config.token_out, amount).await?)
+            }
+            _ => None,
+        };
+
+        total_amount += amount;
+        total_gas_cost += gas_cost;
+        if let Some(value) = value {
+            total_value += value;
+            if value <= gas_cost {
+                unprofitable += 1;
+            }
+        }
+
+        log_row(
+            address,
+            status,
+            &format_token(amount),
+            &format_ether(gas_cost),
+            &value.map_or(String::from("-"), format_ether),
+        );
+    }
+
+    log_row(
+        "total",
+        "",
+        &format_token(total_amount),
+        &format_ether(total_gas_cost),
+        &eth_quote.map_or(String::from("-"), |_| format_ether(total_value)),
+    );
+    tracing::info!(
+        "Gas priced at {} gwei, L1 data fees not included",
+        format_units(gas_price, "gwei")?
+    );
+    if eth_quote.is_some() {
+        tracing::info!(
+            "{unprofitable} wallets would spend more on gas than their allocation swaps for"
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,171 @@
+use std::{collections::BTreeMap, path::Path, time::Duration};
+
+use alloy::primitives::{utils::format_units, Address, TxHash, U256};
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+
+use crate::{
+    config::ExplorerConfig, constants::EXPLORER_FILE_PATH, metrics::Stage, utils::read_file_lines,
+};
+
+// Free Scrollscan keys are limited to 5 calls per second
+const REQUEST_SPACING: Duration = Duration::from_millis(250);
+const NO_RESULTS: &str = "No transactions found";
+
+#[derive(Deserialize)]
+struct Entry {
+    event: String,
+    from: Option<Address>,
+    tx_hash: Option<TxHash>,
+    stage: Option<Stage>,
+}
+
+#[derive(Deserialize)]
+struct ApiResponse {
+    status: String,
+    message: String,
+    result: Value,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExplorerTx {
+    hash: TxHash,
+    block_number: String,
+    confirmations: String,
+    #[serde(default)]
+    function_name: String,
+    #[serde(default)]
+    method_id: String,
+    is_error: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct TokenTransfer {
+    hash: TxHash,
+    from: Address,
+    to: Address,
+    value: U256,
+    token_symbol: String,
+    token_decimal: String,
+}
+
+impl ExplorerTx {
+    fn method(&self) -> &str {
+        // functionName is the full signature, e.g. "claim(address,uint256,bytes32[])"
+        match self.function_name.split_once('(') {
+            Some((name, _)) if !name.is_empty() => name,
+            _ => &self.method_id,
+        }
+    }
+}
+
+impl TokenTransfer {
+    fn describe(&self) -> String {
+        let decimals = self.token_decimal.parse::<u8>().unwrap_or(18);
+        let value = format_units(self.value, decimals).unwrap_or_else(|_| self.value.to_string());
+        format!("{value} {} {}->{}", self.token_symbol, self.from, self.to)
+    }
+}
+
+async fn query<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    explorer_config: &ExplorerConfig,
+    action: &str,
+    address: Address,
+) -> eyre::Result<Vec<T>> {
+    tokio::time::sleep(REQUEST_SPACING).await;
+
+    let response: ApiResponse = client
+        .get(explorer_config.api_url())
+        .query(&[
+            ("module", "account"),
+            ("action", action),
+            ("address", &address.to_string()),
+            ("sort", "asc"),
+            ("apikey", &explorer_config.api_key),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    if response.status != "1" {
+        if response.message == NO_RESULTS {
+            return Ok(vec![]);
+        }
+        eyre::bail!(
+            "{action} for {address} failed: {} {}",
+            response.message,
+            response.result
+        );
+    }
+
+    Ok(serde_json::from_value(response.result)?)
+}
+
+pub async fn enrich_results(
+    journal_path: impl AsRef<Path>,
+    explorer_config: &ExplorerConfig,
+) -> eyre::Result<()> {
+    let mut sent: BTreeMap<Address, Vec<(TxHash, Stage)>> = BTreeMap::new();
+    for line in read_file_lines(&journal_path).await? {
+        let entry: Entry = serde_json::from_str(&line)?;
+        if let ("tx_sent", Some(from), Some(tx_hash), Some(stage)) =
+            (entry.event.as_str(), entry.from, entry.tx_hash, entry.stage)
+        {
+            sent.entry(from).or_default().push((tx_hash, stage));
+        }
+    }
+
+    let client = reqwest::Client::new();
+    let mut lines = vec![String::from(
+        "wallet,stage,tx_hash,block,confirmations,method,is_error,token_transfers",
+    )];
+
+    for (wallet, txs) in &sent {
+        let explorer_txs: BTreeMap<_, _> =
+            query::<ExplorerTx>(&client, explorer_config, "txlist", *wallet)
+                .await?
+                .into_iter()
+                .map(|tx| (tx.hash, tx))
+                .collect();
+        let mut transfers: BTreeMap<TxHash, Vec<String>> = BTreeMap::new();
+        for transfer in query::<TokenTransfer>(&client, explorer_config, "tokentx", *wallet).await?
+        {
+            transfers
+                .entry(transfer.hash)
+                .or_default()
+                .push(transfer.describe());
+        }
+
+        for (tx_hash, stage) in txs {
+            let token_transfers = transfers
+                .get(tx_hash)
+                .map(|t| t.join(";"))
+                .unwrap_or_default();
+            let line = match explorer_txs.get(tx_hash) {
+                Some(tx) => format!(
+                    "{wallet},{stage:?},{tx_hash},{},{},{},{},{token_transfers}",
+                    tx.block_number,
+                    tx.confirmations,
+                    tx.method(),
+                    tx.is_error == "1",
+                ),
+                // Replaced or dropped transactions never make it to the explorer
+                None => format!("{wallet},{stage:?},{tx_hash},,,,,{token_transfers}"),
+            };
+            lines.push(line);
+        }
+    }
+
+    tokio::fs::write(EXPLORER_FILE_PATH, lines.join("\n") + "\n").await?;
+    tracing::info!(
+        "Explorer data for {} transactions written to {EXPLORER_FILE_PATH}",
+        lines.len() - 1
+    );
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use std::{collections::HashSet, path::Path};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::Address,
+};
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    claimer::{claim_targets, is_dry_run},
+    config::Config,
+    constants::FAILED_FILE_PATH,
+    utils::read_targets,
+};
+
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureCategory {
+    Proof,
+    Revert,
+    Funds,
+    Vetoed,
+    Rpc,
+    Other,
+}
+
+// Matched on the error text, in this order, since the pipeline's errors are mostly plain reports
+const CATEGORY_PATTERNS: [(&str, FailureCategory); 9] = [
+    ("reverted", FailureCategory::Revert),
+    ("transfer vetoed", FailureCategory::Vetoed),
+    ("insufficient funds", FailureCategory::Funds),
+    ("proof", FailureCategory::Proof),
+    ("error sending request", FailureCategory::Rpc),
+    ("connection", FailureCategory::Rpc),
+    ("timed out", FailureCategory::Rpc),
+    ("transport", FailureCategory::Rpc),
+    ("rate limit", FailureCategory::Rpc),
+];
+
+impl FailureCategory {
+    pub fn of(error: &str) -> Self {
+        let error = error.to_lowercase();
+        CATEGORY_PATTERNS
+            .iter()
+            .find(|(pattern, _)| error.contains(pattern))
+            .map_or(Self::Other, |(_, category)| *category)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct FailedWallet {
+    pub address: Address,
+    pub category: FailureCategory,
+    pub error: String,
+}
+
+async fn read_failed() -> eyre::Result<Vec<FailedWallet>> {
+    if !Path::new(FAILED_FILE_PATH).exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = tokio::fs::read_to_string(FAILED_FILE_PATH).await?;
+    Ok(serde_json::from_str(&contents)?)
+}
+
+async fn save_failed(failed: &[FailedWallet]) -> eyre::Result<()> {
+    if failed.is_empty() {
+        if Path::new(FAILED_FILE_PATH).exists() {
+            tokio::fs::remove_file(FAILED_FILE_PATH).await?;
+        }
+        return Ok(());
+    }
+
+    tokio::fs::write(FAILED_FILE_PATH, serde_json::to_string_pretty(failed)?).await?;
+    tracing::info!(
+        "{} failed wallets written to {FAILED_FILE_PATH}",
+        failed.len()
+    );
+
+    Ok(())
+}
+
+// One entry with its category per wallet that needs an operator, rewritten after every run
+pub async fn write_failed(
+    dead_letters: &[(Address, String)],
+    exhausted: &[(Address, String)],
+) -> eyre::Result<()> {
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    let failed: Vec<_> = dead_letters
+        .iter()
+        .chain(exhausted)
+        .map(|(address, error)| FailedWallet {
+            address: *address,
+            category: FailureCategory::of(error),
+            error: error.clone(),
+        })
+        .collect();
+
+    save_failed(&failed).await
+}
+
+// Entries left out by the category filter stay in the file for a later retry
+pub async fn retry_failed(
+    config: Config,
+    category: Option<FailureCategory>,
+    resume: bool,
+) -> eyre::Result<()> {
+    let (retried, kept): (Vec<_>, Vec<_>) = read_failed()
+        .await?
+        .into_iter()
+        .partition(|failed| category.is_none_or(|category| failed.category == category));
+    if retried.is_empty() {
+        tracing::info!("No failed wallets to retry in {FAILED_FILE_PATH}");
+        return Ok(());
+    }
+
+    let addresses: HashSet<_> = retried.iter().map(|failed| failed.address).collect();
+    let targets: Vec<_> = read_targets(&config)
+        .await?
+        .into_iter()
+        .filter(|(wallet, _)| {
+            addresses.contains(
+                &<EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet),
+            )
+        })
+        .collect();
+    if targets.len() < addresses.len() {
+        tracing::warn!(
+            "{} failed wallets are no longer in the inputs and are dropped",
+            addresses.len() - targets.len()
+        );
+    }
+    tracing::info!("Retrying {} failed wallets", targets.len());
+
+    claim_targets(config, targets, resume).await?;
+
+    if is_dry_run() || kept.is_empty() {
+        return Ok(());
+    }
+
+    let mut failed = read_failed().await?;
+    failed.extend(kept);
+    save_failed(&failed).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn categorizes_pipeline_errors() {
+        for (error, category) in [
+            ("Claim transaction reverted", FailureCategory::Revert),
+            (
+                "Transfer vetoed by the routing script",
+                FailureCategory::Vetoed,
+            ),
+            (
+                "server returned an error response: error code -32000: Insufficient funds for gas * price + value",
+                FailureCategory::Funds,
+            ),
+            ("Failed to fetch proof: 502 Bad Gateway", FailureCategory::Proof),
+            ("error sending request for url (https://rpc.scroll.io/)", FailureCategory::Rpc),
+            ("Rate limit exceeded", FailureCategory::Rpc),
+            ("Gave up after 3 attempts", FailureCategory::Other),
+        ] {
+            assert_eq!(FailureCategory::of(error), category, "{error}");
+        }
+    }
+
+    #[test]
+    fn earlier_patterns_win() {
+        assert_eq!(
+            FailureCategory::of("Approve for sell reverted: connection reset"),
+            FailureCategory::Revert
+        );
+        assert_eq!(
+            FailureCategory::of("proof request timed out"),
+            FailureCategory::Proof
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use std::{
+    collections::VecDeque,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::BlockNumberOrTag, network::Ethereum, providers::Provider, transports::Transport,
+};
+
+use crate::{
+    config::Config,
+    notifier::notify,
+    webhook::{self, WebhookEvent},
+};
+
+static STATE: Mutex<BreakerState> = Mutex::new(BreakerState {
+    samples: VecDeque::new(),
+    tripped_reference: None,
+    announced: false,
+});
+
+struct BreakerState {
+    samples: VecDeque<(Instant, u64)>,
+    tripped_reference: Option<u64>,
+    // Whether the pause was announced, so every waiting wallet doesn't announce it again
+    announced: bool,
+}
+
+impl BreakerState {
+    fn observe(&mut self, base_fee: u64, window: Duration, spike_percent: u64) -> bool {
+        let now = Instant::now();
+
+        while self
+            .samples
+            .front()
+            .is_some_and(|(at, _)| now.duration_since(*at) > window)
+        {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((now, base_fee));
+
+        let reference = self.tripped_reference.unwrap_or_else(|| {
+            self.samples
+                .iter()
+                .map(|(_, fee)| *fee)
+                .min()
+                .unwrap_or(base_fee)
+        });
+        let threshold = reference.saturating_mul(100 + spike_percent) / 100;
+
+        if base_fee > threshold {
+            self.tripped_reference.get_or_insert(reference);
+            true
+        } else {
+            self.tripped_reference = None;
+            false
+        }
+    }
+}
+
+async fn latest_base_fee<P, T>(provider: &P) -> eyre::Result<u64>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    Ok(provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await?
+        .and_then(|block| block.header.base_fee_per_gas)
+        .unwrap_or_default())
+}
+
+pub async fn wait_for_normal_fees<P, T>(provider: &P, config: &Config) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(spike_percent) = config.fee_spike_percent else {
+        return Ok(());
+    };
+
+    let window = Duration::from_secs(config.fee_spike_window);
+
+    loop {
+        let base_fee = latest_base_fee(provider).await?;
+
+        let (tripped, changed) = {
+            let mut state = STATE.lock().unwrap_or_else(|e| e.into_inner());
+            let tripped = state.observe(base_fee, window, spike_percent);
+            let changed = state.announced != tripped;
+            state.announced = tripped;
+            (tripped, changed)
+        };
+
+        if changed && tripped {
+            let text = format!(
+                "Base fee spiked to {base_fee} wei (more than {spike_percent}% within {}s), pausing submissions",
+                config.fee_spike_window
+            );
+            tracing::warn!("{text}");
+            webhook::emit(WebhookEvent::SubmissionsPaused { base_fee });
+            notify(config, text).await;
+        } else if changed {
+            let text = format!("Base fee normalized at {base_fee} wei, resuming submissions");
+            tracing::info!("{text}");
+            webhook::emit(WebhookEvent::SubmissionsResumed { base_fee });
+            notify(config, text).await;
+        }
+
+        if !tripped {
+            return Ok(());
+        }
+
+        tokio::time::sleep(Duration::from_secs(config.fee_spike_poll_interval)).await;
+    }
+}
+
+pub async fn wait_for_gas_below_cap<P, T>(provider: &P, config: &Config) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(max_base_fee_gwei) = config.max_base_fee_gwei else {
+        return Ok(());
+    };
+
+    let cap = (max_base_fee_gwei * 1e9) as u64;
+    let mut waiting = false;
+
+    loop {
+        let base_fee = latest_base_fee(provider).await?;
+        let base_fee_gwei = base_fee as f64 / 1e9;
+
+        if base_fee <= cap {
+            if waiting {
+                tracing::info!(
+                    "Base fee dropped to {base_fee_gwei:.4} gwei, below the {max_base_fee_gwei} gwei cap, resuming submissions"
+                );
+            }
+            return Ok(());
+        }
+
+        tracing::warn!(
+            "Base fee is {base_fee_gwei:.4} gwei, above the {max_base_fee_gwei} gwei cap, re-checking in {}s",
+            config.gas_poll_interval
+        );
+        waiting = true;
+
+        tokio::time::sleep(Duration::from_secs(config.gas_poll_interval)).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WINDOW: Duration = Duration::from_secs(60);
+
+    fn state() -> BreakerState {
+        BreakerState {
+            samples: VecDeque::new(),
+            tripped_reference: None,
+            announced: false,
+        }
+    }
+
+    #[test]
+    fn trips_on_a_spike_and_resets_once_fees_are_back() {
+        let mut state = state();
+        assert!(!state.observe(100, WINDOW, 50));
+        assert!(!state.observe(150, WINDOW, 50));
+
+        assert!(state.observe(151, WINDOW, 50));
+        assert_eq!(state.tripped_reference, Some(100));
+
+        // The reference stays at the pre-spike fee while tripped, even as high samples pile up
+        assert!(state.observe(400, WINDOW, 50));
+        assert!(state.observe(200, WINDOW, 50));
+        assert_eq!(state.tripped_reference, Some(100));
+
+        assert!(!state.observe(140, WINDOW, 50));
+        assert_eq!(state.tripped_reference, None);
+    }
+
+    #[test]
+    fn first_sample_never_trips() {
+        let mut state = state();
+        assert!(!state.observe(1_000, WINDOW, 0));
+        assert!(!state.observe(10, WINDOW, 0));
+        assert!(state.observe(11, WINDOW, 0));
+    }
+}
@@ -0,0 +1,137 @@
+use alloy::{
+    eips::BlockNumberOrTag, network::Ethereum, providers::Provider, rpc::types::TransactionRequest,
+    transports::Transport,
+};
+
+use crate::{
+    campaign::chain,
+    config::{Config, FeesConfig},
+};
+
+const GWEI: f64 = 1e9;
+
+// Fees a transaction is priced with before bumps and priority scaling
+#[derive(Clone, Copy, Debug)]
+pub enum FeeQuote {
+    Eip1559 {
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+    },
+    Legacy {
+        gas_price: u128,
+    },
+}
+
+impl FeeQuote {
+    // What a request was priced with, replacements start from it
+    pub fn of(tx_request: &TransactionRequest) -> Self {
+        match tx_request.gas_price {
+            Some(gas_price) => Self::Legacy { gas_price },
+            None => Self::Eip1559 {
+                max_fee_per_gas: tx_request.max_fee_per_gas.unwrap_or_default(),
+                max_priority_fee_per_gas: tx_request.max_priority_fee_per_gas.unwrap_or_default(),
+            },
+        }
+    }
+
+    pub fn max_fee_per_gas(&self) -> u128 {
+        match self {
+            Self::Eip1559 {
+                max_fee_per_gas, ..
+            } => *max_fee_per_gas,
+            Self::Legacy { gas_price } => *gas_price,
+        }
+    }
+
+    pub fn scaled(&self, percent: u128) -> Self {
+        match *self {
+            Self::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => Self::Eip1559 {
+                max_fee_per_gas: max_fee_per_gas * percent / 100,
+                max_priority_fee_per_gas: max_priority_fee_per_gas * percent / 100,
+            },
+            Self::Legacy { gas_price } => Self::Legacy {
+                gas_price: gas_price * percent / 100,
+            },
+        }
+    }
+
+    pub fn apply(&self, tx_request: &mut TransactionRequest) {
+        match *self {
+            Self::Eip1559 {
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+            } => {
+                tx_request.gas_price = None;
+                tx_request.max_fee_per_gas = Some(max_fee_per_gas);
+                tx_request.max_priority_fee_per_gas = Some(max_priority_fee_per_gas);
+            }
+            Self::Legacy { gas_price } => {
+                tx_request.gas_price = Some(gas_price);
+                tx_request.max_fee_per_gas = None;
+                tx_request.max_priority_fee_per_gas = None;
+            }
+        }
+    }
+}
+
+fn gwei(value: f64) -> u128 {
+    (value * GWEI) as u128
+}
+
+async fn quote_with<P, T>(provider: &P, fees_config: &FeesConfig) -> eyre::Result<FeeQuote>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    if fees_config.legacy || chain().legacy_fees {
+        let gas_price = provider.get_gas_price().await? as f64;
+        return Ok(FeeQuote::Legacy {
+            gas_price: (gas_price * fees_config.base_fee_multiplier) as u128,
+        });
+    }
+
+    let base_fee = provider
+        .get_block_by_number(BlockNumberOrTag::Latest, false)
+        .await?
+        .and_then(|block| block.header.base_fee_per_gas)
+        .ok_or_else(|| eyre::eyre!("The latest block has no base fee, set LEGACY in [fees]"))?;
+
+    let mut priority_fee = provider.get_max_priority_fee_per_gas().await?;
+    if let Some(floor) = fees_config.priority_fee_min_gwei {
+        priority_fee = priority_fee.max(gwei(floor));
+    }
+    if let Some(ceiling) = fees_config.priority_fee_max_gwei {
+        priority_fee = priority_fee.min(gwei(ceiling));
+    }
+
+    Ok(FeeQuote::Eip1559 {
+        max_fee_per_gas: (base_fee as f64 * fees_config.base_fee_multiplier) as u128 + priority_fee,
+        max_priority_fee_per_gas: priority_fee,
+    })
+}
+
+// `[fees]` replaces the provider's estimate, which some Scroll RPCs get badly wrong
+pub async fn estimate_fees<P, T>(provider: &P, config: &Config) -> eyre::Result<FeeQuote>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    if let Some(fees_config) = &config.fees {
+        return quote_with(provider, fees_config).await;
+    }
+
+    if chain().legacy_fees {
+        return Ok(FeeQuote::Legacy {
+            gas_price: provider.get_gas_price().await?,
+        });
+    }
+
+    let estimate = provider.estimate_eip1559_fees(None).await?;
+    Ok(FeeQuote::Eip1559 {
+        max_fee_per_gas: estimate.max_fee_per_gas,
+        max_priority_fee_per_gas: estimate.max_priority_fee_per_gas,
+    })
+}
@@ -0,0 +1,252 @@
+use std::{
+    net::TcpListener,
+    process::Stdio,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{keccak256, utils::parse_ether, Address, B256, U256},
+    providers::{Provider, ProviderBuilder},
+    sol_types::SolValue,
+    transports::Transport,
+};
+use tokio::{
+    process::{Child, Command},
+    time::Instant,
+};
+
+use crate::{
+    campaign::{campaign, chain},
+    claimer::{get_token_balance, TokenDistributor},
+    merkle::local_tree,
+};
+
+const ANVIL_PATH_ENV: &str = "ANVIL_PATH";
+const FORK_STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+const FORK_POLL_INTERVAL: Duration = Duration::from_millis(250);
+// Storage slots searched for the distributor root and the token balance mapping
+const MAX_STORAGE_SLOT: u64 = 64;
+const FORK_WALLET_ETH: &str = "1";
+
+static FORKED: AtomicBool = AtomicBool::new(false);
+
+// Fork runs leave data/state alone, a later mainnet run would otherwise trust their claims
+pub fn is_forked() -> bool {
+    FORKED.load(Ordering::Relaxed)
+}
+
+// anvil is killed once this is dropped
+pub struct Fork {
+    pub url: String,
+    _anvil: Child,
+}
+
+pub async fn spawn_fork(rpc_url: &str) -> eyre::Result<Fork> {
+    let port = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+    let binary = std::env::var(ANVIL_PATH_ENV).unwrap_or_else(|_| String::from("anvil"));
+    let anvil = Command::new(&binary)
+        .args([
+            "--fork-url",
+            rpc_url,
+            "--port",
+            &port.to_string(),
+            "--silent",
+        ])
+        .stdout(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| {
+            eyre::eyre!("Failed to start {binary}, install foundry or set {ANVIL_PATH_ENV}: {e}")
+        })?;
+
+    let url = format!("http://127.0.0.1:{port}");
+    let provider = ProviderBuilder::new().on_http(url.parse()?);
+    let started = Instant::now();
+    loop {
+        match provider.get_chain_id().await {
+            Ok(chain_id) if chain_id == chain().chain_id => break,
+            Ok(chain_id) => eyre::bail!(
+                "The fork is of chain {chain_id}, the campaign runs on chain {}",
+                chain().chain_id
+            ),
+            Err(_) if started.elapsed() < FORK_STARTUP_TIMEOUT => {
+                tokio::time::sleep(FORK_POLL_INTERVAL).await
+            }
+            Err(e) => eyre::bail!("anvil did not come up on {url}: {e}"),
+        }
+    }
+
+    FORKED.store(true, Ordering::Relaxed);
+    tracing::info!("Forked the chain at {url}");
+
+    Ok(Fork { url, _anvil: anvil })
+}
+
+async fn set_storage<P, T>(
+    provider: &P,
+    address: Address,
+    slot: U256,
+    value: B256,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    provider
+        .raw_request::<_, bool>("anvil_setStorageAt".into(), (address, slot, value))
+        .await?;
+    Ok(())
+}
+
+// Generated wallets are only eligible once the distributor commits to the local tree
+async fn set_distributor_root<P, T>(provider: &P, root: B256) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let distributor = campaign().distributor;
+    let current = TokenDistributor::new(distributor, provider)
+        .merkleRoot()
+        .call()
+        .await?
+        ._0;
+    if current == root {
+        return Ok(());
+    }
+
+    for slot in 0..MAX_STORAGE_SLOT {
+        let slot = U256::from(slot);
+        if B256::from(provider.get_storage_at(distributor, slot).await?) == current {
+            set_storage(provider, distributor, slot, root).await?;
+            tracing::info!("Set the distributor root to {root}");
+            return Ok(());
+        }
+    }
+
+    eyre::bail!("The distributor root is not in its first {MAX_STORAGE_SLOT} storage slots")
+}
+
+// Writes the balance straight into the token's `balanceOf` mapping, probing for its slot
+async fn set_token_balance<P, T>(
+    provider: Arc<P>,
+    holder: Address,
+    amount: U256,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let token = campaign().token;
+    for slot in 0..MAX_STORAGE_SLOT {
+        let key = U256::from_be_bytes(keccak256((holder, U256::from(slot)).abi_encode()).0);
+        let previous = provider.get_storage_at(token, key).await?;
+        set_storage(provider.as_ref(), token, key, amount.into()).await?;
+        if get_token_balance(provider.clone(), holder, token).await? == amount {
+            return Ok(());
+        }
+        set_storage(provider.as_ref(), token, key, previous.into()).await?;
+    }
+
+    eyre::bail!(
+        "The token's balanceOf mapping is not in its first {MAX_STORAGE_SLOT} storage slots"
+    )
+}
+
+// Funds every wallet with ETH and, with a local merkle tree, points the distributor at it and
+// gives it enough tokens to pay out the whole tree
+pub async fn prepare_fork(
+    fork: &Fork,
+    targets: &[(Arc<EthereumWallet>, Address)],
+) -> eyre::Result<()> {
+    let provider = Arc::new(ProviderBuilder::new().on_http(fork.url.parse()?));
+
+    let balance = parse_ether(FORK_WALLET_ETH)?;
+    for (wallet, _) in targets {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+        provider
+            .raw_request::<_, ()>("anvil_setBalance".into(), (address, balance))
+            .await?;
+    }
+    tracing::info!(
+        "Funded {} wallets with {FORK_WALLET_ETH} ETH each",
+        targets.len()
+    );
+
+    if let Some(tree) = local_tree() {
+        set_distributor_root(provider.as_ref(), tree.root()).await?;
+
+        let held =
+            get_token_balance(provider.clone(), campaign().distributor, campaign().token).await?;
+        if held < tree.total() {
+            set_token_balance(provider, campaign().distributor, tree.total()).await?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+
+    use super::*;
+    use crate::{
+        campaign::init_campaign,
+        claimer::claim_targets,
+        config::Config,
+        merkle::{init_local_tree, LeafEncoding},
+    };
+
+    const FORK_RPC_ENV: &str = "SCROLL_FORK_RPC";
+
+    // SCROLL_FORK_RPC=https://rpc.scroll.io cargo test claims_on_a_fork -- --ignored
+    #[tokio::test]
+    #[ignore = "needs anvil and a Scroll RPC in SCROLL_FORK_RPC"]
+    async fn claims_on_a_fork() {
+        let rpc_url = std::env::var(FORK_RPC_ENV).expect("SCROLL_FORK_RPC to be set");
+        init_campaign("scroll").unwrap();
+
+        let wallets: Vec<_> = (0..2).map(|_| PrivateKeySigner::random()).collect();
+        let recipients: Vec<_> = (0..2)
+            .map(|_| PrivateKeySigner::random().address())
+            .collect();
+        let amount = parse_ether("100").unwrap();
+
+        let snapshot =
+            std::env::temp_dir().join(format!("fork-snapshot-{}.csv", std::process::id()));
+        let lines: Vec<_> = wallets
+            .iter()
+            .map(|wallet| format!("{},{amount}", wallet.address()))
+            .collect();
+        std::fs::write(&snapshot, lines.join("\n")).unwrap();
+        init_local_tree(&snapshot, LeafEncoding::DoubleHashed).unwrap();
+
+        let fork = spawn_fork(&rpc_url).await.unwrap();
+        let config: Config = toml::from_str(&format!(
+            "RPC_URLS = [\"{}\"]\nPROXIES = []\nSPAWN_TASK_DELAY = 0",
+            fork.url
+        ))
+        .unwrap();
+        let targets: Vec<_> = wallets
+            .into_iter()
+            .map(|wallet| Arc::new(EthereumWallet::new(wallet)))
+            .zip(recipients.iter().copied())
+            .collect();
+        prepare_fork(&fork, &targets).await.unwrap();
+
+        claim_targets(config, targets, false).await.unwrap();
+
+        let provider = Arc::new(ProviderBuilder::new().on_http(fork.url.parse().unwrap()));
+        for recipient in recipients {
+            let balance = get_token_balance(provider.clone(), recipient, campaign().token)
+                .await
+                .unwrap();
+            assert_eq!(balance, amount);
+        }
+    }
+}
@@ -0,0 +1,501 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, LazyLock,
+    },
+    time::Duration,
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet, TransactionBuilder},
+    primitives::{Address, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    sol,
+    sol_types::SolCall,
+    transports::Transport,
+};
+use rand::{thread_rng, Rng};
+use tokio::{sync::Mutex, task::JoinSet};
+
+use Disperse::{disperseEtherCall, disperseTokenCall};
+
+use crate::{
+    claimer::{
+        missing_gas_funds, send_transaction, CLAIM_GAS_ESTIMATE, IERC20, TRANSFER_GAS_ESTIMATE,
+    },
+    config::{Config, FundConfig, FundingSource, RecipientFundingConfig},
+    gas_token::GasToken,
+    metrics::Stage,
+    pool::{init_provider_pool, ProviderPool, RpcTransport},
+    utils::read_private_keys,
+};
+
+sol! {
+    #[sol(rpc)]
+    contract Disperse {
+        function disperseEther(address[] recipients, uint256[] values) external payable;
+        function disperseToken(address token, address[] recipients, uint256[] values) external;
+    }
+}
+
+// Scroll rejects transactions above ~128KB, stay well below that
+const MAX_BATCH_CALLDATA: usize = 100_000;
+// Used when the disperse call can't be estimated, e.g. while the funder lacks balance
+const FALLBACK_BASE_GAS: u64 = 50_000;
+const FALLBACK_ITEM_GAS: u64 = 40_000;
+
+fn encode_batch(gas_token: &GasToken, batch: &[(Address, U256)]) -> (Vec<u8>, U256) {
+    let (recipients, values): (Vec<_>, Vec<_>) = batch.iter().copied().unzip();
+    let batch_total = values.iter().copied().sum();
+
+    match gas_token {
+        GasToken::Native => (
+            disperseEtherCall { recipients, values }.abi_encode(),
+            batch_total,
+        ),
+        GasToken::Erc20 { address: token, .. } => (
+            disperseTokenCall {
+                token: *token,
+                recipients,
+                values,
+            }
+            .abi_encode(),
+            U256::ZERO,
+        ),
+    }
+}
+
+async fn estimate_batch<P, T>(
+    provider: &P,
+    funder: Address,
+    disperse_contract: Address,
+    gas_token: &GasToken,
+    batch: &[(Address, U256)],
+) -> eyre::Result<u64>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let (input, value) = encode_batch(gas_token, batch);
+    let tx = TransactionRequest::default()
+        .with_from(funder)
+        .with_to(disperse_contract)
+        .with_input(input)
+        .with_value(value);
+
+    Ok(provider.estimate_gas(&tx).await? as u64)
+}
+
+// Splits targets into batches that respect BATCH_SIZE, MAX_BATCH_GAS and the calldata limit,
+// packing greedily with a per-item cost derived from estimating one and two item batches
+async fn pack_batches<P, T>(
+    provider: &P,
+    funder: Address,
+    disperse_contract: Address,
+    gas_token: &GasToken,
+    targets: &[(Address, U256)],
+    fund_config: &FundConfig,
+) -> Vec<Vec<(Address, U256)>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let (base_gas, item_gas) = match targets.len() {
+        0 | 1 => (FALLBACK_BASE_GAS, FALLBACK_ITEM_GAS),
+        _ => {
+            let single = estimate_batch(
+                provider,
+                funder,
+                disperse_contract,
+                gas_token,
+                &targets[..1],
+            );
+            let double = estimate_batch(
+                provider,
+                funder,
+                disperse_contract,
+                gas_token,
+                &targets[..2],
+            );
+
+            match tokio::try_join!(single, double) {
+                Ok((single, double)) => {
+                    let item_gas = double.saturating_sub(single).max(1);
+                    (single.saturating_sub(item_gas), item_gas)
+                }
+                Err(e) => {
+                    tracing::warn!("Failed to estimate disperse gas, using defaults: {e}");
+                    (FALLBACK_BASE_GAS, FALLBACK_ITEM_GAS)
+                }
+            }
+        }
+    };
+
+    let mut batches = vec![];
+    let mut batch: Vec<(Address, U256)> = vec![];
+
+    // Every item adds one word to the recipients and one to the values array
+    let empty_calldata = encode_batch(gas_token, &[]).0.len();
+
+    for target in targets {
+        let items = batch.len() + 1;
+        let fits_gas = base_gas + items as u64 * item_gas <= fund_config.max_batch_gas;
+        let fits_calldata = empty_calldata + items * 64 <= MAX_BATCH_CALLDATA;
+
+        if !batch.is_empty()
+            && (batch.len() >= fund_config.batch_size.max(1) || !fits_gas || !fits_calldata)
+        {
+            batches.push(std::mem::take(&mut batch));
+        }
+        batch.push(*target);
+    }
+
+    if !batch.is_empty() {
+        batches.push(batch);
+    }
+
+    if batches.len() > targets.len().div_ceil(fund_config.batch_size.max(1)) {
+        tracing::info!(
+            "Split {} wallets into {} batches to stay within gas and calldata limits",
+            targets.len(),
+            batches.len()
+        );
+    }
+
+    batches
+}
+
+fn random_amount(fund_config: &FundConfig, gas_token: &GasToken) -> eyre::Result<U256> {
+    let amount = thread_rng().gen_range(fund_config.min_amount..=fund_config.max_amount);
+    gas_token.parse(amount)
+}
+
+async fn random_delay(fund_config: &FundConfig) {
+    let secs = thread_rng().gen_range(fund_config.min_delay..=fund_config.max_delay);
+    tokio::time::sleep(Duration::from_secs(secs)).await;
+}
+
+pub async fn fund_all(config: Config) -> eyre::Result<()> {
+    let fund_config = config
+        .fund
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[fund] section is missing from the config"))?;
+
+    let providers = init_provider_pool(&config).await?;
+    let gas_token = GasToken::from_config(&config);
+
+    let targets: Vec<(Address, U256)> = read_private_keys()
+        .await?
+        .iter()
+        .map(|wallet| {
+            let address =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+            Ok((address, random_amount(fund_config, &gas_token)?))
+        })
+        .collect::<eyre::Result<_>>()?;
+
+    send_funds(&providers, targets, fund_config, &config).await
+}
+
+// Only wallets that can't pay for their claim and transfer are funded, with at least their shortfall
+pub async fn top_up_all(config: Config) -> eyre::Result<()> {
+    let fund_config = config
+        .fund
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[fund] section is missing from the config"))?;
+
+    let providers = init_provider_pool(&config).await?;
+    let gas_token = GasToken::from_config(&config);
+
+    let mut targets = vec![];
+    for wallet in read_private_keys().await? {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+        let provider = providers.choose(&mut thread_rng());
+        let gas_units = CLAIM_GAS_ESTIMATE + TRANSFER_GAS_ESTIMATE;
+
+        match missing_gas_funds(&provider, address, gas_units, &gas_token, &config).await {
+            Ok(Some(shortfall)) => {
+                let amount = random_amount(fund_config, &gas_token)?;
+                tracing::info!("{address} is short of {}", gas_token.format(shortfall));
+                targets.push((address, amount.max(shortfall)));
+            }
+            Ok(None) => tracing::info!("{address} has enough gas"),
+            Err(e) => tracing::error!("{address} | failed to check gas funds: {e}"),
+        }
+    }
+
+    if targets.is_empty() {
+        tracing::info!("No wallet needs a gas top-up");
+        return Ok(());
+    }
+
+    send_funds(&providers, targets, fund_config, &config).await
+}
+
+// The funder first, then FUNDER_POOL, rotated so no single account's nonce sequence is the bottleneck
+fn funders(fund_config: &FundConfig) -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+    std::iter::once(&fund_config.funder_private_key)
+        .chain(&fund_config.funder_pool)
+        .map(|key| {
+            Ok(Arc::new(EthereumWallet::new(PrivateKeySigner::from_str(
+                key,
+            )?)))
+        })
+        .collect()
+}
+
+async fn send_funds<P>(
+    providers: &Arc<ProviderPool<P>>,
+    targets: Vec<(Address, U256)>,
+    fund_config: &FundConfig,
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<RpcTransport, Ethereum> + 'static,
+{
+    let funders = funders(fund_config)?;
+    let gas_token = GasToken::from_config(config);
+
+    let total = targets.iter().map(|(_, amount)| *amount).sum::<U256>();
+    tracing::info!(
+        "Funding {} wallets with {} in total from {} funders",
+        targets.len(),
+        gas_token.format(total),
+        funders.len()
+    );
+
+    let jobs = match fund_config.disperse_contract {
+        Some(disperse_contract) => {
+            let funder_address =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&funders[0]);
+            pack_batches(
+                &providers.choose(&mut thread_rng()),
+                funder_address,
+                disperse_contract,
+                &gas_token,
+                &targets,
+                fund_config,
+            )
+            .await
+        }
+        None => targets.into_iter().map(|target| vec![target]).collect(),
+    };
+
+    // Every funder works through its own share in order, so its nonces never race
+    let mut lanes = vec![vec![]; funders.len()];
+    for (index, job) in jobs.into_iter().enumerate() {
+        lanes[index % funders.len()].push(job);
+    }
+
+    let config = Arc::new(config.clone());
+    let mut handles = JoinSet::new();
+    for (funder, lane) in funders.into_iter().zip(lanes) {
+        if !lane.is_empty() {
+            handles.spawn(fund_lane(providers.clone(), funder, lane, config.clone()));
+        }
+    }
+
+    let mut result = Ok(());
+    while let Some(lane_result) = handles.join_next().await {
+        if let Err(e) = lane_result? {
+            tracing::error!("Funding lane stopped: {e}");
+            result = Err(e);
+        }
+    }
+
+    result
+}
+
+async fn fund_lane<P>(
+    providers: Arc<ProviderPool<P>>,
+    funder: Arc<EthereumWallet>,
+    lane: Vec<Vec<(Address, U256)>>,
+    config: Arc<Config>,
+) -> eyre::Result<()>
+where
+    P: Provider<RpcTransport, Ethereum> + 'static,
+{
+    let fund_config = config
+        .fund
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[fund] section is missing from the config"))?;
+    let funder_address =
+        <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&funder);
+    let gas_token = GasToken::from_config(&config);
+
+    match fund_config.disperse_contract {
+        Some(disperse_contract) => {
+            if let GasToken::Erc20 { address: token, .. } = &gas_token {
+                let lane_total = lane
+                    .iter()
+                    .flatten()
+                    .map(|(_, amount)| *amount)
+                    .sum::<U256>();
+                let provider = providers.choose(&mut thread_rng());
+                let allowance = IERC20::new(*token, provider.clone())
+                    .allowance(funder_address, disperse_contract)
+                    .call()
+                    .await?
+                    ._0;
+
+                if allowance < lane_total {
+                    let input = IERC20::approveCall {
+                        spender: disperse_contract,
+                        amount: lane_total,
+                    }
+                    .abi_encode();
+                    if !send_transaction(
+                        provider,
+                        funder.clone(),
+                        *token,
+                        Some(input.into()),
+                        U256::ZERO,
+                        Stage::Approve,
+                        &config,
+                    )
+                    .await?
+                    {
+                        eyre::bail!("Approve for disperse from {funder_address} reverted");
+                    }
+                }
+            }
+
+            for batch in lane {
+                let batch_total = batch.iter().map(|(_, amount)| *amount).sum::<U256>();
+                let (input, value) = encode_batch(&gas_token, &batch);
+
+                tracing::info!(
+                    "Dispersing {} to {} wallets from {funder_address}",
+                    gas_token.format(batch_total),
+                    batch.len()
+                );
+
+                let provider = providers.choose(&mut thread_rng());
+                if !send_transaction(
+                    provider,
+                    funder.clone(),
+                    disperse_contract,
+                    Some(input.into()),
+                    value,
+                    Stage::Fund,
+                    &config,
+                )
+                .await?
+                {
+                    eyre::bail!(
+                        "Disperse of {} to {} wallets from {funder_address} reverted",
+                        gas_token.format(batch_total),
+                        batch.len()
+                    );
+                }
+
+                random_delay(fund_config).await;
+            }
+        }
+        None => {
+            let mut failed = 0;
+            for (address, amount) in lane.into_iter().flatten() {
+                tracing::info!(
+                    "Sending {} to {address} from {funder_address}",
+                    gas_token.format(amount)
+                );
+
+                let (to, input, value) = gas_token.transfer(address, amount);
+                let provider = providers.choose(&mut thread_rng());
+                match send_transaction(
+                    provider,
+                    funder.clone(),
+                    to,
+                    input,
+                    value,
+                    Stage::Fund,
+                    &config,
+                )
+                .await
+                {
+                    Ok(true) => {}
+                    Ok(false) => {
+                        tracing::error!("Funding transfer to {address} reverted");
+                        failed += 1;
+                    }
+                    Err(e) => {
+                        tracing::error!("Failed to fund {address}: {e}");
+                        failed += 1;
+                    }
+                }
+
+                random_delay(fund_config).await;
+            }
+
+            if failed > 0 {
+                eyre::bail!("{failed} funding transfers from {funder_address} failed");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// Sends from one funder are serialized, concurrent wallet pipelines would otherwise race for its
+// nonce, while different funders of the pool send in parallel
+static FUNDER_LOCKS: LazyLock<Mutex<HashMap<Address, Arc<Mutex<()>>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+static NEXT_FUNDER: AtomicUsize = AtomicUsize::new(0);
+
+pub async fn fund_recipient<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    recipient: Address,
+    funding: &RecipientFundingConfig,
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let gas_token = GasToken::from_config(config);
+    let amount =
+        gas_token.parse(thread_rng().gen_range(funding.min_amount..=funding.max_amount))?;
+
+    tracing::info!(
+        "Funding recipient {recipient} with {}",
+        gas_token.format(amount)
+    );
+
+    let (to, input, value) = gas_token.transfer(recipient, amount);
+    let sent = match funding.source {
+        FundingSource::Wallet => {
+            send_transaction(provider, wallet, to, input, value, Stage::Fund, config).await?
+        }
+        FundingSource::Funder => {
+            let fund_config = config.fund.as_ref().ok_or_else(|| {
+                eyre::eyre!("[fund] section is required to fund recipients from the funder")
+            })?;
+            let funders = funders(fund_config)?;
+            let funder =
+                funders[NEXT_FUNDER.fetch_add(1, Ordering::Relaxed) % funders.len()].clone();
+            let funder_address =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&funder);
+            let lock = FUNDER_LOCKS
+                .lock()
+                .await
+                .entry(funder_address)
+                .or_default()
+                .clone();
+
+            let _guard = lock.lock().await;
+            send_transaction(provider, funder, to, input, value, Stage::Fund, config).await?
+        }
+    };
+
+    if !sent {
+        eyre::bail!("Funding transaction to {recipient} reverted");
+    }
+
+    Ok(())
+}
@@ -0,0 +1,94 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{
+        utils::{format_units, parse_units},
+        Address, Bytes, U256,
+    },
+    providers::Provider,
+    sol_types::SolCall,
+    transports::Transport,
+};
+
+use crate::{campaign::chain, claimer::IERC20, config::Config};
+
+#[derive(Clone, Debug)]
+pub enum GasToken {
+    Native,
+    Erc20 {
+        address: Address,
+        symbol: String,
+        decimals: u8,
+        tokens_per_eth: f64,
+    },
+}
+
+impl GasToken {
+    pub fn from_config(config: &Config) -> Self {
+        match &config.gas_token {
+            Some(gas_token) => Self::Erc20 {
+                address: gas_token.token,
+                symbol: gas_token.symbol.clone(),
+                decimals: gas_token.decimals,
+                tokens_per_eth: gas_token.tokens_per_eth,
+            },
+            None => Self::Native,
+        }
+    }
+
+    pub fn decimals(&self) -> u8 {
+        match self {
+            Self::Native => chain().native_decimals,
+            Self::Erc20 { decimals, .. } => *decimals,
+        }
+    }
+
+    pub fn format(&self, amount: U256) -> String {
+        let symbol = match self {
+            Self::Native => &chain().native_symbol,
+            Self::Erc20 { symbol, .. } => symbol,
+        };
+        let amount = format_units(amount, self.decimals()).unwrap_or_else(|_| amount.to_string());
+
+        format!("{amount} {symbol}")
+    }
+
+    pub fn parse(&self, amount: f64) -> eyre::Result<U256> {
+        Ok(parse_units(&amount.to_string(), self.decimals())?.into())
+    }
+
+    pub fn price_wei(&self, wei: U256) -> eyre::Result<U256> {
+        match self {
+            Self::Native => Ok(wei),
+            Self::Erc20 { tokens_per_eth, .. } => {
+                let eth: f64 = format_units(wei, chain().native_decimals)?.parse()?;
+                self.parse(eth * tokens_per_eth)
+            }
+        }
+    }
+
+    pub async fn balance<P, T>(&self, provider: &P, address: Address) -> eyre::Result<U256>
+    where
+        P: Provider<T, Ethereum>,
+        T: Transport + Clone,
+    {
+        match self {
+            Self::Native => Ok(provider.get_balance(address).await?),
+            Self::Erc20 { address: token, .. } => Ok(IERC20::new(*token, provider)
+                .balanceOf(address)
+                .call()
+                .await?
+                ._0),
+        }
+    }
+
+    // Target, calldata and value of a transaction moving `amount` of the gas token to `to`
+    pub fn transfer(&self, to: Address, amount: U256) -> (Address, Option<Bytes>, U256) {
+        match self {
+            Self::Native => (to, None, amount),
+            Self::Erc20 { address, .. } => {
+                let input = IERC20::transferCall { to, amount }.abi_encode();
+                (*address, Some(input.into()), U256::ZERO)
+            }
+        }
+    }
+}
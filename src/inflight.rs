@@ -0,0 +1,184 @@
+use std::{
+    collections::BTreeMap,
+    panic::PanicHookInfo,
+    path::Path,
+    sync::{LazyLock, Mutex, MutexGuard, TryLockError},
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, TxHash},
+    providers::Provider,
+    transports::Transport,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    claimer::find_receipt,
+    constants::INFLIGHT_FILE_PATH,
+    journal::{journal_path, unix_timestamp},
+    metrics::Stage,
+    seal::{read_sealed, seal},
+};
+
+const SETTLE_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+static IN_FLIGHT: LazyLock<Mutex<BTreeMap<Address, InFlight>>> =
+    LazyLock::new(|| Mutex::new(BTreeMap::new()));
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct InFlight {
+    pub since: u64,
+    pub stage: Option<Stage>,
+    // Every hash broadcast for the current transaction, fee bumps included
+    pub pending: Vec<TxHash>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Crash {
+    timestamp: u64,
+    message: String,
+    journal: Option<String>,
+    wallets: BTreeMap<Address, InFlight>,
+}
+
+fn in_flight() -> MutexGuard<'static, BTreeMap<Address, InFlight>> {
+    IN_FLIGHT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub fn begin(address: Address) {
+    in_flight().insert(
+        address,
+        InFlight {
+            since: unix_timestamp(),
+            ..Default::default()
+        },
+    );
+}
+
+pub fn sent(address: Address, stage: Stage, tx_hash: TxHash) {
+    let mut in_flight = in_flight();
+    let wallet = in_flight.entry(address).or_insert_with(|| InFlight {
+        since: unix_timestamp(),
+        ..Default::default()
+    });
+
+    if wallet.stage != Some(stage) {
+        wallet.pending.clear();
+    }
+    wallet.stage = Some(stage);
+    wallet.pending.push(tx_hash);
+}
+
+pub fn settled(address: Address) {
+    if let Some(wallet) = in_flight().get_mut(&address) {
+        wallet.pending.clear();
+    }
+}
+
+pub fn finish(address: Address) {
+    in_flight().remove(&address);
+}
+
+pub fn count() -> usize {
+    in_flight().len()
+}
+
+fn write_crash(info: &PanicHookInfo) -> eyre::Result<()> {
+    // The panicking thread may hold the lock, a crash without breadcrumbs beats a hung process
+    let wallets = match IN_FLIGHT.try_lock() {
+        Ok(in_flight) => in_flight.clone(),
+        Err(TryLockError::Poisoned(e)) => e.into_inner().clone(),
+        Err(TryLockError::WouldBlock) => eyre::bail!("in-flight wallets are locked"),
+    };
+    // Startup panics happen before any wallet is touched and leave nothing to resume
+    if wallets.is_empty() {
+        return Ok(());
+    }
+
+    let crash = Crash {
+        timestamp: unix_timestamp(),
+        message: info.to_string(),
+        journal: journal_path().map(|path| path.display().to_string()),
+        wallets,
+    };
+
+    if let Some(parent) = Path::new(INFLIGHT_FILE_PATH).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(
+        INFLIGHT_FILE_PATH,
+        seal(&serde_json::to_string_pretty(&crash)?)?,
+    )?;
+
+    Ok(())
+}
+
+pub fn install_panic_hook() {
+    let previous = std::panic::take_hook();
+
+    std::panic::set_hook(Box::new(move |info| {
+        if let Err(e) = write_crash(info) {
+            eprintln!("Failed to save in-flight wallets to {INFLIGHT_FILE_PATH}: {e}");
+        }
+        previous(info);
+    }));
+}
+
+// Runs before a resumed claim so transactions broadcast before the crash aren't sent twice
+pub async fn settle_crashed<P, T>(provider: &P, inclusion_timeout: Duration) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(contents) = read_sealed(INFLIGHT_FILE_PATH).await? else {
+        return Ok(());
+    };
+    let crash: Crash = serde_json::from_str(&contents)?;
+
+    tracing::warn!(
+        "The run of {} crashed with {} wallets in flight: {}",
+        crash.timestamp,
+        crash.wallets.len(),
+        crash.message
+    );
+    if let Some(journal) = &crash.journal {
+        tracing::info!("Its journal is {journal}");
+    }
+
+    for (address, wallet) in &crash.wallets {
+        let stage = wallet.stage.map_or_else(
+            || String::from("before its first transaction"),
+            |stage| format!("at {stage}"),
+        );
+        if wallet.pending.is_empty() {
+            tracing::info!("{address} was interrupted {stage}");
+            continue;
+        }
+
+        tracing::info!(
+            "{address} was interrupted {stage}, waiting for {} pending hashes",
+            wallet.pending.len()
+        );
+        let started = Instant::now();
+        loop {
+            if let Some(receipt) = find_receipt(provider, &wallet.pending).await? {
+                tracing::info!(
+                    "{address} | {} mined with status {}",
+                    receipt.transaction_hash,
+                    receipt.status()
+                );
+                break;
+            }
+            if started.elapsed() >= inclusion_timeout {
+                tracing::warn!("{address} | pending transaction not mined, it will be replaced");
+                break;
+            }
+            tokio::time::sleep(SETTLE_POLL_INTERVAL).await;
+        }
+    }
+
+    tokio::fs::remove_file(INFLIGHT_FILE_PATH).await?;
+    Ok(())
+}
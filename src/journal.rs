@@ -0,0 +1,140 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use alloy::primitives::{Address, TxHash, B256, U256};
+use serde::Serialize;
+
+use crate::{constants::JOURNAL_FOLDER_PATH, metrics::Stage};
+
+static JOURNAL: OnceLock<Mutex<File>> = OnceLock::new();
+static JOURNAL_PATH: OnceLock<PathBuf> = OnceLock::new();
+
+#[derive(Serialize, Debug)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum Event {
+    ProofFetched {
+        address: Address,
+        amount: U256,
+        merkle_root: Option<B256>,
+    },
+    TxBuilt {
+        from: Address,
+        to: Address,
+        nonce: u64,
+        gas_limit: u64,
+    },
+    TxSent {
+        from: Address,
+        tx_hash: TxHash,
+        stage: Stage,
+    },
+    Receipt {
+        from: Address,
+        tx_hash: TxHash,
+        stage: Stage,
+        status: bool,
+        gas_used: u128,
+        effective_gas_price: u128,
+    },
+    Reorged {
+        from: Address,
+        tx_hash: TxHash,
+        stage: Stage,
+    },
+    Abandoned {
+        from: Address,
+        nonce: u64,
+        cancel_tx_hash: Option<TxHash>,
+    },
+    Retry {
+        address: Address,
+        error: String,
+    },
+    Skip {
+        address: Address,
+        reason: String,
+    },
+    StageFailed {
+        address: Address,
+        stage: Stage,
+        error: String,
+    },
+    Completed {
+        address: Address,
+    },
+    ForeignTx {
+        address: Address,
+        nonces: Vec<u64>,
+    },
+    Note {
+        text: String,
+    },
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    event: &'a Event,
+}
+
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+pub fn init_journal(journal_folder_path: impl AsRef<Path>) -> eyre::Result<PathBuf> {
+    std::fs::create_dir_all(&journal_folder_path)?;
+
+    let path = journal_folder_path
+        .as_ref()
+        .join(format!("run-{}.jsonl", unix_timestamp()));
+
+    let file = OpenOptions::new().create(true).append(true).open(&path)?;
+
+    JOURNAL
+        .set(Mutex::new(file))
+        .map_err(|_| eyre::eyre!("Journal is already initialized"))?;
+    JOURNAL_PATH.get_or_init(|| path.clone());
+
+    Ok(path)
+}
+
+pub fn init_default_journal() -> eyre::Result<PathBuf> {
+    init_journal(JOURNAL_FOLDER_PATH)
+}
+
+pub fn journal_path() -> Option<&'static Path> {
+    JOURNAL_PATH.get().map(PathBuf::as_path)
+}
+
+pub fn record(event: Event) {
+    let Some(journal) = JOURNAL.get() else {
+        return;
+    };
+
+    let entry = Entry {
+        timestamp: unix_timestamp(),
+        event: &event,
+    };
+
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            tracing::warn!("Failed to serialize journal entry {event:?}: {e}");
+            return;
+        }
+    };
+
+    let mut file = journal.lock().unwrap_or_else(|e| e.into_inner());
+    if let Err(e) = writeln!(file, "{line}").and_then(|_| file.flush()) {
+        tracing::warn!("Failed to write journal entry: {e}");
+    }
+}
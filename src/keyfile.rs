@@ -0,0 +1,371 @@
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::OnceLock,
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{utils::parse_ether, Address},
+    providers::Provider,
+};
+use rand::{thread_rng, RngCore};
+use tokio::io::AsyncWriteExt;
+
+use crate::{
+    claimer::is_dry_run,
+    config::{Config, DelegationStep},
+    constants::{MANIFEST_FILE_PATH, RECIPIENT_KEYS_FILE_PATH},
+    fork::is_forked,
+    gas_token::GasToken,
+    manifest::write_manifest,
+    multicall::{read_claim_statuses, ClaimStatus},
+    paths::{private_keys_path, wallets_path},
+    pool::init_provider_pool,
+    seal::{is_sealed, seal_with, unseal_with},
+    service,
+    signer::{LocalKeys, SignerBackend},
+    state,
+    token::{format_token, parse_token},
+    utils::{derive_recipient_signers, derive_recipients, read_private_keys},
+    verify::BALANCE_TOLERANCE,
+    wallets::read_wallets,
+};
+
+pub const KEYSTORE_PASSWORD_ENV: &str = "KEYSTORE_PASSWORD";
+
+// What a sweep leaves behind for its own fee margin
+const LEFTOVER_ETH: &str = "0.0001";
+
+static PASSWORD: OnceLock<String> = OnceLock::new();
+
+// Echo is turned off through stty where there is one, otherwise the password shows as typed
+fn set_echo(enabled: bool) {
+    let _ = Command::new("stty")
+        .arg(if enabled { "echo" } else { "-echo" })
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::null())
+        .status();
+}
+
+fn prompt(message: &str) -> eyre::Result<String> {
+    print!("{message}");
+    std::io::stdout().flush()?;
+
+    set_echo(false);
+    let mut password = String::new();
+    let read = std::io::stdin().read_line(&mut password);
+    set_echo(true);
+    println!();
+
+    read?;
+    Ok(password.trim_end_matches(['\r', '\n']).to_owned())
+}
+
+fn read_password(confirm: bool) -> eyre::Result<String> {
+    if let Ok(password) = std::env::var(KEYSTORE_PASSWORD_ENV) {
+        return Ok(password);
+    }
+    if service::is_enabled() {
+        eyre::bail!("Key files are encrypted, set {KEYSTORE_PASSWORD_ENV} to run as a service");
+    }
+
+    let password = prompt("Key file password: ")?;
+    if confirm && prompt("Repeat the password: ")? != password {
+        eyre::bail!("Passwords don't match");
+    }
+
+    Ok(password)
+}
+
+// One password unlocks both the keys file and the wallets CSV, so it's asked for once per run
+fn password(confirm: bool) -> eyre::Result<&'static str> {
+    if let Some(password) = PASSWORD.get() {
+        return Ok(password);
+    }
+
+    let password = read_password(confirm)?;
+    if password.is_empty() {
+        eyre::bail!("The key file password must not be empty");
+    }
+
+    Ok(PASSWORD.get_or_init(|| password))
+}
+
+pub async fn read_key_file(path: impl AsRef<Path>) -> eyre::Result<String> {
+    let contents = tokio::fs::read_to_string(&path).await?;
+    if !is_sealed(&contents) {
+        return Ok(contents);
+    }
+
+    unseal_with(password(false)?, &contents).map_err(|_| {
+        eyre::eyre!(
+            "Failed to decrypt {}, wrong password?",
+            path.as_ref().display()
+        )
+    })
+}
+
+pub async fn encrypt_key_files() -> eyre::Result<()> {
+    let paths: Vec<PathBuf> = [private_keys_path(), wallets_path()]
+        .into_iter()
+        .filter(|path| path.exists())
+        .map(Path::to_path_buf)
+        .collect();
+    if paths.is_empty() {
+        eyre::bail!("Found neither a private keys file nor a wallets CSV to encrypt");
+    }
+
+    let mut encrypted = 0;
+    for path in &paths {
+        let contents = tokio::fs::read_to_string(path).await?;
+        if is_sealed(&contents) {
+            tracing::info!("{} is already encrypted", path.display());
+            continue;
+        }
+
+        let password = password(true)?;
+        let sealed = seal_with(password, &contents)?;
+        // The plaintext is only replaced once the sealed copy is known to open again
+        if unseal_with(password, &sealed)? != contents {
+            eyre::bail!("Encrypted {} does not decrypt back", path.display());
+        }
+
+        tokio::fs::write(path, sealed).await?;
+        tracing::info!("Encrypted {}", path.display());
+        encrypted += 1;
+    }
+
+    if encrypted > 0 && Path::new(MANIFEST_FILE_PATH).exists() {
+        write_manifest().await?;
+        tracing::info!("Rewrote {MANIFEST_FILE_PATH} for the encrypted files");
+    }
+
+    Ok(())
+}
+
+// Derived recipients end up holding the payouts, their keys only ever reach the disk sealed
+pub async fn export_recipient_keys(config: Config) -> eyre::Result<()> {
+    let Some(recipient_mnemonic) = &config.recipient_mnemonic else {
+        eyre::bail!(
+            "export-keys writes the keys of derived recipients, set up [recipient_mnemonic]"
+        );
+    };
+    if Path::new(RECIPIENT_KEYS_FILE_PATH).exists() {
+        eyre::bail!("{RECIPIENT_KEYS_FILE_PATH} already exists, move it away first");
+    }
+
+    let wallets = read_private_keys().await?;
+    // Fails when the mnemonic no longer derives the recipients earlier runs paid
+    derive_recipients(recipient_mnemonic, &wallets).await?;
+    let keys: Vec<_> = derive_recipient_signers(recipient_mnemonic, wallets.len())?
+        .into_iter()
+        .take(wallets.len())
+        .map(|signer| signer.to_bytes().to_string())
+        .collect();
+
+    let password = password(true)?;
+    let contents = keys.join("\n") + "\n";
+    let sealed = seal_with(password, &contents)?;
+    if unseal_with(password, &sealed)? != contents {
+        eyre::bail!("Encrypted recipient keys do not decrypt back");
+    }
+
+    tokio::fs::write(RECIPIENT_KEYS_FILE_PATH, sealed).await?;
+    tracing::info!(
+        "Wrote the keys of {} recipients to {RECIPIENT_KEYS_FILE_PATH}, it opens with the key file password",
+        keys.len()
+    );
+
+    Ok(())
+}
+
+async fn matches_backup(contents: &str, key_backups: &[PathBuf]) -> eyre::Result<bool> {
+    for backup in key_backups {
+        let sealed = match tokio::fs::read_to_string(backup).await {
+            Ok(sealed) if is_sealed(&sealed) => sealed,
+            Ok(_) => {
+                tracing::warn!(
+                    "Key backup {} is not encrypted, ignoring it",
+                    backup.display()
+                );
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to read key backup {}: {e}", backup.display());
+                continue;
+            }
+        };
+
+        if unseal_with(password(false)?, &sealed).is_ok_and(|backed_up| backed_up == contents) {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+// Delegated claims and kept stake shares stay with the wallet after its transfer
+fn keeps_funds(config: &Config, wallet: Address) -> bool {
+    if config
+        .delegation
+        .as_ref()
+        .is_some_and(|delegation| delegation.after_claim == DelegationStep::InsteadOfTransfer)
+    {
+        return true;
+    }
+
+    config
+        .stake
+        .as_ref()
+        .is_some_and(|stake_config| match stake_config.keep_for(wallet) {
+            (_, Some(keep_amount)) => keep_amount > 0.0,
+            (keep_percent, None) => keep_percent > 0.0,
+        })
+}
+
+// Paid out means nothing is left to claim or move, so the keys have no work left. The state only
+// says a transfer confirmed, the balances are read back from the chain before anything is wiped
+async fn paid_out(config: &Config, path: &Path) -> eyre::Result<bool> {
+    let wallets = match path == wallets_path() {
+        true => read_wallets()
+            .await?
+            .into_iter()
+            .map(|entry| entry.wallet)
+            .collect(),
+        false => {
+            LocalKeys {
+                path: path.to_path_buf(),
+            }
+            .wallets(0)
+            .await?
+        }
+    };
+    let addresses: Vec<_> = wallets
+        .iter()
+        .map(|wallet| {
+            <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet.as_ref())
+        })
+        .collect();
+
+    for address in &addresses {
+        if keeps_funds(config, *address) {
+            return Ok(false);
+        }
+        if !state::get(*address)
+            .await?
+            .is_some_and(|state| state.transferred)
+        {
+            return Ok(false);
+        }
+    }
+
+    let providers = init_provider_pool(config).await?;
+    let provider = providers.choose(&mut thread_rng());
+    let token_tolerance = parse_token(BALANCE_TOLERANCE)?;
+    let eth_tolerance = parse_ether(LEFTOVER_ETH)?;
+
+    let statuses = read_claim_statuses(&provider, &addresses).await?;
+    for (address, status) in addresses.iter().zip(statuses) {
+        let Some(ClaimStatus { balance, .. }) = status else {
+            tracing::warn!("{address} | failed to read the token balance");
+            return Ok(false);
+        };
+        let eth_balance = provider.get_balance(*address).await?;
+        if balance > token_tolerance || eth_balance > eth_tolerance {
+            tracing::info!(
+                "{address} | still holds {} and {}",
+                format_token(balance),
+                GasToken::Native.format(eth_balance)
+            );
+            return Ok(false);
+        }
+    }
+
+    Ok(true)
+}
+
+// One pass of random bytes synced to disk before the unlink. Copy-on-write filesystems and SSD
+// wear levelling can still keep old blocks around, full disk encryption is the real protection
+async fn shred(path: &Path) -> eyre::Result<()> {
+    let len = tokio::fs::metadata(path).await?.len() as usize;
+    let mut noise = vec![0u8; len];
+    rand::thread_rng().fill_bytes(&mut noise);
+
+    let mut file = tokio::fs::OpenOptions::new().write(true).open(path).await?;
+    file.write_all(&noise).await?;
+    file.sync_all().await?;
+    file.set_len(0).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    tokio::fs::remove_file(path).await?;
+    Ok(())
+}
+
+// Plaintext key files are only wiped once an encrypted backup decrypts to the same contents or
+// every wallet in them is paid out and empty on chain, otherwise they stay for the next run
+pub async fn wipe_key_files(config: &Config) -> eyre::Result<()> {
+    if is_dry_run() || is_forked() {
+        tracing::info!("Dry or fork run, leaving the key files in place");
+        return Ok(());
+    }
+
+    for path in [private_keys_path(), wallets_path()] {
+        let Ok(contents) = tokio::fs::read_to_string(path).await else {
+            continue;
+        };
+        if is_sealed(&contents) {
+            tracing::info!("{} is encrypted, nothing to wipe", path.display());
+            continue;
+        }
+
+        let reason = if matches_backup(&contents, &config.key_backups).await? {
+            "an encrypted backup matches it"
+        } else if paid_out(config, path).await? {
+            "every wallet in it is paid out"
+        } else {
+            tracing::warn!(
+                "Keeping {}: no encrypted backup matches it and not every wallet is paid out",
+                path.display()
+            );
+            continue;
+        };
+
+        shred(path).await?;
+        tracing::info!("Wiped {}, {reason}", path.display());
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::signers::local::PrivateKeySigner;
+
+    use super::*;
+
+    #[test]
+    fn delegated_wallets_keep_their_funds() {
+        let config: Config = toml::from_str(
+            r#"
+RPC_URLS = []
+PROXIES = []
+SPAWN_TASK_DELAY = 0
+
+[delegation]
+AFTER_CLAIM = "instead_of_transfer"
+DELEGATEES = [{ DELEGATEE = "0x0000000000000000000000000000000000000001", NUMERATOR = 10000 }]
+"#,
+        )
+        .unwrap();
+        let wallet = PrivateKeySigner::random().address();
+
+        assert!(keeps_funds(&config, wallet));
+        assert!(!keeps_funds(
+            &toml::from_str("RPC_URLS = []\nPROXIES = []\nSPAWN_TASK_DELAY = 0").unwrap(),
+            wallet
+        ));
+    }
+}
@@ -0,0 +1,626 @@
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fs::OpenOptions,
+    io::Write,
+    path::Path,
+    sync::Mutex,
+};
+
+use alloy::{
+    network::{Ethereum, ReceiptResponse},
+    primitives::{utils::format_units, Address, TxHash, U256},
+    providers::Provider,
+    rpc::types::TransactionReceipt,
+    sol,
+    transports::Transport,
+};
+use clap::ValueEnum;
+use rand::thread_rng;
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use crate::{
+    campaign::{campaign, chain},
+    claimer::IERC20,
+    config::Config,
+    constants::{LEDGER_EXPORT_FOLDER_PATH, LEDGER_FILE_PATH},
+    fork::is_forked,
+    journal::unix_timestamp,
+    metrics::Stage,
+    pool::init_provider_pool,
+    token::token_info,
+    utils::read_file_lines,
+};
+
+sol! {
+    event Transfer(address indexed from, address indexed to, uint256 value);
+}
+
+// Appends from concurrent wallet tasks must not interleave
+static LEDGER: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, ValueEnum, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerFormat {
+    #[default]
+    Koinly,
+    Cointracking,
+}
+
+impl LedgerFormat {
+    fn name(&self) -> &'static str {
+        match self {
+            Self::Koinly => "koinly",
+            Self::Cointracking => "cointracking",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TokenTransfer {
+    pub token: Address,
+    pub from: Address,
+    pub to: Address,
+    pub amount: U256,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct LedgerEntry {
+    timestamp: u64,
+    wallet: Address,
+    stage: Stage,
+    tx_hash: TxHash,
+    from: Address,
+    to: Option<Address>,
+    value: U256,
+    gas_used: u128,
+    effective_gas_price: u128,
+    l1_fee: Option<U256>,
+    transfers: Vec<TokenTransfer>,
+    // Entries written before reverts were recorded all succeeded
+    #[serde(default = "default_status")]
+    status: bool,
+}
+
+fn default_status() -> bool {
+    true
+}
+
+pub fn token_transfers(receipt: &TransactionReceipt) -> Vec<TokenTransfer> {
+    receipt
+        .inner
+        .logs()
+        .iter()
+        .filter_map(|log| {
+            let transfer = log.log_decode::<Transfer>().ok()?;
+            Some(TokenTransfer {
+                token: transfer.inner.address,
+                from: transfer.inner.data.from,
+                to: transfer.inner.data.to,
+                amount: transfer.inner.data.value,
+            })
+        })
+        .collect()
+}
+
+// Scroll receipts carry the L1 data fee on top of the L2 gas, alloy's receipt type drops it
+async fn l1_fee<P, T>(provider: &P, tx_hash: TxHash) -> Option<U256>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let receipt: serde_json::Value = provider
+        .raw_request("eth_getTransactionReceipt".into(), (tx_hash,))
+        .await
+        .ok()?;
+    serde_json::from_value(receipt.get("l1Fee")?.clone()).ok()
+}
+
+// Kept across runs, token movements come from the receipt's Transfer logs rather than the calldata.
+// Reverted transactions are recorded too, their gas and L1 fee are still paid
+pub async fn record<P, T>(
+    provider: &P,
+    wallet: Address,
+    stage: Stage,
+    value: U256,
+    receipt: &TransactionReceipt,
+) where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    if is_forked() {
+        return;
+    }
+
+    let transfers = token_transfers(receipt);
+    let entry = LedgerEntry {
+        timestamp: unix_timestamp(),
+        wallet,
+        stage,
+        tx_hash: receipt.transaction_hash,
+        from: receipt.from,
+        to: receipt.to,
+        value,
+        gas_used: receipt.gas_used(),
+        effective_gas_price: receipt.effective_gas_price(),
+        l1_fee: l1_fee(provider, receipt.transaction_hash).await,
+        transfers,
+        status: receipt.status(),
+    };
+
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            tracing::warn!("Failed to serialize ledger entry {entry:?}: {e}");
+            return;
+        }
+    };
+
+    let _lock = LEDGER.lock().unwrap_or_else(|e| e.into_inner());
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(LEDGER_FILE_PATH)
+        .and_then(|mut file| writeln!(file, "{line}"));
+    if let Err(e) = written {
+        tracing::warn!("Failed to write ledger entry for {}: {e}", entry.tx_hash);
+    }
+}
+
+async fn read_ledger() -> eyre::Result<Vec<LedgerEntry>> {
+    if !Path::new(LEDGER_FILE_PATH).exists() {
+        return Ok(vec![]);
+    }
+
+    read_file_lines(LEDGER_FILE_PATH)
+        .await?
+        .iter()
+        .map(|line| Ok(serde_json::from_str(line)?))
+        .collect()
+}
+
+// Transactions recorded for every wallet, to be re-read from the chain rather than trusted
+pub async fn recorded_transactions() -> eyre::Result<HashMap<Address, Vec<(Stage, TxHash)>>> {
+    let mut transactions: HashMap<_, Vec<_>> = HashMap::new();
+    for entry in read_ledger().await? {
+        transactions
+            .entry(entry.wallet)
+            .or_default()
+            .push((entry.stage, entry.tx_hash));
+    }
+
+    Ok(transactions)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum RowKind {
+    Airdrop,
+    Trade,
+    Withdrawal,
+    Deposit,
+    Fee,
+}
+
+// One side of a row, already formatted in the currency's units
+type Amount = (String, String);
+
+struct Row {
+    timestamp: u64,
+    kind: RowKind,
+    sent: Option<Amount>,
+    received: Option<Amount>,
+    fee: Option<Amount>,
+    stage: Stage,
+    tx_hash: TxHash,
+}
+
+struct Currencies {
+    known: HashMap<Address, (String, u8)>,
+}
+
+impl Currencies {
+    fn new() -> Self {
+        let known = HashMap::from([(
+            campaign().token,
+            (token_info().symbol.clone(), token_info().decimals),
+        )]);
+
+        Self { known }
+    }
+
+    fn unknown(&self, entries: &[LedgerEntry]) -> HashSet<Address> {
+        entries
+            .iter()
+            .flat_map(|entry| &entry.transfers)
+            .map(|transfer| transfer.token)
+            .filter(|token| !self.known.contains_key(token))
+            .collect()
+    }
+
+    async fn resolve<P, T>(&mut self, provider: &P, tokens: HashSet<Address>)
+    where
+        P: Provider<T, Ethereum>,
+        T: Transport + Clone,
+    {
+        for address in tokens {
+            let token = IERC20::new(address, provider);
+            let currency = match (token.symbol().call().await, token.decimals().call().await) {
+                (Ok(symbol), Ok(decimals)) => (symbol._0, decimals._0),
+                _ => {
+                    tracing::warn!(
+                        "Failed to read symbol and decimals of {address}, its amounts stay in base units"
+                    );
+                    (address.to_string(), 0)
+                }
+            };
+            self.known.insert(address, currency);
+        }
+    }
+
+    fn token(&self, token: Address, amount: U256) -> Amount {
+        let (symbol, decimals) = &self.known[&token];
+        (format_amount(amount, *decimals), symbol.clone())
+    }
+
+    fn native(&self, amount: U256) -> Amount {
+        (
+            format_amount(amount, chain().native_decimals),
+            chain().native_symbol.clone(),
+        )
+    }
+}
+
+fn format_amount(amount: U256, decimals: u8) -> String {
+    let amount = format_units(amount, decimals).unwrap_or_else(|_| amount.to_string());
+    match amount.contains('.') {
+        true => amount
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string(),
+        false => amount,
+    }
+}
+
+fn format_date(timestamp: u64) -> String {
+    let date =
+        OffsetDateTime::from_unix_timestamp(timestamp as i64).unwrap_or(OffsetDateTime::UNIX_EPOCH);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+        date.year(),
+        u8::from(date.month()),
+        date.day(),
+        date.hour(),
+        date.minute(),
+        date.second()
+    )
+}
+
+// Tax tools take one sent and one received amount per row, so extra legs get rows of their own
+fn rows_of(entry: &LedgerEntry, currencies: &Currencies) -> Vec<(Address, Row)> {
+    let wallet = entry.wallet;
+    let mut sent: Vec<_> = entry
+        .transfers
+        .iter()
+        .filter(|transfer| transfer.from == wallet)
+        .map(|transfer| currencies.token(transfer.token, transfer.amount))
+        .collect();
+    // A reverted transaction moved nothing but still paid its fee
+    if entry.from == wallet && !entry.value.is_zero() && entry.status {
+        sent.push(currencies.native(entry.value));
+    }
+    let received: Vec<_> = entry
+        .transfers
+        .iter()
+        .filter(|transfer| transfer.to == wallet)
+        .map(|transfer| currencies.token(transfer.token, transfer.amount))
+        .collect();
+    let fee = currencies.native(
+        U256::from(entry.gas_used) * U256::from(entry.effective_gas_price)
+            + entry.l1_fee.unwrap_or_default(),
+    );
+
+    let kind = match (sent.is_empty(), received.is_empty()) {
+        (true, false) if entry.stage == Stage::Claim => RowKind::Airdrop,
+        (true, false) => RowKind::Deposit,
+        (false, false) => RowKind::Trade,
+        (false, true) => RowKind::Withdrawal,
+        (true, true) => RowKind::Fee,
+    };
+    let row = |sent, received, fee| Row {
+        timestamp: entry.timestamp,
+        kind,
+        sent,
+        received,
+        fee,
+        stage: entry.stage,
+        tx_hash: entry.tx_hash,
+    };
+
+    let mut rows = vec![];
+    let legs = sent.len().max(received.len()).max(1);
+    let (mut sent, mut received) = (sent.into_iter(), received.into_iter());
+    for leg in 0..legs {
+        let paid_fee = (leg == 0 && entry.from == wallet).then(|| fee.clone());
+        let leg = row(sent.next(), received.next(), paid_fee);
+        // A relayed transaction that moved nothing has nothing to book for the wallet
+        if leg.sent.is_some() || leg.received.is_some() || leg.fee.is_some() {
+            rows.push((wallet, leg));
+        }
+    }
+    // A relayer pays the gas of the wallets it sends for
+    if entry.from != wallet {
+        let mut relayed = row(None, None, Some(fee));
+        relayed.kind = RowKind::Fee;
+        rows.push((entry.from, relayed));
+    }
+
+    rows
+}
+
+fn csv_amount(amount: &Option<Amount>) -> [String; 2] {
+    match amount {
+        Some((amount, currency)) => [amount.clone(), currency.clone()],
+        None => [String::new(), String::new()],
+    }
+}
+
+fn koinly_line(row: &Row, _exchange: &str) -> String {
+    // Koinly books a fee without any transfer as a cost sent from the wallet
+    let (sent, fee, label) = match row.kind {
+        RowKind::Fee => (&row.fee, &None, "cost"),
+        RowKind::Airdrop => (&row.sent, &row.fee, "airdrop"),
+        _ => (&row.sent, &row.fee, ""),
+    };
+
+    [
+        vec![format!("{} UTC", format_date(row.timestamp))],
+        csv_amount(sent).to_vec(),
+        csv_amount(&row.received).to_vec(),
+        csv_amount(fee).to_vec(),
+        vec![
+            String::new(),
+            String::new(),
+            String::from(label),
+            row.stage.to_string(),
+            row.tx_hash.to_string(),
+        ],
+    ]
+    .concat()
+    .join(",")
+}
+
+fn cointracking_line(row: &Row, exchange: &str) -> String {
+    let (sent, fee, kind) = match row.kind {
+        RowKind::Fee => (&row.fee, &None, "Other Fee"),
+        RowKind::Airdrop => (&row.sent, &row.fee, "Airdrop"),
+        RowKind::Trade => (&row.sent, &row.fee, "Trade"),
+        RowKind::Withdrawal => (&row.sent, &row.fee, "Withdrawal"),
+        RowKind::Deposit => (&row.sent, &row.fee, "Deposit"),
+    };
+
+    [
+        vec![String::from(kind)],
+        csv_amount(&row.received).to_vec(),
+        csv_amount(sent).to_vec(),
+        csv_amount(fee).to_vec(),
+        vec![
+            String::from(exchange),
+            String::new(),
+            row.stage.to_string(),
+            format_date(row.timestamp),
+            row.tx_hash.to_string(),
+        ],
+    ]
+    .concat()
+    .join(",")
+}
+
+pub async fn export_ledger(config: Config, format: LedgerFormat) -> eyre::Result<()> {
+    let entries = read_ledger().await?;
+    if entries.is_empty() {
+        tracing::info!("No transactions recorded in {LEDGER_FILE_PATH} yet");
+        return Ok(());
+    }
+
+    // The RPCs are only needed for tokens other than the campaign's
+    let mut currencies = Currencies::new();
+    let unknown = currencies.unknown(&entries);
+    if !unknown.is_empty() {
+        let providers = init_provider_pool(&config).await?;
+        currencies
+            .resolve(&providers.choose(&mut thread_rng()), unknown)
+            .await;
+    }
+
+    let mut wallets: BTreeMap<Address, Vec<Row>> = BTreeMap::new();
+    for (address, row) in entries.iter().flat_map(|entry| rows_of(entry, &currencies)) {
+        wallets.entry(address).or_default().push(row);
+    }
+
+    let (header, line): (&str, fn(&Row, &str) -> String) = match format {
+        LedgerFormat::Koinly => (
+            "Date,Sent Amount,Sent Currency,Received Amount,Received Currency,Fee Amount,Fee Currency,Net Worth Amount,Net Worth Currency,Label,Description,TxHash",
+            koinly_line,
+        ),
+        LedgerFormat::Cointracking => (
+            "Type,Buy Amount,Buy Currency,Sell Amount,Sell Currency,Fee,Fee Currency,Exchange,Trade-Group,Comment,Date,Tx-ID",
+            cointracking_line,
+        ),
+    };
+
+    let folder = Path::new(LEDGER_EXPORT_FOLDER_PATH).join(format.name());
+    tokio::fs::create_dir_all(&folder).await?;
+    for (address, rows) in &wallets {
+        let mut lines = vec![String::from(header)];
+        lines.extend(rows.iter().map(|row| line(row, &config.campaign)));
+        tokio::fs::write(
+            folder.join(format!("{address}.csv")),
+            lines.join("\n") + "\n",
+        )
+        .await?;
+    }
+
+    tracing::info!(
+        "{} transactions of {} wallets exported to {}",
+        entries.len(),
+        wallets.len(),
+        folder.display()
+    );
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::utils::parse_ether;
+
+    use super::*;
+    use crate::campaign::init_campaign;
+
+    const WALLET: Address = Address::with_last_byte(1);
+    const RECIPIENT: Address = Address::with_last_byte(2);
+    const RELAYER: Address = Address::with_last_byte(3);
+    const SCR: Address = Address::with_last_byte(10);
+    const USDC: Address = Address::with_last_byte(11);
+
+    fn currencies() -> Currencies {
+        let _ = init_campaign("scroll");
+
+        Currencies {
+            known: HashMap::from([
+                (SCR, (String::from("SCR"), 18)),
+                (USDC, (String::from("USDC"), 6)),
+            ]),
+        }
+    }
+
+    fn entry(stage: Stage, from: Address, transfers: Vec<TokenTransfer>) -> LedgerEntry {
+        LedgerEntry {
+            timestamp: 0,
+            wallet: WALLET,
+            stage,
+            tx_hash: TxHash::ZERO,
+            from,
+            to: None,
+            value: U256::ZERO,
+            gas_used: 100_000,
+            effective_gas_price: 1_000_000_000,
+            l1_fee: Some(U256::from(1_000)),
+            transfers,
+            status: true,
+        }
+    }
+
+    fn transfer(token: Address, from: Address, to: Address, amount: &str) -> TokenTransfer {
+        TokenTransfer {
+            token,
+            from,
+            to,
+            amount: parse_ether(amount).unwrap(),
+        }
+    }
+
+    fn amount(amount: &str, currency: &str) -> Option<Amount> {
+        Some((String::from(amount), String::from(currency)))
+    }
+
+    fn fee() -> Option<Amount> {
+        amount("0.000100000000001", &chain().native_symbol)
+    }
+
+    #[test]
+    fn claim_is_an_airdrop_paying_its_own_fee() {
+        let rows = rows_of(
+            &entry(
+                Stage::Claim,
+                WALLET,
+                vec![transfer(SCR, Address::ZERO, WALLET, "12.5")],
+            ),
+            &currencies(),
+        );
+
+        assert_eq!(rows.len(), 1);
+        let (address, row) = &rows[0];
+        assert_eq!(*address, WALLET);
+        assert!(row.kind == RowKind::Airdrop);
+        assert_eq!(row.sent, None);
+        assert_eq!(row.received, amount("12.5", "SCR"));
+        assert_eq!(row.fee, fee());
+    }
+
+    #[test]
+    fn relayer_books_the_fee_of_a_relayed_transfer() {
+        let rows = rows_of(
+            &entry(
+                Stage::Transfer,
+                RELAYER,
+                vec![transfer(SCR, WALLET, RECIPIENT, "3")],
+            ),
+            &currencies(),
+        );
+
+        assert_eq!(rows.len(), 2);
+        let (address, row) = &rows[0];
+        assert_eq!(*address, WALLET);
+        assert!(row.kind == RowKind::Withdrawal);
+        assert_eq!(row.sent, amount("3", "SCR"));
+        assert_eq!(row.fee, None);
+
+        let (address, row) = &rows[1];
+        assert_eq!(*address, RELAYER);
+        assert!(row.kind == RowKind::Fee);
+        assert_eq!((&row.sent, &row.received), (&None, &None));
+        assert_eq!(row.fee, fee());
+
+        // Nothing moved for the wallet, so only the relayer has a row
+        let rows = rows_of(&entry(Stage::Transfer, RELAYER, vec![]), &currencies());
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].0, RELAYER);
+    }
+
+    #[test]
+    fn extra_trade_legs_get_rows_without_the_fee() {
+        let mut trade = entry(
+            Stage::Sell,
+            WALLET,
+            vec![
+                transfer(SCR, WALLET, RELAYER, "10"),
+                TokenTransfer {
+                    token: USDC,
+                    from: RELAYER,
+                    to: WALLET,
+                    amount: U256::from(7_000_000),
+                },
+            ],
+        );
+        trade.value = parse_ether("0.5").unwrap();
+
+        let rows = rows_of(&trade, &currencies());
+
+        assert_eq!(rows.len(), 2);
+        assert!(rows.iter().all(|(address, _)| *address == WALLET));
+        assert!(rows.iter().all(|(_, row)| row.kind == RowKind::Trade));
+        assert_eq!(rows[0].1.sent, amount("10", "SCR"));
+        assert_eq!(rows[0].1.received, amount("7", "USDC"));
+        assert_eq!(rows[0].1.fee, fee());
+        assert_eq!(rows[1].1.sent, amount("0.5", &chain().native_symbol));
+        assert_eq!(rows[1].1.received, None);
+        assert_eq!(rows[1].1.fee, None);
+    }
+
+    #[test]
+    fn reverted_transaction_books_only_its_fee() {
+        let mut reverted = entry(Stage::Transfer, WALLET, vec![]);
+        reverted.value = parse_ether("0.5").unwrap();
+        reverted.status = false;
+
+        let rows = rows_of(&reverted, &currencies());
+
+        assert_eq!(rows.len(), 1);
+        let (address, row) = &rows[0];
+        assert_eq!(*address, WALLET);
+        assert!(row.kind == RowKind::Fee);
+        assert_eq!((&row.sent, &row.received), (&None, &None));
+        assert_eq!(row.fee, fee());
+    }
+}
@@ -0,0 +1,313 @@
+use std::time::Instant;
+
+use balances::balances;
+use bench::bench;
+use campaign::{chain, init_campaign};
+use check::check_eligibility;
+use claimer::{claim_for_all, enable_dry_run};
+use cli::{Cli, Command, ConfigAction};
+use compare::compare_runs;
+use delegation::{audit_delegation, delegate_all};
+use doctor::{check_config, doctor};
+use estimate::estimate;
+use eyre::WrapErr;
+use failed::retry_failed;
+use fork::{prepare_fork, spawn_fork};
+use fund::{fund_all, top_up_all};
+
+use inflight::install_panic_hook;
+use journal::{init_default_journal, Event};
+use keyfile::{encrypt_key_files, export_recipient_keys, wipe_key_files};
+use ledger::export_ledger;
+use logger::init_default_logger;
+use manifest::{verify_manifest, write_manifest};
+use merkle::{init_local_tree, local_tree};
+use mnemonic::init_mnemonic;
+use paths::{enter_profile, init_paths};
+use plan::{review_plan, Plan};
+use prefetch::fetch_proofs;
+use proof::parse_fixture;
+use proof_check::verify_proofs;
+use proxy_health::check_proxies;
+use relayer::init_relayer;
+use retry_budget::init_retry_budget;
+use script::init_script;
+use seal::init_sealing;
+use service::init_service;
+use signer::init_signer;
+use snapshot::snapshot;
+use state::{add_note, add_run_note, backup_state, restore_state};
+use throttle::init_throttle;
+use timeline::export_timeline;
+use transfer::{sweep_all, transfer_all};
+use usage::write_usage_report;
+use utils::read_targets;
+use verify::verify;
+use watch::watch;
+use webhook::init_webhook;
+
+mod approval;
+mod auth;
+mod balances;
+mod bench;
+#[cfg(feature = "browser")]
+mod browser;
+mod calldata;
+mod campaign;
+mod check;
+mod claimer;
+pub mod cli;
+mod compare;
+mod config;
+mod confirm;
+mod constants;
+mod cookies;
+mod dashboard;
+mod delegation;
+mod discovery;
+mod doctor;
+mod dust;
+mod embed;
+mod errors;
+mod estimate;
+mod explorer;
+mod failed;
+mod fee_breaker;
+mod fees;
+mod fork;
+mod fund;
+mod gas_token;
+mod inflight;
+mod journal;
+mod keyfile;
+mod ledger;
+mod logger;
+mod manifest;
+mod merkle;
+mod metrics;
+mod mnemonic;
+mod monitor;
+mod multicall;
+mod nonces;
+mod notifier;
+mod paths;
+mod payout;
+mod plan;
+mod pool;
+mod prefetch;
+mod price;
+mod priority;
+mod proof;
+mod proof_check;
+mod proxy_health;
+mod race;
+mod rate_limit;
+mod relayer;
+mod report;
+mod retry_budget;
+mod safety;
+mod schedule;
+mod script;
+mod seal;
+mod sell;
+mod service;
+mod signer;
+mod snapshot;
+mod stake;
+mod state;
+mod throttle;
+mod timeline;
+mod token;
+mod tranches;
+mod transfer;
+mod usage;
+mod utils;
+mod verify;
+mod wallets;
+mod watch;
+mod watchlist;
+mod webhook;
+
+pub use claimer::RunSummary;
+pub use config::Config;
+pub use embed::{Claimer, ClaimerBuilder, ProofFuture, ProofProvider, TransferPolicy};
+pub use proof::ProofResponse;
+
+// Everything the binary does, so `main` only parses the command line
+pub async fn run(cli: Cli) -> eyre::Result<()> {
+    let started = Instant::now();
+
+    init_paths(cli.config, cli.private_keys, cli.recipients, cli.wallets);
+    let profile_dir = cli
+        .profile
+        .map(|profile| {
+            enter_profile(&profile).wrap_err_with(|| format!("Failed to enter profile {profile}"))
+        })
+        .transpose()?;
+
+    // A service has no terminal to draw on, its logs stay plain
+    let dashboard = cli.dashboard && !cli.non_interactive;
+    if dashboard {
+        dashboard::enable();
+    }
+    let _guard = init_default_logger(dashboard);
+    install_panic_hook();
+    let journal_path = init_default_journal().wrap_err("Failed to start the run journal")?;
+    tracing::info!("Writing run journal to {}", journal_path.display());
+    if let Some(profile_dir) = profile_dir {
+        tracing::info!("Using profile at {}", profile_dir.display());
+    }
+
+    if cli.non_interactive {
+        init_service().wrap_err("Failed to start service mode")?;
+    }
+
+    if cli.dry_run {
+        enable_dry_run();
+    }
+
+    if cli.accept_plan {
+        plan::accept_changes();
+    }
+    if cli.interactive {
+        confirm::enable(cli.confirm_first);
+    }
+
+    let command = cli.command.unwrap_or_default();
+    // Reports everything the initialization below would stop on at once, so it runs before any of it
+    if let Command::Config {
+        action: ConfigAction::Check,
+    } = command
+    {
+        let result = check_config().await;
+        if cli.non_interactive {
+            service::stop();
+        }
+        return result;
+    }
+
+    if !matches!(command, Command::Manifest) {
+        verify_manifest()
+            .await
+            .wrap_err("Input files don't match the manifest")?;
+    }
+
+    let mut config = Config::read_default().await?;
+    init_sealing(config.encrypt_state).wrap_err("Failed to set up state encryption")?;
+    init_campaign(&config.campaign).wrap_err("Failed to load the campaign")?;
+    init_mnemonic(config.mnemonic.as_ref()).wrap_err("Failed to derive the mnemonic wallets")?;
+    init_signer(config.signer.as_ref(), chain().chain_id)
+        .await
+        .wrap_err("Failed to load the signer backend wallets")?;
+    init_script(config.script.as_deref()).wrap_err("Failed to compile the wallet rules script")?;
+    init_relayer(config.relayer.as_ref()).wrap_err("Failed to set up the relayer")?;
+    if let Some(snapshot) = &config.merkle_snapshot {
+        init_local_tree(snapshot, config.merkle_leaf_encoding)
+            .wrap_err("Failed to read the merkle snapshot")?;
+    }
+    init_throttle(config.max_tps);
+    init_webhook(config.webhook.as_ref());
+    init_retry_budget(
+        config.retry_budget_attempts,
+        config.retry_budget_secs,
+        config.priority_retry_attempts(),
+    );
+
+    // Kept alive until the command is done, anvil stops with it
+    let _fork = match &cli.fork {
+        Some(rpc_url) => {
+            let fork = spawn_fork(rpc_url)
+                .await
+                .wrap_err("Failed to start the anvil fork")?;
+            config.rpc_urls = vec![fork.url.clone()];
+            prepare_fork(&fork, &read_targets(&config).await?)
+                .await
+                .wrap_err("Failed to prepare the fork")?;
+            Some(fork)
+        }
+        None => None,
+    };
+
+    if matches!(
+        command,
+        Command::Claim | Command::RetryFailed { .. } | Command::Watch
+    ) && cli.fork.is_none()
+    {
+        match backup_state(config.state_backups).await {
+            Ok(Some(backup)) => tracing::info!("Backed up the state to {}", backup.display()),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Failed to back up the state: {e}"),
+        }
+    }
+
+    let fetches_proofs = matches!(
+        command,
+        Command::Claim
+            | Command::RetryFailed { .. }
+            | Command::Watch
+            | Command::Check
+            | Command::Estimate
+            | Command::VerifyProofs
+            | Command::Verify
+    );
+    if fetches_proofs && local_tree().is_none() {
+        check_proxies(&config).await;
+    }
+
+    if let Some(text) = cli.note {
+        journal::record(Event::Note { text: text.clone() });
+        add_run_note(&journal_path, text)
+            .await
+            .wrap_err("Failed to save the run note")?;
+    }
+
+    match command {
+        Command::Claim => {
+            let wipe_config = config.wipe_keys.then(|| config.clone());
+            claim_for_all(config, cli.resume).await?;
+            if let Some(wipe_config) = wipe_config {
+                wipe_key_files(&wipe_config).await?;
+            }
+        }
+        Command::RetryFailed { category } => retry_failed(config, category, cli.resume).await?,
+        Command::Transfer => transfer_all(config).await?,
+        Command::Sweep => sweep_all(config).await?,
+        Command::Check => check_eligibility(config).await?,
+        Command::Delegate => delegate_all(config).await?,
+        Command::Balances => balances(config).await?,
+        Command::Estimate => estimate(config).await?,
+        Command::Doctor => doctor(config).await?,
+        Command::Config { .. } => unreachable!("config commands run before the config is read"),
+        Command::Bench { rewrite_config } => bench(config, rewrite_config).await?,
+        Command::Plan => {
+            review_plan(&Plan::build(&read_targets(&config).await?, &config).await).await?
+        }
+        Command::FetchProofs => fetch_proofs(config).await?,
+        Command::VerifyProofs => verify_proofs(config).await?,
+        Command::Verify => verify(config).await?,
+        Command::AuditDelegation => audit_delegation(config).await?,
+        Command::ParseFixture { path } => parse_fixture(path).await?,
+        Command::Timeline { journal } => export_timeline(journal).await?,
+        Command::Export { format } => export_ledger(config, format).await?,
+        Command::CompareRuns { a, b } => compare_runs(a, b).await?,
+        Command::Fund => fund_all(config).await?,
+        Command::GasTopup => top_up_all(config).await?,
+        Command::Watch => watch(config).await?,
+        Command::Snapshot => snapshot(config).await?,
+        Command::Manifest => write_manifest().await?,
+        Command::Restore { backup } => restore_state(backup).await?,
+        Command::Note { address, text } => add_note(address, text).await?,
+        Command::EncryptKeys => encrypt_key_files().await?,
+        Command::ExportKeys => export_recipient_keys(config).await?,
+    }
+
+    if let Err(e) = write_usage_report(started.elapsed()).await {
+        tracing::warn!("Failed to write the resource usage report: {e}");
+    }
+
+    if cli.non_interactive {
+        service::stop();
+    }
+
+    Ok(())
+}
@@ -1,14 +1,137 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::Address;
+use serde::Deserialize;
+use time::OffsetDateTime;
+use tracing::{
+    field::{Field, Visit},
+    span::{Attributes, Id},
+    Event, Span, Subscriber,
+};
 use tracing_appender::{
     non_blocking::WorkerGuard,
     rolling::{RollingFileAppender, Rotation},
 };
 use tracing_subscriber::{
-    filter::LevelFilter, fmt, layer::SubscriberExt, util::SubscriberInitExt, Layer,
+    filter::LevelFilter, fmt, layer::Context, layer::SubscriberExt, registry::LookupSpan,
+    util::SubscriberInitExt, Layer,
+};
+
+use crate::{
+    config::apply_env_overrides,
+    dashboard::{DashboardLayer, MessageVisitor},
+    paths::config_path,
 };
 
 const LOGS_FOLDER_PATH: &str = "data/logs";
+const WALLET_SPAN: &str = "wallet";
+
+#[derive(Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    #[default]
+    Pretty,
+    Compact,
+    Json,
+}
+
+// Read straight from the config file since the logger is up before the config is parsed
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+struct LogSettings {
+    #[serde(default)]
+    log_format: LogFormat,
+    #[serde(default)]
+    wallet_logs: bool,
+}
+
+fn read_log_settings() -> LogSettings {
+    let mut table: toml::Table = std::fs::read_to_string(config_path())
+        .ok()
+        .and_then(|config| toml::from_str(&config).ok())
+        .unwrap_or_default();
+    if apply_env_overrides(&mut table).is_err() {
+        return LogSettings::default();
+    }
+
+    toml::Value::Table(table).try_into().unwrap_or_default()
+}
+
+// Every event inside this span also lands in the wallet's own log file
+pub fn wallet_span(address: Address) -> Span {
+    tracing::info_span!(WALLET_SPAN, address = %address)
+}
 
-pub fn init_logger(logs_folder_path: &str) -> WorkerGuard {
+struct WalletAddress(String);
+
+#[derive(Default)]
+struct AddressVisitor(Option<String>);
+
+impl Visit for AddressVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "address" {
+            self.0 = Some(format!("{value:?}"));
+        }
+    }
+}
+
+// Appends to <folder>/<address>.<date>.log, opened per event so 800 wallets don't hold 800 files
+struct WalletFileLayer {
+    folder: PathBuf,
+}
+
+impl<S> Layer<S> for WalletFileLayer
+where
+    S: Subscriber + for<'a> LookupSpan<'a>,
+{
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != WALLET_SPAN {
+            return;
+        }
+
+        let mut visitor = AddressVisitor::default();
+        attrs.record(&mut visitor);
+        if let (Some(address), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(WalletAddress(address));
+        }
+    }
+
+    fn on_event(&self, event: &Event<'_>, ctx: Context<'_, S>) {
+        let Some(address) = ctx.event_scope(event).and_then(|scope| {
+            scope.from_root().find_map(|span| {
+                span.extensions()
+                    .get::<WalletAddress>()
+                    .map(|a| a.0.clone())
+            })
+        }) else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+        let now = OffsetDateTime::now_utc();
+        let line = format!(
+            "{now} {:>5} {}: {}{}\n",
+            event.metadata().level(),
+            event.metadata().target(),
+            visitor.message,
+            visitor.fields
+        );
+
+        let path = self.folder.join(format!("{address}.{}.log", now.date()));
+        let _ = std::fs::create_dir_all(&self.folder);
+        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(path) {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+}
+
+pub fn init_logger(logs_folder_path: &str, dashboard: bool) -> WorkerGuard {
+    let settings = read_log_settings();
     let file_appender = RollingFileAppender::builder()
         .rotation(Rotation::HOURLY)
         .filename_prefix("app")
@@ -21,26 +144,47 @@ pub fn init_logger(logs_folder_path: &str) -> WorkerGuard {
     let stdout_filter = LevelFilter::INFO;
     let file_filter = LevelFilter::INFO;
 
-    let stdout_layer = fmt::layer()
-        .with_writer(std::io::stdout)
-        .with_thread_ids(true)
-        .pretty() // comment this out if want to use the default format
-        .with_ansi(true)
-        .with_filter(stdout_filter);
+    let stdout_layer = (!dashboard).then(|| {
+        let layer = fmt::layer()
+            .with_writer(std::io::stdout)
+            .with_thread_ids(true)
+            .with_ansi(true);
+        match settings.log_format {
+            LogFormat::Pretty => layer.pretty().with_filter(stdout_filter).boxed(),
+            LogFormat::Compact => layer.compact().with_filter(stdout_filter).boxed(),
+            LogFormat::Json => layer
+                .json()
+                .with_ansi(false)
+                .with_filter(stdout_filter)
+                .boxed(),
+        }
+    });
+    let dashboard_layer = dashboard.then_some(DashboardLayer.with_filter(stdout_filter));
+
+    let file_layer = fmt::layer().with_writer(writer).with_ansi(false);
+    let file_layer = match settings.log_format {
+        LogFormat::Pretty => file_layer.with_filter(file_filter).boxed(),
+        LogFormat::Compact => file_layer.compact().with_filter(file_filter).boxed(),
+        LogFormat::Json => file_layer.json().with_filter(file_filter).boxed(),
+    };
 
-    let file_layer = fmt::layer()
-        .with_writer(writer)
-        .with_ansi(false)
-        .with_filter(file_filter);
+    let wallet_layer = settings.wallet_logs.then(|| {
+        WalletFileLayer {
+            folder: Path::new(logs_folder_path).join("wallets"),
+        }
+        .with_filter(file_filter)
+    });
 
     tracing_subscriber::registry()
         .with(stdout_layer)
+        .with(dashboard_layer)
         .with(file_layer)
+        .with(wallet_layer)
         .init();
 
     guard
 }
 
-pub fn init_default_logger() -> WorkerGuard {
-    init_logger(LOGS_FOLDER_PATH)
+pub fn init_default_logger(dashboard: bool) -> WorkerGuard {
+    init_logger(LOGS_FOLDER_PATH, dashboard)
 }
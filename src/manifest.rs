@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+use alloy::hex;
+use sha2::{Digest, Sha256};
+
+use crate::{
+    constants::{CAMPAIGNS_FILE_PATH, MANIFEST_FILE_PATH, RECIPIENTS_WHITELIST_FILE_PATH},
+    paths::{config_path, private_keys_path, recipients_path, wallets_path},
+    utils::read_file_lines,
+};
+
+fn manifest_inputs() -> [PathBuf; 6] {
+    [
+        private_keys_path().to_path_buf(),
+        recipients_path().to_path_buf(),
+        wallets_path().to_path_buf(),
+        PathBuf::from(RECIPIENTS_WHITELIST_FILE_PATH),
+        config_path().to_path_buf(),
+        PathBuf::from(CAMPAIGNS_FILE_PATH),
+    ]
+}
+
+async fn sha256_file(path: impl AsRef<Path>) -> eyre::Result<String> {
+    let contents = tokio::fs::read(path).await?;
+    Ok(hex::encode(Sha256::digest(&contents)))
+}
+
+// Lines use the `sha256sum` format: `<hex digest>  <path>`
+pub async fn verify_manifest() -> eyre::Result<()> {
+    if !Path::new(MANIFEST_FILE_PATH).exists() {
+        return Ok(());
+    }
+
+    for line in read_file_lines(MANIFEST_FILE_PATH).await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (expected, path) = line
+            .split_once(char::is_whitespace)
+            .ok_or_else(|| eyre::eyre!("Malformed manifest line: {line}"))?;
+        let path = path.trim_start().trim_start_matches('*');
+
+        let actual = sha256_file(path)
+            .await
+            .map_err(|e| eyre::eyre!("Failed to hash {path}: {e}"))?;
+        if !actual.eq_ignore_ascii_case(expected) {
+            eyre::bail!(
+                "{path} does not match {MANIFEST_FILE_PATH}: expected {expected}, got {actual}"
+            );
+        }
+    }
+
+    tracing::info!("Input files match {MANIFEST_FILE_PATH}");
+
+    Ok(())
+}
+
+pub async fn write_manifest() -> eyre::Result<()> {
+    let mut lines = vec![];
+    for path in manifest_inputs() {
+        if path.exists() {
+            lines.push(format!("{}  {}", sha256_file(&path).await?, path.display()));
+        }
+    }
+
+    tokio::fs::write(MANIFEST_FILE_PATH, lines.join("\n") + "\n").await?;
+    tracing::info!(
+        "Wrote hashes of {} files to {MANIFEST_FILE_PATH}",
+        lines.len()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::Path,
+    str::FromStr,
+    sync::OnceLock,
+};
+
+use alloy::{
+    primitives::{keccak256, Address, B256, U256},
+    sol_types::SolValue,
+};
+use serde::Deserialize;
+
+use crate::proof::ProofResponse;
+
+static LOCAL_TREE: OnceLock<MerkleTree> = OnceLock::new();
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum LeafEncoding {
+    #[default]
+    DoubleHashed,
+    Packed,
+}
+
+pub fn leaf_hash(account: Address, amount: U256, encoding: LeafEncoding) -> B256 {
+    match encoding {
+        LeafEncoding::DoubleHashed => keccak256(keccak256((account, amount).abi_encode())),
+        LeafEncoding::Packed => keccak256((account, amount).abi_encode_packed()),
+    }
+}
+
+pub fn hash_pair(a: B256, b: B256) -> B256 {
+    if a <= b {
+        keccak256([a.as_slice(), b.as_slice()].concat())
+    } else {
+        keccak256([b.as_slice(), a.as_slice()].concat())
+    }
+}
+
+pub fn compute_root(leaf: B256, proof: &[B256]) -> B256 {
+    proof
+        .iter()
+        .fold(leaf, |node, sibling| hash_pair(node, *sibling))
+}
+
+pub fn verify(account: Address, amount: U256, proof: &[B256], root: B256) -> Option<LeafEncoding> {
+    [LeafEncoding::DoubleHashed, LeafEncoding::Packed]
+        .into_iter()
+        .find(|encoding| compute_root(leaf_hash(account, amount, *encoding), proof) == root)
+}
+
+// Same layout as OpenZeppelin's StandardMerkleTree: leaves sorted by hash and stored
+// at the end of a flat array, parents at (i - 1) / 2
+pub struct MerkleTree {
+    nodes: Vec<B256>,
+    leaves: HashMap<Address, (usize, U256)>,
+}
+
+impl MerkleTree {
+    pub fn build(entries: &[(Address, U256)], encoding: LeafEncoding) -> eyre::Result<Self> {
+        if entries.is_empty() {
+            eyre::bail!("Cannot build a merkle tree without entries");
+        }
+
+        let mut hashed: Vec<_> = entries
+            .iter()
+            .map(|(account, amount)| (leaf_hash(*account, *amount, encoding), *account, *amount))
+            .collect();
+        hashed.sort_by_key(|(hash, _, _)| *hash);
+
+        let len = 2 * hashed.len() - 1;
+        let mut nodes = vec![B256::ZERO; len];
+        let mut leaves = HashMap::new();
+
+        for (position, (hash, account, amount)) in hashed.into_iter().enumerate() {
+            let index = len - 1 - position;
+            nodes[index] = hash;
+            if leaves.insert(account, (index, amount)).is_some() {
+                eyre::bail!("{account} appears more than once in the snapshot");
+            }
+        }
+
+        for index in (0..len - leaves.len()).rev() {
+            nodes[index] = hash_pair(nodes[2 * index + 1], nodes[2 * index + 2]);
+        }
+
+        Ok(Self { nodes, leaves })
+    }
+
+    pub fn root(&self) -> B256 {
+        self.nodes[0]
+    }
+
+    pub fn total(&self) -> U256 {
+        self.leaves
+            .values()
+            .fold(U256::ZERO, |total, (_, amount)| total + amount)
+    }
+
+    pub fn proof(&self, account: Address) -> Option<(U256, Vec<B256>)> {
+        let (mut index, amount) = *self.leaves.get(&account)?;
+
+        let mut proof = vec![];
+        while index > 0 {
+            let sibling = if index % 2 == 1 { index + 1 } else { index - 1 };
+            proof.push(self.nodes[sibling]);
+            index = (index - 1) / 2;
+        }
+
+        Some((amount, proof))
+    }
+
+    pub fn proof_response(&self, account: Address) -> ProofResponse {
+        match self.proof(account) {
+            Some((amount, proof)) => ProofResponse::Eligible {
+                proof,
+                amount,
+                fields: BTreeMap::new(),
+            },
+            None => ProofResponse::Ineligible,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StandardTreeDump {
+    values: Vec<StandardTreeValue>,
+}
+
+#[derive(Deserialize)]
+struct StandardTreeValue {
+    value: (Address, String),
+}
+
+fn parse_amount(amount: &str) -> eyre::Result<U256> {
+    Ok(U256::from_str(amount.trim())?)
+}
+
+// Accepts `address,amount` CSV lines or an OpenZeppelin StandardMerkleTree JSON dump
+fn read_snapshot(path: &Path) -> eyre::Result<Vec<(Address, U256)>> {
+    let contents = std::fs::read_to_string(path)?;
+
+    if path
+        .extension()
+        .is_some_and(|extension| extension == "json")
+    {
+        let dump: StandardTreeDump = serde_json::from_str(&contents)?;
+        return dump
+            .values
+            .into_iter()
+            .map(|entry| Ok((entry.value.0, parse_amount(&entry.value.1)?)))
+            .collect();
+    }
+
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .filter(|line| !line.to_ascii_lowercase().starts_with("address"))
+        .map(|line| {
+            let (account, amount) = line
+                .split_once(',')
+                .ok_or_else(|| eyre::eyre!("Malformed snapshot line: {line}"))?;
+            Ok((Address::from_str(account.trim())?, parse_amount(amount)?))
+        })
+        .collect()
+}
+
+pub fn init_local_tree(path: impl AsRef<Path>, encoding: LeafEncoding) -> eyre::Result<()> {
+    let entries = read_snapshot(path.as_ref())?;
+    let tree = MerkleTree::build(&entries, encoding)?;
+
+    tracing::info!(
+        "Built merkle tree of {} entries from {}, root {}",
+        entries.len(),
+        path.as_ref().display(),
+        tree.root()
+    );
+
+    LOCAL_TREE
+        .set(tree)
+        .map_err(|_| eyre::eyre!("Local merkle tree is already initialized"))
+}
+
+pub fn local_tree() -> Option<&'static MerkleTree> {
+    LOCAL_TREE.get()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn proofs_of_built_tree_verify_against_its_root() {
+        let entries: Vec<_> = (1..=7u64)
+            .map(|i| (Address::with_last_byte(i as u8), U256::from(i * 1_000)))
+            .collect();
+
+        for encoding in [LeafEncoding::DoubleHashed, LeafEncoding::Packed] {
+            let tree = MerkleTree::build(&entries, encoding).unwrap();
+
+            for (account, amount) in &entries {
+                let (proven_amount, proof) = tree.proof(*account).unwrap();
+                assert_eq!(proven_amount, *amount);
+                assert_eq!(
+                    verify(*account, *amount, &proof, tree.root()),
+                    Some(encoding)
+                );
+            }
+        }
+
+        let tree = MerkleTree::build(&entries, LeafEncoding::DoubleHashed).unwrap();
+        assert!(tree.proof(Address::with_last_byte(42)).is_none());
+    }
+}
@@ -0,0 +1,168 @@
+use std::{collections::BTreeMap, fmt, sync::Mutex, time::Duration};
+
+use serde::{Deserialize, Serialize};
+
+static GAS_SAMPLES: Mutex<BTreeMap<Stage, GasStats>> = Mutex::new(BTreeMap::new());
+static TIMINGS: Mutex<BTreeMap<Timing, Vec<Duration>>> = Mutex::new(BTreeMap::new());
+static GAS_SPENT: Mutex<u128> = Mutex::new(0);
+static ACCESS_LIST_SAVINGS: Mutex<BTreeMap<Stage, (u64, u128)>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Stage {
+    Claim,
+    Transfer,
+    Approve,
+    Sell,
+    Stake,
+    Fund,
+    Delegate,
+    Dust,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Stage::Claim => "claim",
+            Stage::Transfer => "transfer",
+            Stage::Approve => "approve",
+            Stage::Sell => "sell",
+            Stage::Stake => "stake",
+            Stage::Fund => "fund",
+            Stage::Delegate => "delegate",
+            Stage::Dust => "dust",
+        };
+
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Timing {
+    ProofFetch,
+    Broadcast,
+    Inclusion(Stage),
+}
+
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Timing::ProofFetch => f.write_str("proof fetch"),
+            Timing::Broadcast => f.write_str("rpc broadcast"),
+            Timing::Inclusion(stage) => write!(f, "{stage} inclusion"),
+        }
+    }
+}
+
+#[derive(Default)]
+struct GasStats {
+    count: u64,
+    estimated: u128,
+    used: u128,
+    max_drift_percent: f64,
+}
+
+pub fn record_gas(stage: Stage, estimated: u128, used: u128) {
+    let drift_percent = if estimated == 0 {
+        0.0
+    } else {
+        (used as f64 - estimated as f64) / estimated as f64 * 100.0
+    };
+
+    let mut samples = GAS_SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    let stats = samples.entry(stage).or_default();
+
+    stats.count += 1;
+    stats.estimated += estimated;
+    stats.used += used;
+    if drift_percent.abs() > stats.max_drift_percent.abs() {
+        stats.max_drift_percent = drift_percent;
+    }
+}
+
+pub fn record_gas_cost(cost: u128) {
+    *GAS_SPENT.lock().unwrap_or_else(|e| e.into_inner()) += cost;
+}
+
+// Wei paid for every receipt of this process so far
+pub fn gas_spent() -> u128 {
+    *GAS_SPENT.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+// Gas an access list took off a transaction's estimate, the realized share shows in avg used
+pub fn record_access_list_savings(stage: Stage, saved: u64) {
+    let mut savings = ACCESS_LIST_SAVINGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+    let (count, total) = savings.entry(stage).or_default();
+
+    *count += 1;
+    *total += u128::from(saved);
+}
+
+pub fn log_gas_summary() {
+    let samples = GAS_SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+    let savings = ACCESS_LIST_SAVINGS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    for (stage, stats) in samples.iter() {
+        let avg_estimated = stats.estimated / stats.count as u128;
+        let avg_used = stats.used / stats.count as u128;
+        let error_percent =
+            (stats.used as f64 - stats.estimated as f64) / stats.estimated.max(1) as f64 * 100.0;
+
+        tracing::info!(
+            "Gas {stage}: {} txs | avg estimated {avg_estimated} | avg used {avg_used} | error {error_percent:+.1}% | worst {:+.1}%",
+            stats.count,
+            stats.max_drift_percent
+        );
+
+        if let Some((count, total)) = savings.get(stage) {
+            tracing::info!(
+                "Gas {stage}: access lists saved {total} gas over {count} txs, avg {}",
+                total / u128::from(*count)
+            );
+        }
+    }
+}
+
+pub fn record_duration(timing: Timing, duration: Duration) {
+    let mut timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+    timings.entry(timing).or_default().push(duration);
+}
+
+fn percentile(sorted: &[Duration], percent: usize) -> Duration {
+    let index = (sorted.len() * percent).div_ceil(100).saturating_sub(1);
+    sorted[index.min(sorted.len() - 1)]
+}
+
+pub fn log_timing_summary(completed: usize, elapsed: Duration) {
+    let mut timings = TIMINGS.lock().unwrap_or_else(|e| e.into_inner());
+
+    for (timing, durations) in timings.iter_mut() {
+        if durations.is_empty() {
+            continue;
+        }
+
+        durations.sort_unstable();
+        let average = durations.iter().sum::<Duration>() / durations.len() as u32;
+
+        tracing::info!(
+            "Timing {timing}: {} samples | avg {:.1}s | p50 {:.1}s | p90 {:.1}s | p99 {:.1}s | max {:.1}s",
+            durations.len(),
+            average.as_secs_f64(),
+            percentile(durations, 50).as_secs_f64(),
+            percentile(durations, 90).as_secs_f64(),
+            percentile(durations, 99).as_secs_f64(),
+            durations[durations.len() - 1].as_secs_f64(),
+        );
+    }
+
+    let minutes = elapsed.as_secs_f64() / 60.0;
+    tracing::info!(
+        "Completed {completed} wallets in {:.1} minutes ({:.2} wallets/min)",
+        minutes,
+        completed as f64 / minutes.max(f64::EPSILON)
+    );
+}
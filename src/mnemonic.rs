@@ -0,0 +1,219 @@
+use std::{
+    num::NonZeroU32,
+    sync::{Arc, OnceLock},
+};
+
+use alloy::{network::EthereumWallet, primitives::B256, signers::local::PrivateKeySigner};
+use k256::{
+    elliptic_curve::{sec1::ToEncodedPoint, PrimeField},
+    FieldBytes, Scalar, SecretKey,
+};
+use ring::{hmac, pbkdf2};
+
+use crate::{auth::register_signer, config::MnemonicConfig};
+
+pub const MNEMONIC_ENV: &str = "MNEMONIC";
+pub const RECIPIENT_MNEMONIC_ENV: &str = "RECIPIENT_MNEMONIC";
+
+const SEED_ITERATIONS: u32 = 2048;
+const HARDENED: u32 = 0x8000_0000;
+const WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+
+static DERIVED: OnceLock<Vec<Arc<EthereumWallet>>> = OnceLock::new();
+
+struct ExtendedKey {
+    key: Scalar,
+    chain_code: [u8; 32],
+}
+
+impl ExtendedKey {
+    fn from_hmac(key: &[u8], data: &[u8]) -> ([u8; 32], [u8; 32]) {
+        let tag = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA512, key), data);
+        let (left, right) = tag.as_ref().split_at(32);
+        (left.try_into().unwrap(), right.try_into().unwrap())
+    }
+
+    fn master(seed: &[u8]) -> eyre::Result<Self> {
+        let (key, chain_code) = Self::from_hmac(b"Bitcoin seed", seed);
+        let key = Option::from(Scalar::from_repr(FieldBytes::from(key)))
+            .filter(|key: &Scalar| !bool::from(key.is_zero()))
+            .ok_or_else(|| eyre::eyre!("Seed derives an invalid master key"))?;
+
+        Ok(Self { key, chain_code })
+    }
+
+    fn child(&self, index: u32) -> eyre::Result<Self> {
+        let mut data = Vec::with_capacity(37);
+        if index >= HARDENED {
+            data.push(0);
+            data.extend_from_slice(&self.key.to_bytes());
+        } else {
+            let secret = SecretKey::from_bytes(&self.key.to_bytes())?;
+            data.extend_from_slice(secret.public_key().to_encoded_point(true).as_bytes());
+        }
+        data.extend_from_slice(&index.to_be_bytes());
+
+        let (tweak, chain_code) = Self::from_hmac(&self.chain_code, &data);
+        let key = Option::<Scalar>::from(Scalar::from_repr(FieldBytes::from(tweak)))
+            .map(|tweak| tweak + self.key)
+            .filter(|key| !bool::from(key.is_zero()))
+            .ok_or_else(|| eyre::eyre!("Child {index} is invalid, skip to the next index"))?;
+
+        Ok(Self { key, chain_code })
+    }
+}
+
+// Only the seed is needed, BIP-39 words are not checked against the wordlist
+fn seed(phrase: &str, passphrase: &str) -> eyre::Result<[u8; 64]> {
+    let words: Vec<_> = phrase.split_whitespace().collect();
+    if !WORD_COUNTS.contains(&words.len()) {
+        eyre::bail!(
+            "Mnemonic has {} words, expected 12, 15, 18, 21 or 24",
+            words.len()
+        );
+    }
+    // Non-ASCII phrases would need NFKD normalization first
+    if !phrase.is_ascii() || !passphrase.is_ascii() {
+        eyre::bail!("Only ASCII mnemonics and passphrases are supported");
+    }
+
+    let mut seed = [0u8; 64];
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA512,
+        NonZeroU32::new(SEED_ITERATIONS).unwrap(),
+        format!("mnemonic{passphrase}").as_bytes(),
+        words.join(" ").as_bytes(),
+        &mut seed,
+    );
+
+    Ok(seed)
+}
+
+fn parse_path(path: &str) -> eyre::Result<Vec<u32>> {
+    let mut segments = path.split('/');
+    if segments.next() != Some("m") {
+        eyre::bail!("Derivation path {path} must start with m/");
+    }
+
+    segments
+        .map(|segment| {
+            let (index, hardened) = match segment.strip_suffix('\'') {
+                Some(index) => (index, true),
+                None => (segment, false),
+            };
+            let index: u32 = index
+                .parse()
+                .ok()
+                .filter(|index| *index < HARDENED)
+                .ok_or_else(|| eyre::eyre!("Invalid segment {segment:?} in {path}"))?;
+
+            Ok(if hardened { index | HARDENED } else { index })
+        })
+        .collect()
+}
+
+// `m/44'/60'/0'/0/{0..199}` expands to 200 paths, both ends included
+pub fn expand_paths(template: &str) -> eyre::Result<Vec<String>> {
+    let Some((prefix, rest)) = template.split_once('{') else {
+        return Ok(vec![template.to_owned()]);
+    };
+    let (range, suffix) = rest
+        .split_once('}')
+        .ok_or_else(|| eyre::eyre!("Unclosed range in derivation path {template}"))?;
+    let (start, end) = range
+        .split_once("..")
+        .ok_or_else(|| eyre::eyre!("Range {{{range}}} must look like {{0..199}}"))?;
+    let (start, end): (u32, u32) = (start.trim().parse()?, end.trim().parse()?);
+    if start > end {
+        eyre::bail!("Range {{{range}}} is empty");
+    }
+
+    Ok((start..=end)
+        .map(|index| format!("{prefix}{index}{suffix}"))
+        .collect())
+}
+
+pub fn derive_signer(seed: &[u8], path: &str) -> eyre::Result<PrivateKeySigner> {
+    let key = parse_path(path)?
+        .into_iter()
+        .try_fold(ExtendedKey::master(seed)?, |key, index| key.child(index))?;
+
+    Ok(PrivateKeySigner::from_bytes(&B256::from_slice(
+        &key.key.to_bytes(),
+    ))?)
+}
+
+// One signer per path of PATH, the phrase is read from `env` when the section has none
+pub fn derive_signers(
+    mnemonic_config: &MnemonicConfig,
+    section: &str,
+    env: &str,
+) -> eyre::Result<Vec<PrivateKeySigner>> {
+    let phrase = match &mnemonic_config.phrase {
+        Some(phrase) => phrase.clone(),
+        None => std::env::var(env)
+            .map_err(|_| eyre::eyre!("[{section}] has no PHRASE and {env} is not set"))?,
+    };
+    let seed = seed(&phrase, &mnemonic_config.passphrase)?;
+
+    expand_paths(&mnemonic_config.path)?
+        .iter()
+        .map(|path| derive_signer(&seed, path))
+        .collect()
+}
+
+pub fn init_mnemonic(mnemonic_config: Option<&MnemonicConfig>) -> eyre::Result<()> {
+    let Some(mnemonic_config) = mnemonic_config else {
+        return Ok(());
+    };
+
+    let wallets: Vec<_> = derive_signers(mnemonic_config, "mnemonic", MNEMONIC_ENV)?
+        .into_iter()
+        .map(|signer| {
+            register_signer(&signer);
+            Arc::new(EthereumWallet::new(signer))
+        })
+        .collect();
+    tracing::info!(
+        "Derived {} wallets from {}",
+        wallets.len(),
+        mnemonic_config.path
+    );
+
+    DERIVED
+        .set(wallets)
+        .map_err(|_| eyre::eyre!("Mnemonic wallets are already derived"))
+}
+
+pub fn derived_wallets() -> &'static [Arc<EthereumWallet>] {
+    DERIVED.get().map_or(&[], Vec::as_slice)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy::primitives::address;
+
+    use super::*;
+
+    #[test]
+    fn derives_the_well_known_test_accounts() {
+        let seed = seed(
+            "test test test test test test test test test test test junk",
+            "",
+        )
+        .unwrap();
+        let paths = expand_paths("m/44'/60'/0'/0/{0..1}").unwrap();
+        let addresses: Vec<_> = paths
+            .iter()
+            .map(|path| derive_signer(&seed, path).unwrap().address())
+            .collect();
+
+        assert_eq!(
+            addresses,
+            [
+                address!("f39Fd6e51aad88F6F4ce6aB8827279cffFb92266"),
+                address!("70997970C51812dc3A010C7d01b50e0d17dc79C8"),
+            ]
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{b256, keccak256, Address, B256, U256},
+    providers::Provider,
+    sol,
+    transports::Transport,
+};
+
+use crate::{
+    campaign::campaign,
+    claimer::{get_token_balance, TokenDistributor},
+    config::Config,
+};
+
+// Every nonce this process broadcast a transaction at, per wallet
+static OWN_NONCES: LazyLock<Mutex<HashMap<Address, HashSet<u64>>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+// bytes32(uint256(keccak256("eip1967.proxy.implementation")) - 1)
+const IMPLEMENTATION_SLOT: B256 =
+    b256!("360894a13ba1a3210667c828492db98dca3e2076cc3735a920a3ca505d382bbc");
+
+sol! {
+    #[sol(rpc)]
+    contract Ownable {
+        function owner() external view returns (address);
+        function paused() external view returns (bool);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistributorState {
+    owner: Option<Address>,
+    paused: Option<bool>,
+    code_hash: B256,
+    implementation: B256,
+    merkle_root: Option<B256>,
+    balance: U256,
+}
+
+pub async fn read_distributor_state<P, T>(provider: Arc<P>) -> eyre::Result<DistributorState>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let distributor = campaign().distributor;
+    let ownable = Ownable::new(distributor, provider.clone());
+
+    // Distributors that aren't Ownable or Pausable simply don't report those fields
+    let owner = ownable.owner().call().await.map(|owner| owner._0).ok();
+    let paused = ownable.paused().call().await.map(|paused| paused._0).ok();
+    let code_hash = keccak256(provider.get_code_at(distributor).await?);
+    let implementation = provider
+        .get_storage_at(distributor, IMPLEMENTATION_SLOT.into())
+        .await?
+        .into();
+    let merkle_root = TokenDistributor::new(distributor, provider.clone())
+        .merkleRoot()
+        .call()
+        .await
+        .map(|root| root._0)
+        .ok();
+    let balance = get_token_balance(provider, distributor, campaign().token).await?;
+
+    Ok(DistributorState {
+        owner,
+        paused,
+        code_hash,
+        implementation,
+        merkle_root,
+        balance,
+    })
+}
+
+// Compares against the previous observation, every returned line is a reason to stop claiming
+pub fn incidents(
+    previous: &DistributorState,
+    current: &DistributorState,
+    config: &Config,
+) -> Vec<String> {
+    let mut incidents = vec![];
+
+    if current.paused == Some(true) {
+        incidents.push(String::from("distributor is paused"));
+    }
+    if current.owner != previous.owner {
+        incidents.push(format!(
+            "owner changed from {:?} to {:?}",
+            previous.owner, current.owner
+        ));
+    }
+    if current.code_hash != previous.code_hash || current.implementation != previous.implementation
+    {
+        incidents.push(String::from("distributor code or implementation changed"));
+    }
+    if current.merkle_root != previous.merkle_root {
+        incidents.push(format!(
+            "merkle root changed from {:?} to {:?}",
+            previous.merkle_root, current.merkle_root
+        ));
+    }
+    if current.balance.is_zero() && !previous.balance.is_zero() {
+        incidents.push(String::from("distributor holds no tokens anymore"));
+    } else if current.balance < previous.balance {
+        let outflow = previous.balance - current.balance;
+        let limit = previous.balance * U256::from(config.distributor_outflow_alert_percent)
+            / U256::from(100);
+        if outflow > limit {
+            incidents.push(format!(
+                "{outflow} tokens left the distributor since the last check, above {}% of its balance",
+                config.distributor_outflow_alert_percent
+            ));
+        }
+    }
+
+    incidents
+}
+
+pub fn record_own_nonce(address: Address, nonce: u64) {
+    OWN_NONCES
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(address)
+        .or_default()
+        .insert(nonce);
+}
+
+// Nonces used between two observations that this process never sent a transaction at
+pub fn foreign_nonces(address: Address, previous: u64, current: u64) -> Vec<u64> {
+    let own_nonces = OWN_NONCES.lock().unwrap_or_else(|e| e.into_inner());
+    let own = own_nonces.get(&address);
+
+    (previous..current)
+        .filter(|nonce| !own.is_some_and(|own| own.contains(nonce)))
+        .collect()
+}
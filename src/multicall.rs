@@ -0,0 +1,113 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{Address, Bytes, U256},
+    providers::Provider,
+    sol,
+    sol_types::SolCall,
+    transports::Transport,
+};
+
+use Multicall3::Call3;
+
+use crate::{
+    campaign::campaign,
+    claimer::{TokenDistributor, IERC20},
+    constants::MULTICALL3_ADDRESS,
+};
+
+// Stays well below the gas cap public RPCs put on a single eth_call
+const CLAIM_STATUS_BATCH_SIZE: usize = 500;
+
+sol! {
+    #[sol(rpc)]
+    contract Multicall3 {
+        struct Call3 {
+            address target;
+            bool allowFailure;
+            bytes callData;
+        }
+
+        struct Result {
+            bool success;
+            bytes returnData;
+        }
+
+        function aggregate3(Call3[] calldata calls) external payable returns (Result[] memory returnData);
+        function getEthBalance(address addr) external view returns (uint256 balance);
+    }
+}
+
+pub fn call<C: SolCall>(target: Address, call: &C) -> Call3 {
+    Call3 {
+        target,
+        allowFailure: true,
+        callData: call.abi_encode().into(),
+    }
+}
+
+pub fn decode<C: SolCall>(data: &Option<Bytes>) -> Option<C::Return> {
+    C::abi_decode_returns(data.as_ref()?, true).ok()
+}
+
+// Runs all calls in one eth_call, failed calls come back as None
+pub async fn aggregate<P, T>(provider: &P, calls: Vec<Call3>) -> eyre::Result<Vec<Option<Bytes>>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let multicall = Multicall3::new(MULTICALL3_ADDRESS, provider);
+    let results = multicall.aggregate3(calls).call().await?.returnData;
+
+    Ok(results
+        .into_iter()
+        .map(|result| result.success.then_some(result.returnData))
+        .collect())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct ClaimStatus {
+    pub claimed: bool,
+    pub balance: U256,
+}
+
+// Wallets whose reads failed within a batch come back as None
+pub async fn read_claim_statuses<P, T>(
+    provider: &P,
+    addresses: &[Address],
+) -> eyre::Result<Vec<Option<ClaimStatus>>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let mut statuses = Vec::with_capacity(addresses.len());
+
+    for batch in addresses.chunks(CLAIM_STATUS_BATCH_SIZE) {
+        let calls = batch
+            .iter()
+            .flat_map(|address| {
+                [
+                    call(
+                        campaign().distributor,
+                        &TokenDistributor::hasClaimedCall { user: *address },
+                    ),
+                    call(
+                        campaign().token,
+                        &IERC20::balanceOfCall { account: *address },
+                    ),
+                ]
+            })
+            .collect();
+        let results = aggregate(provider, calls).await?;
+
+        statuses.extend(results.chunks(2).map(|results| {
+            let claimed = decode::<TokenDistributor::hasClaimedCall>(&results[0])?.claimed;
+            // The token may not be deployed yet while claims are still pending
+            let balance = decode::<IERC20::balanceOfCall>(&results[1])
+                .map(|balance| balance._0)
+                .unwrap_or_default();
+            Some(ClaimStatus { claimed, balance })
+        }));
+    }
+
+    Ok(statuses)
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use alloy::{network::Ethereum, primitives::Address, providers::Provider, transports::Transport};
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
+
+#[derive(Default)]
+struct Sender {
+    // Held while a nonce is picked and until a node accepts it, not through the receipt wait
+    assigning: Arc<AsyncMutex<()>>,
+    // After the transactions this process broadcast, which the chain or a lagging RPC of the pool
+    // may not count yet
+    next: Option<u64>,
+    // Nonces of accepted transactions that aren't mined or abandoned yet
+    in_flight: BTreeSet<u64>,
+}
+
+static SENDERS: LazyLock<Mutex<HashMap<Address, Sender>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn with_sender<R>(address: Address, f: impl FnOnce(&mut Sender) -> R) -> R {
+    f(SENDERS
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .entry(address)
+        .or_default())
+}
+
+async fn assign(address: Address) -> OwnedMutexGuard<()> {
+    with_sender(address, |sender| sender.assigning.clone())
+        .lock_owned()
+        .await
+}
+
+// A node accepted a transaction of `address` at `nonce`, the next send takes the nonce after it
+pub fn sent(address: Address, nonce: u64) {
+    with_sender(address, |sender| {
+        sender.next = Some(sender.next.unwrap_or_default().max(nonce + 1));
+        sender.in_flight.insert(nonce);
+    });
+}
+
+pub fn settled(address: Address, nonce: u64) {
+    with_sender(address, |sender| {
+        sender.in_flight.remove(&nonce);
+    });
+}
+
+// Only a cancel may land at the nonce now, so later sends go by what the chain counts again
+pub fn abandoned(address: Address, nonce: u64) {
+    with_sender(address, |sender| {
+        sender.in_flight.remove(&nonce);
+        sender.next = sender.in_flight.last().map(|nonce| nonce + 1);
+    });
+}
+
+pub struct NonceLease {
+    address: Address,
+    nonce: u64,
+    assigning: Option<OwnedMutexGuard<()>>,
+    in_flight: bool,
+}
+
+impl NonceLease {
+    pub fn nonce(&self) -> u64 {
+        self.nonce
+    }
+
+    // The nonce is taken once a transaction carrying it reached a node, bumps reuse it while the
+    // sender's next transaction goes out with the following one
+    pub fn broadcast(&mut self) {
+        if !self.in_flight {
+            sent(self.address, self.nonce);
+            self.in_flight = true;
+        }
+        self.assigning = None;
+    }
+
+    // The chain already used our nonce, whatever it counts now comes after it
+    pub async fn refresh<P, T>(&mut self, provider: &P, address: Address) -> eyre::Result<u64>
+    where
+        P: Provider<T, Ethereum>,
+        T: Transport + Clone,
+    {
+        if self.assigning.is_none() {
+            self.assigning = Some(assign(address).await);
+        }
+        if std::mem::take(&mut self.in_flight) {
+            settled(address, self.nonce);
+        }
+
+        let pending = provider.get_transaction_count(address).pending().await?;
+        let next = with_sender(address, |sender| sender.next).unwrap_or_default();
+        self.nonce = pending.max(self.nonce + 1).max(next);
+
+        Ok(self.nonce)
+    }
+
+    pub fn abandon(mut self) {
+        if std::mem::take(&mut self.in_flight) {
+            abandoned(self.address, self.nonce);
+        }
+    }
+}
+
+impl Drop for NonceLease {
+    fn drop(&mut self) {
+        if self.in_flight {
+            settled(self.address, self.nonce);
+        }
+    }
+}
+
+pub async fn lease<P, T>(provider: &P, address: Address) -> eyre::Result<NonceLease>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let assigning = assign(address).await;
+
+    let pending = provider.get_transaction_count(address).pending().await?;
+    let next = with_sender(address, |sender| sender.next);
+    let nonce = next.map_or(pending, |next| next.max(pending));
+
+    Ok(NonceLease {
+        address,
+        nonce,
+        assigning: Some(assigning),
+        in_flight: false,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn next(address: Address) -> Option<u64> {
+        with_sender(address, |sender| sender.next)
+    }
+
+    #[test]
+    fn abandoning_a_nonce_closes_the_gap_it_left() {
+        let address = Address::with_last_byte(0xa1);
+        sent(address, 4);
+        sent(address, 5);
+        settled(address, 4);
+        assert_eq!(next(address), Some(6));
+
+        // Nonce 5 still waits for its receipt, so nothing below it is reused
+        sent(address, 6);
+        abandoned(address, 6);
+        assert_eq!(next(address), Some(6));
+
+        abandoned(address, 5);
+        assert_eq!(next(address), None);
+    }
+}
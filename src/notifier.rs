@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{utils::format_ether, Address, U256};
+use reqwest::Client;
+use serde_json::json;
+
+use crate::{
+    config::{Config, NotifyConfig},
+    metrics,
+};
+
+const NOTIFY_TIMEOUT: Duration = Duration::from_secs(10);
+// Telegram rejects longer messages, Discord stops at 2000 characters
+const MAX_MESSAGE_LEN: usize = 2000;
+
+static CLIENT: LazyLock<Client> = LazyLock::new(|| {
+    Client::builder()
+        .timeout(NOTIFY_TIMEOUT)
+        .build()
+        .expect("Notification client to build")
+});
+
+async fn send_telegram(bot_token: &str, chat_id: &str, text: &str) -> eyre::Result<()> {
+    CLIENT
+        .post(format!(
+            "https://api.telegram.org/bot{bot_token}/sendMessage"
+        ))
+        .json(&json!({ "chat_id": chat_id, "text": text }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+async fn send_discord(webhook_url: &str, text: &str) -> eyre::Result<()> {
+    CLIENT
+        .post(webhook_url)
+        .json(&json!({ "content": text }))
+        .send()
+        .await?
+        .error_for_status()?;
+
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Channel {
+    Telegram,
+    Discord,
+}
+
+fn channels(notify_config: &NotifyConfig) -> Vec<Channel> {
+    let mut channels = vec![];
+    if notify_config.telegram_bot_token.is_some() {
+        channels.push(Channel::Telegram);
+    }
+    if notify_config.discord_webhook_url.is_some() {
+        channels.push(Channel::Discord);
+    }
+
+    channels
+}
+
+fn digest_interval(notify_config: &NotifyConfig, channel: Channel) -> Option<Duration> {
+    let hours = match channel {
+        Channel::Telegram => notify_config.telegram_digest_hours,
+        Channel::Discord => notify_config.discord_digest_hours,
+    };
+
+    hours.map(|hours| Duration::from_secs(hours * 3600))
+}
+
+async fn deliver(notify_config: &NotifyConfig, channel: Channel, text: &str) {
+    let text: String = text.chars().take(MAX_MESSAGE_LEN).collect();
+
+    let sent = match channel {
+        Channel::Telegram => match (
+            &notify_config.telegram_bot_token,
+            &notify_config.telegram_chat_id,
+        ) {
+            (Some(bot_token), Some(chat_id)) => send_telegram(bot_token, chat_id, &text).await,
+            _ => Ok(()),
+        },
+        Channel::Discord => match &notify_config.discord_webhook_url {
+            Some(webhook_url) => send_discord(webhook_url, &text).await,
+            None => Ok(()),
+        },
+    };
+
+    if let Err(e) = sent {
+        tracing::warn!("Failed to send {channel:?} notification: {e}");
+    }
+}
+
+// Notifications are best effort, a dead chat must never stop a run
+pub async fn notify(config: &Config, text: impl AsRef<str>) {
+    let Some(notify_config) = &config.notify else {
+        return;
+    };
+
+    for channel in channels(notify_config) {
+        deliver(notify_config, channel, text.as_ref()).await;
+    }
+}
+
+// Events of long running modes only go to channels that don't get digests instead
+pub async fn notify_event(config: &Config, text: impl AsRef<str>) {
+    let Some(notify_config) = &config.notify else {
+        return;
+    };
+
+    for channel in channels(notify_config) {
+        if digest_interval(notify_config, channel).is_none() {
+            deliver(notify_config, channel, text.as_ref()).await;
+        }
+    }
+}
+
+struct DigestWindow {
+    interval: Duration,
+    started: Instant,
+    gas_spent_before: u128,
+    processed: usize,
+    failures: Vec<Address>,
+}
+
+impl DigestWindow {
+    fn new(interval: Duration) -> Self {
+        Self {
+            interval,
+            started: Instant::now(),
+            gas_spent_before: metrics::gas_spent(),
+            processed: 0,
+            failures: vec![],
+        }
+    }
+
+    fn render(&self, pending: usize) -> String {
+        let gas_spent = metrics::gas_spent() - self.gas_spent_before;
+        let mut text = format!(
+            "Digest of the last {}h: {} wallets processed, {pending} pending, {} failures, {} ETH gas spent",
+            self.interval.as_secs() / 3600,
+            self.processed,
+            self.failures.len(),
+            format_ether(U256::from(gas_spent))
+        );
+        if !self.failures.is_empty() {
+            text += &format!("\nFailed wallets: {:?}", self.failures);
+        }
+
+        text
+    }
+}
+
+// Every channel keeps its own window, so channels with different intervals see complete counts
+pub struct Digests {
+    windows: HashMap<Channel, DigestWindow>,
+    pending: usize,
+}
+
+impl Digests {
+    pub fn new(config: &Config) -> Self {
+        let windows = config
+            .notify
+            .iter()
+            .flat_map(|notify_config| {
+                channels(notify_config).into_iter().filter_map(|channel| {
+                    let interval = digest_interval(notify_config, channel)?;
+                    Some((channel, DigestWindow::new(interval)))
+                })
+            })
+            .collect();
+
+        Self {
+            windows,
+            pending: 0,
+        }
+    }
+
+    pub fn record_processed(&mut self) {
+        for window in self.windows.values_mut() {
+            window.processed += 1;
+        }
+    }
+
+    pub fn record_failure(&mut self, address: Address) {
+        for window in self.windows.values_mut() {
+            window.failures.push(address);
+        }
+    }
+
+    pub fn set_pending(&mut self, pending: usize) {
+        self.pending = pending;
+    }
+
+    pub async fn send_due(&mut self, config: &Config) {
+        let Some(notify_config) = &config.notify else {
+            return;
+        };
+
+        for (channel, window) in self.windows.iter_mut() {
+            if window.started.elapsed() < window.interval {
+                continue;
+            }
+
+            deliver(notify_config, *channel, &window.render(self.pending)).await;
+            *window = DigestWindow::new(window.interval);
+        }
+    }
+}
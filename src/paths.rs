@@ -0,0 +1,93 @@
+use std::{
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::constants::{
+    CONFIG_FILE_PATH, PRIVATE_KEYS_FILE_PATH, PROFILES_FOLDER_PATH, RECIPIENTS_FILE_PATH,
+    WALLETS_FILE_PATH,
+};
+
+static PATHS: OnceLock<Paths> = OnceLock::new();
+
+struct Paths {
+    config: PathBuf,
+    private_keys: PathBuf,
+    recipients: PathBuf,
+    wallets: PathBuf,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Self {
+            config: PathBuf::from(CONFIG_FILE_PATH),
+            private_keys: PathBuf::from(PRIVATE_KEYS_FILE_PATH),
+            recipients: PathBuf::from(RECIPIENTS_FILE_PATH),
+            wallets: PathBuf::from(WALLETS_FILE_PATH),
+        }
+    }
+}
+
+pub fn init_paths(
+    config: Option<PathBuf>,
+    private_keys: Option<PathBuf>,
+    recipients: Option<PathBuf>,
+    wallets: Option<PathBuf>,
+) {
+    let defaults = Paths::default();
+
+    // Explicit paths are relative to where the claimer was started, not to the profile
+    let [config, private_keys, recipients, wallets] = [config, private_keys, recipients, wallets]
+        .map(|path| path.map(|path| std::path::absolute(&path).unwrap_or(path)));
+
+    PATHS.get_or_init(|| Paths {
+        config: config.unwrap_or(defaults.config),
+        private_keys: private_keys.unwrap_or(defaults.private_keys),
+        recipients: recipients.unwrap_or(defaults.recipients),
+        wallets: wallets.unwrap_or(defaults.wallets),
+    });
+}
+
+fn paths() -> &'static Paths {
+    PATHS.get_or_init(Paths::default)
+}
+
+pub fn config_path() -> &'static Path {
+    &paths().config
+}
+
+pub fn private_keys_path() -> &'static Path {
+    &paths().private_keys
+}
+
+pub fn recipients_path() -> &'static Path {
+    &paths().recipients
+}
+
+pub fn wallets_path() -> &'static Path {
+    &paths().wallets
+}
+
+// Every data/ path is relative, so a profile is simply a directory holding its own data/
+pub fn enter_profile(name: &str) -> eyre::Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        eyre::bail!("Profile name {name:?} may only contain letters, digits, '-' and '_'");
+    }
+
+    let dir = Path::new(PROFILES_FOLDER_PATH).join(name);
+    if !dir.join(CONFIG_FILE_PATH).exists() {
+        eyre::bail!(
+            "Profile {name} has no {}",
+            dir.join(CONFIG_FILE_PATH).display()
+        );
+    }
+
+    let dir = std::path::absolute(dir)?;
+    std::env::set_current_dir(&dir)?;
+
+    Ok(dir)
+}
@@ -0,0 +1,80 @@
+use std::sync::Arc;
+
+use alloy::{
+    network::{Ethereum, NetworkWallet},
+    primitives::{
+        utils::{format_units, parse_units},
+        Address, U256,
+    },
+    providers::Provider,
+    transports::Transport,
+};
+
+use crate::{
+    claimer::{transfer_token, IERC20},
+    config::Config,
+    journal::{self, Event},
+    sell::sell,
+};
+
+pub async fn pay_out_stablecoin<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    amount_in: U256,
+    recipient: Address,
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let payout_config = config
+        .payout
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[payout] section is missing from the config"))?;
+    let address = wallet.default_signer_address();
+
+    let stablecoin = IERC20::new(payout_config.stablecoin, provider.clone());
+    let decimals = stablecoin.decimals().call().await?._0;
+    let balance_before = stablecoin.balanceOf(address).call().await?._0;
+
+    sell(
+        provider.clone(),
+        wallet.clone(),
+        amount_in,
+        payout_config.stablecoin,
+        address,
+        config,
+    )
+    .await?;
+
+    let balance_after = stablecoin.balanceOf(address).call().await?._0;
+    let proceeds = balance_after.saturating_sub(balance_before);
+    let minimum = parse_units(&payout_config.min_amount.to_string(), decimals)?.get_absolute();
+
+    let formatted = format_units(proceeds, decimals)?;
+
+    if proceeds < minimum {
+        let reason = format!(
+            "stablecoin proceeds {formatted} below payout minimum {}",
+            payout_config.min_amount
+        );
+        tracing::warn!("Keeping proceeds on {address}: {reason}");
+        journal::record(Event::Skip { address, reason });
+        return Ok(());
+    }
+
+    tracing::info!("Paying out {formatted} stablecoin from {address} to {recipient}");
+    transfer_token(
+        provider,
+        wallet,
+        payout_config.stablecoin,
+        recipient,
+        proceeds,
+        config,
+    )
+    .await?;
+
+    Ok(())
+}
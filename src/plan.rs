@@ -0,0 +1,277 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    claimer::is_dry_run,
+    config::{Config, PayoutMode},
+    confirm,
+    constants::PLAN_FILE_PATH,
+    prefetch,
+    proof::{known_allocation, ProofResponse},
+    seal::{read_sealed, write_sealed},
+    token::format_token,
+};
+
+static ACCEPT_CHANGES: AtomicBool = AtomicBool::new(false);
+
+pub fn accept_changes() {
+    ACCEPT_CHANGES.store(true, Ordering::Relaxed);
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PlanEntry {
+    pub wallet: Address,
+    pub recipient: Address,
+    #[serde(default)]
+    pub allocation: Option<U256>,
+    #[serde(default)]
+    pub payout: PayoutMode,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Plan {
+    pub entries: Vec<PlanEntry>,
+}
+
+impl Plan {
+    pub async fn build(targets: &[(Arc<EthereumWallet>, Address)], config: &Config) -> Self {
+        let mut entries = Vec::with_capacity(targets.len());
+        for (wallet, recipient) in targets {
+            let wallet =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+            let allocation = match prefetch::peek(wallet) {
+                Some(ProofResponse::Eligible { amount, .. }) => Some(amount),
+                Some(ProofResponse::Ineligible) => None,
+                None => known_allocation(wallet).await,
+            };
+            entries.push(PlanEntry {
+                wallet,
+                recipient: *recipient,
+                allocation,
+                payout: config.payout_mode(),
+            });
+        }
+
+        Self { entries }
+    }
+
+    pub async fn load_previous() -> eyre::Result<Option<Self>> {
+        match read_sealed(PLAN_FILE_PATH).await? {
+            Some(plan_str) => Ok(Some(serde_json::from_str(&plan_str)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub async fn persist(&self) -> eyre::Result<()> {
+        write_sealed(PLAN_FILE_PATH, &serde_json::to_string_pretty(self)?).await
+    }
+
+    // Allocations only count as changed when both plans know them, proofs may not be fetched yet
+    pub fn diff(&self, previous: &Plan) -> Vec<String> {
+        let previous_positions: HashMap<_, _> = previous
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(position, entry)| (entry.wallet, (position, entry)))
+            .collect();
+
+        let mut changes = vec![];
+
+        for (position, entry) in self.entries.iter().enumerate() {
+            let Some((old_position, old)) = previous_positions.get(&entry.wallet) else {
+                changes.push(format!("+ {} -> {}", entry.wallet, entry.recipient));
+                continue;
+            };
+
+            if old.recipient != entry.recipient {
+                changes.push(format!(
+                    "~ {}: recipient {} -> {}",
+                    entry.wallet, old.recipient, entry.recipient
+                ));
+            }
+            if let (Some(old_allocation), Some(allocation)) = (old.allocation, entry.allocation) {
+                if old_allocation != allocation {
+                    changes.push(format!(
+                        "~ {}: allocation {} -> {}",
+                        entry.wallet,
+                        format_token(old_allocation),
+                        format_token(allocation)
+                    ));
+                }
+            }
+            if old.payout != entry.payout {
+                changes.push(format!(
+                    "~ {}: payout {:?} -> {:?}",
+                    entry.wallet, old.payout, entry.payout
+                ));
+            }
+            if *old_position != position {
+                changes.push(format!(
+                    "~ {}: position {} -> {}",
+                    entry.wallet,
+                    old_position + 1,
+                    position + 1
+                ));
+            }
+        }
+
+        for entry in &previous.entries {
+            if !self.entries.iter().any(|new| new.wallet == entry.wallet) {
+                changes.push(format!("- {} -> {}", entry.wallet, entry.recipient));
+            }
+        }
+
+        changes
+    }
+}
+
+// A changed plan moves tokens somewhere the previous run didn't, so it is only persisted once
+// it's accepted with --accept-plan or at the --interactive prompt
+pub async fn review_plan(plan: &Plan) -> eyre::Result<()> {
+    match Plan::load_previous().await? {
+        Some(previous) => {
+            let changes = plan.diff(&previous);
+
+            if changes.is_empty() {
+                tracing::info!("Plan is unchanged since the previous run");
+            } else {
+                tracing::warn!(
+                    "Plan changed since the previous run ({} changes):",
+                    changes.len()
+                );
+                for change in changes {
+                    tracing::warn!("{change}");
+                }
+
+                if ACCEPT_CHANGES.load(Ordering::Relaxed) {
+                    tracing::info!("Plan changes accepted with --accept-plan");
+                } else if !confirm::is_enabled() {
+                    eyre::bail!("Plan changed since the previous run, review the changes above and rerun with --accept-plan");
+                } else if !confirm::ask(String::from("Accept the changed plan?")).await? {
+                    eyre::bail!("Plan changes were not accepted");
+                }
+            }
+        }
+        None => tracing::info!(
+            "No previous plan found, {} wallets planned",
+            plan.entries.len()
+        ),
+    }
+
+    if is_dry_run() {
+        return Ok(());
+    }
+
+    plan.persist().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::campaign::init_campaign;
+
+    fn entry(wallet: u8, recipient: u8) -> PlanEntry {
+        PlanEntry {
+            wallet: Address::with_last_byte(wallet),
+            recipient: Address::with_last_byte(recipient),
+            allocation: None,
+            payout: PayoutMode::Token,
+        }
+    }
+
+    fn plan(entries: Vec<PlanEntry>) -> Plan {
+        Plan { entries }
+    }
+
+    #[test]
+    fn same_plan_has_no_changes() {
+        let previous = plan(vec![entry(1, 11), entry(2, 12)]);
+        assert!(plan(previous.entries.clone()).diff(&previous).is_empty());
+    }
+
+    #[test]
+    fn reports_added_and_removed_wallets() {
+        let previous = plan(vec![entry(1, 11), entry(2, 12)]);
+        let current = plan(vec![entry(1, 11), entry(3, 13)]);
+
+        assert_eq!(
+            current.diff(&previous),
+            vec![
+                format!(
+                    "+ {} -> {}",
+                    Address::with_last_byte(3),
+                    Address::with_last_byte(13)
+                ),
+                format!(
+                    "- {} -> {}",
+                    Address::with_last_byte(2),
+                    Address::with_last_byte(12)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn reports_recipient_changes() {
+        let previous = plan(vec![entry(1, 11)]);
+        let current = plan(vec![entry(1, 21)]);
+
+        assert_eq!(
+            current.diff(&previous),
+            vec![format!(
+                "~ {}: recipient {} -> {}",
+                Address::with_last_byte(1),
+                Address::with_last_byte(11),
+                Address::with_last_byte(21)
+            )]
+        );
+    }
+
+    #[test]
+    fn reports_reordered_wallets() {
+        let previous = plan(vec![entry(1, 11), entry(2, 12)]);
+        let current = plan(vec![entry(2, 12), entry(1, 11)]);
+
+        assert_eq!(
+            current.diff(&previous),
+            vec![
+                format!("~ {}: position 2 -> 1", Address::with_last_byte(2)),
+                format!("~ {}: position 1 -> 2", Address::with_last_byte(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn allocations_only_change_when_both_are_known() {
+        let _ = init_campaign("scroll");
+
+        let mut previous = entry(1, 11);
+        let mut current = entry(1, 11);
+        current.allocation = Some(U256::from(5));
+        assert!(plan(vec![current.clone()])
+            .diff(&plan(vec![previous.clone()]))
+            .is_empty());
+
+        previous.allocation = Some(U256::from(3));
+        assert_eq!(
+            plan(vec![current]).diff(&plan(vec![previous])),
+            vec![format!(
+                "~ {}: allocation {} -> {}",
+                Address::with_last_byte(1),
+                format_token(U256::from(3)),
+                format_token(U256::from(5))
+            )]
+        );
+    }
+}
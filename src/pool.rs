@@ -0,0 +1,288 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::Ethereum,
+    providers::{Provider, ProviderBuilder, RootProvider},
+    rpc::{client::ClientBuilder, types::SyncStatus},
+    transports::{http::Http, layers::RetryBackoffLayer, ws::WsConnect, BoxTransport, Transport},
+};
+use eyre::WrapErr;
+use rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng, Rng};
+
+use crate::{
+    campaign::chain, config::Config, discovery::discover_rpc_urls, proof::client_for,
+    rate_limit::RateLimitLayer, token::reconcile_token, usage::UsageLayer,
+};
+
+// Boxed so HTTP and WebSocket endpoints share one pool, a boxed pubsub frontend still lets
+// pending transactions follow newHeads subscriptions instead of polling
+pub type RpcTransport = BoxTransport;
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct ProviderPool<P> {
+    urls: Vec<String>,
+    providers: Vec<Arc<P>>,
+    heads: Vec<AtomicU64>,
+    healthy: Vec<AtomicBool>,
+    max_head_lag: u64,
+    max_latency: Duration,
+}
+
+impl<P> ProviderPool<P> {
+    pub fn new(
+        entries: Vec<(String, Arc<P>)>,
+        max_head_lag: u64,
+        max_latency: Duration,
+    ) -> Arc<Self> {
+        let heads = entries.iter().map(|_| AtomicU64::new(0)).collect();
+        let healthy = entries.iter().map(|_| AtomicBool::new(true)).collect();
+        let (urls, providers) = entries.into_iter().unzip();
+
+        Arc::new(Self {
+            urls,
+            providers,
+            heads,
+            healthy,
+            max_head_lag,
+            max_latency,
+        })
+    }
+
+    fn median_head(&self) -> u64 {
+        let mut heads: Vec<_> = self
+            .heads
+            .iter()
+            .map(|head| head.load(Ordering::Relaxed))
+            .filter(|head| *head != 0)
+            .collect();
+
+        if heads.is_empty() {
+            return 0;
+        }
+
+        heads.sort_unstable();
+        heads[heads.len() / 2]
+    }
+
+    fn is_fresh(&self, index: usize, median: u64) -> bool {
+        self.heads[index].load(Ordering::Relaxed) + self.max_head_lag >= median
+    }
+
+    fn is_usable(&self, index: usize, median: u64) -> bool {
+        self.healthy[index].load(Ordering::Relaxed) && self.is_fresh(index, median)
+    }
+
+    fn choose_among(&self, rng: &mut ThreadRng, excluded: Option<&Arc<P>>) -> Option<Arc<P>> {
+        let median = self.median_head();
+        let usable: Vec<_> = (0..self.providers.len())
+            .filter(|index| self.is_usable(*index, median))
+            .filter(|index| {
+                excluded.is_none_or(|excluded| !Arc::ptr_eq(excluded, &self.providers[*index]))
+            })
+            .collect();
+
+        usable
+            .choose(rng)
+            .map(|index| self.providers[*index].clone())
+    }
+
+    pub fn endpoints(&self) -> impl Iterator<Item = (&str, &Arc<P>)> {
+        self.urls.iter().map(String::as_str).zip(&self.providers)
+    }
+
+    pub fn choose(&self, rng: &mut ThreadRng) -> Arc<P> {
+        self.choose_among(rng, None).unwrap_or_else(|| {
+            let index = rng.gen_range(0..self.providers.len());
+            self.providers[index].clone()
+        })
+    }
+
+    // Failed tasks are retried elsewhere, the same node is only reused when it's the last one left
+    pub fn choose_other(&self, rng: &mut ThreadRng, failed: &Arc<P>) -> Arc<P> {
+        self.choose_among(rng, Some(failed))
+            .unwrap_or_else(|| self.choose(rng))
+    }
+}
+
+async fn check_health<P, T>(provider: &P) -> eyre::Result<(u64, Duration)>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let started = Instant::now();
+    let chain_id = provider.get_chain_id().await?;
+    let latency = started.elapsed();
+
+    if chain_id != chain().chain_id {
+        eyre::bail!("chain id {chain_id}, expected {}", chain().chain_id);
+    }
+
+    // Some public endpoints don't serve eth_syncing, only a node that says it's syncing fails
+    if let Ok(SyncStatus::Info(info)) = provider.syncing().await {
+        eyre::bail!(
+            "still syncing at block {} of {}",
+            info.current_block,
+            info.highest_block
+        );
+    }
+
+    Ok((provider.get_block_number().await?, latency))
+}
+
+impl<P> ProviderPool<P>
+where
+    P: Send + Sync + 'static,
+{
+    pub async fn refresh_health<T>(&self)
+    where
+        P: Provider<T, Ethereum>,
+        T: Transport + Clone,
+    {
+        let median_before = self.median_head();
+        let was_fresh: Vec<_> = (0..self.providers.len())
+            .map(|index| self.is_fresh(index, median_before))
+            .collect();
+
+        for (index, provider) in self.providers.iter().enumerate() {
+            let url = &self.urls[index];
+            let check = tokio::time::timeout(HEALTH_CHECK_TIMEOUT, check_health(provider.as_ref()));
+            let (head, healthy) = match check.await {
+                Ok(Ok((head, latency))) if latency > self.max_latency => {
+                    tracing::warn!(
+                        "{url} answered eth_chainId in {latency:?}, above MAX_RPC_LATENCY"
+                    );
+                    (head, false)
+                }
+                Ok(Ok((head, _))) => (head, true),
+                Ok(Err(e)) => {
+                    tracing::warn!("Health check of {url} failed: {e}");
+                    (0, false)
+                }
+                Err(_) => {
+                    tracing::warn!("Health check of {url} timed out");
+                    (0, false)
+                }
+            };
+            self.heads[index].store(head, Ordering::Relaxed);
+
+            match (
+                self.healthy[index].swap(healthy, Ordering::Relaxed),
+                healthy,
+            ) {
+                (true, false) => tracing::warn!("Excluding {url}: failed its health check"),
+                (false, true) => tracing::info!("{url} is healthy again, re-including"),
+                _ => {}
+            }
+        }
+
+        let median = self.median_head();
+        for (index, url) in self.urls.iter().enumerate() {
+            match (was_fresh[index], self.is_fresh(index, median)) {
+                (true, false) => tracing::warn!(
+                    "Excluding {url}: head {} lags behind pool median {median}",
+                    self.heads[index].load(Ordering::Relaxed)
+                ),
+                (false, true) => tracing::info!("{url} caught up with the pool, re-including"),
+                _ => {}
+            }
+        }
+    }
+
+    pub fn spawn_health_checker<T>(self: &Arc<Self>, interval: Duration)
+    where
+        P: Provider<T, Ethereum>,
+        T: Transport + Clone,
+    {
+        let pool = self.clone();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(interval).await;
+                pool.refresh_health().await;
+            }
+        });
+    }
+}
+
+pub fn is_websocket(rpc_url: &str) -> bool {
+    rpc_url.starts_with("ws://") || rpc_url.starts_with("wss://")
+}
+
+pub async fn init_provider_pool(
+    config: &Config,
+) -> eyre::Result<Arc<ProviderPool<impl Provider<RpcTransport, Ethereum> + 'static>>> {
+    let mut rpc_urls = config.rpc_urls.clone();
+    if config.rpc_discovery {
+        match discover_rpc_urls(&rpc_urls).await {
+            Ok(discovered) => {
+                tracing::info!("Discovered {} healthy public RPCs", discovered.len());
+                rpc_urls.extend(discovered);
+            }
+            Err(e) => tracing::warn!("RPC discovery failed: {e}"),
+        }
+    }
+
+    let retry_layer = RetryBackoffLayer::new(10, 2, 500);
+
+    let mut providers = vec![];
+    for rpc_url in rpc_urls {
+        let rate_limit = RateLimitLayer::new(&rpc_url, config.rpc_rate_limit_for(&rpc_url));
+        let client = match is_websocket(&rpc_url) {
+            // Retries and usage accounting only wrap HTTP, the pubsub backend reconnects on its own
+            true => match ClientBuilder::default()
+                .layer(rate_limit)
+                .ws(WsConnect::new(&rpc_url))
+                .await
+            {
+                Ok(client) => client.boxed(),
+                Err(e) => {
+                    tracing::warn!("Leaving out {rpc_url}: failed to connect: {e}");
+                    continue;
+                }
+            },
+            false => {
+                let http_client = client_for(config.rpc_proxy_for(&rpc_url), config)
+                    .wrap_err_with(|| format!("Invalid RPC proxy for {rpc_url}"))?;
+                let url = rpc_url
+                    .parse()
+                    .wrap_err_with(|| format!("Invalid RPC URL {rpc_url}"))?;
+                ClientBuilder::default()
+                    .layer(retry_layer.clone())
+                    .layer(UsageLayer::new(&rpc_url))
+                    .layer(rate_limit)
+                    .transport(Http::with_client(http_client, url), false)
+                    .boxed()
+            }
+        };
+
+        if let Some(poll_interval) = chain().poll_interval().filter(|_| !client.is_local()) {
+            client.set_poll_interval(poll_interval);
+        }
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .on_provider(RootProvider::new(client));
+
+        providers.push((rpc_url, Arc::new(provider)));
+    }
+    if providers.is_empty() {
+        eyre::bail!("None of the RPC_URLS could be set up");
+    }
+
+    let pool = ProviderPool::new(
+        providers,
+        config.max_head_lag,
+        Duration::from_millis(config.max_rpc_latency),
+    );
+    pool.refresh_health().await;
+    pool.spawn_health_checker(Duration::from_secs(config.head_check_interval));
+    reconcile_token(pool.choose(&mut thread_rng()).as_ref()).await;
+
+    Ok(pool)
+}
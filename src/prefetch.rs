@@ -0,0 +1,164 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, LazyLock, Mutex},
+    time::Duration,
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::Address,
+};
+use rand::thread_rng;
+use tokio::task::JoinSet;
+
+use crate::{
+    config::Config,
+    constants::PROOFS_FOLDER_PATH,
+    proof::{fetch_proof, is_rate_limited, ProofResponse},
+    utils::read_private_keys,
+};
+
+const RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(10);
+const MAX_REQUEUES: usize = 3;
+const DEFAULT_FETCH_CONCURRENCY: usize = 4;
+
+static PREFETCHED: LazyLock<Mutex<HashMap<Address, ProofResponse>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn take(address: Address) -> Option<ProofResponse> {
+    PREFETCHED.lock().unwrap().remove(&address)
+}
+
+pub fn peek(address: Address) -> Option<ProofResponse> {
+    PREFETCHED.lock().unwrap().get(&address).cloned()
+}
+
+struct Concurrency {
+    limit: usize,
+    max: usize,
+    successes: usize,
+}
+
+impl Concurrency {
+    fn on_success(&mut self) {
+        self.successes += 1;
+        if self.successes >= self.limit && self.limit < self.max {
+            self.limit += 1;
+            self.successes = 0;
+            tracing::info!(
+                "Proof fetches succeeding, raising concurrency to {}",
+                self.limit
+            );
+        }
+    }
+
+    fn on_rate_limited(&mut self) {
+        self.limit = (self.limit / 2).max(1);
+        self.successes = 0;
+        tracing::warn!(
+            "Proof API is rate limiting, lowering concurrency to {}",
+            self.limit
+        );
+    }
+}
+
+async fn run_prefetch(addresses: Vec<Address>, config: Arc<Config>, initial: usize) {
+    let total = addresses.len();
+    let mut rng = thread_rng();
+    let mut queue = VecDeque::from(addresses);
+    let mut requeues: HashMap<Address, usize> = HashMap::new();
+    let mut in_flight = JoinSet::new();
+    let mut concurrency = Concurrency {
+        limit: initial.max(1),
+        max: config.prefetch_max_concurrency.max(initial),
+        successes: 0,
+    };
+
+    tracing::info!("Prefetching {total} proofs starting at concurrency {initial}");
+
+    loop {
+        while in_flight.len() < concurrency.limit {
+            let Some(address) = queue.pop_front() else {
+                break;
+            };
+            let proxy = config.proxy_for(address, &mut rng);
+            let config = config.clone();
+
+            in_flight.spawn(async move { (address, fetch_proof(address, &proxy, &config).await) });
+        }
+
+        let Some(res) = in_flight.join_next().await else {
+            break;
+        };
+        let (address, result) = res.unwrap();
+
+        match result {
+            Ok(proof_response) => {
+                concurrency.on_success();
+                PREFETCHED.lock().unwrap().insert(address, proof_response);
+            }
+            Err(e) if is_rate_limited(&e) => {
+                concurrency.on_rate_limited();
+
+                let attempts = requeues.entry(address).or_default();
+                *attempts += 1;
+                if *attempts <= MAX_REQUEUES {
+                    queue.push_back(address);
+                }
+
+                tokio::time::sleep(RATE_LIMIT_BACKOFF).await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to prefetch the proof of {address}, it is fetched again when claiming: {e}");
+            }
+        }
+    }
+
+    tracing::info!(
+        "Prefetched {}/{total} proofs",
+        PREFETCHED.lock().unwrap().len()
+    );
+}
+
+pub async fn prefetch_proofs(addresses: Vec<Address>, config: Arc<Config>) {
+    if let Some(initial) = config.prefetch_concurrency {
+        run_prefetch(addresses, config, initial).await;
+    }
+}
+
+// Fills the proof cache ahead of the claim window, `claim` then reads every proof from disk and
+// sends its transactions without waiting on the proof API
+pub async fn fetch_proofs(config: Config) -> eyre::Result<()> {
+    let addresses: Vec<_> = read_private_keys()
+        .await?
+        .iter()
+        .map(|wallet| <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet))
+        .collect();
+    let initial = config
+        .prefetch_concurrency
+        .unwrap_or(DEFAULT_FETCH_CONCURRENCY);
+    run_prefetch(addresses.clone(), Arc::new(config), initial).await;
+
+    let prefetched = PREFETCHED.lock().unwrap();
+    let ineligible = prefetched
+        .values()
+        .filter(|proof_response| matches!(proof_response, ProofResponse::Ineligible))
+        .count();
+    let missing: Vec<_> = addresses
+        .iter()
+        .filter(|address| !prefetched.contains_key(*address))
+        .collect();
+
+    tracing::info!(
+        "{} proofs stored in {PROOFS_FOLDER_PATH}, {ineligible} wallets are not eligible",
+        prefetched.len() - ineligible
+    );
+    if !missing.is_empty() {
+        tracing::warn!(
+            "{} proofs could not be fetched, run fetch-proofs again to retry them: {missing:?}",
+            missing.len()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,149 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::Ethereum,
+    primitives::{utils::format_units, Address, U256},
+    providers::Provider,
+    transports::Transport,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    claimer::IERC20,
+    config::{Config, PriceConfig, PriceGateAction, PriceSource},
+    sell::quote,
+    token::{parse_token, token_info},
+};
+
+const DEFAULT_COINGECKO_URL: &str = "https://api.coingecko.com/api/v3/simple/price";
+const PRICE_REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+
+// One price serves every wallet for CACHE_SECS, so a run doesn't hit the feed once per wallet
+static CACHED: Mutex<Option<(f64, Instant)>> = Mutex::const_new(None);
+
+async fn coingecko_price(price_config: &PriceConfig) -> eyre::Result<f64> {
+    let url = price_config
+        .coingecko_url
+        .as_deref()
+        .unwrap_or(DEFAULT_COINGECKO_URL);
+    let id = price_config.coingecko_id.as_str();
+    let prices: HashMap<String, HashMap<String, f64>> = reqwest::Client::new()
+        .get(url)
+        .query(&[("ids", id), ("vs_currencies", "usd")])
+        .timeout(PRICE_REQUEST_TIMEOUT)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+
+    prices
+        .get(id)
+        .and_then(|price| price.get("usd"))
+        .copied()
+        .ok_or_else(|| eyre::eyre!("CoinGecko has no USD price for {id}"))
+}
+
+// Quotes one token into the USD token through the [sell] pool
+async fn dex_price<P, T>(
+    provider: &P,
+    config: &Config,
+    price_config: &PriceConfig,
+) -> eyre::Result<f64>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let sell_config = config
+        .sell
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[price] SOURCE = \"dex\" quotes through the [sell] pool"))?;
+    let usd_token: Address = price_config
+        .usd_token
+        .or(config.payout.as_ref().map(|payout| payout.stablecoin))
+        .ok_or_else(|| eyre::eyre!("[price] SOURCE = \"dex\" needs USD_TOKEN"))?;
+
+    let decimals = IERC20::new(usd_token, provider).decimals().call().await?._0;
+    let amount_out = quote(provider, sell_config, usd_token, parse_token(1.0)?).await?;
+
+    Ok(format_units(amount_out, decimals)?.parse()?)
+}
+
+pub async fn usd_price<P, T>(provider: &P, config: &Config) -> eyre::Result<Option<f64>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(price_config) = &config.price else {
+        return Ok(None);
+    };
+
+    let mut cached = CACHED.lock().await;
+    if let Some((price, fetched)) = *cached {
+        if fetched.elapsed() < Duration::from_secs(price_config.cache_secs) {
+            return Ok(Some(price));
+        }
+    }
+
+    let price = match price_config.source {
+        PriceSource::Coingecko => coingecko_price(price_config).await?,
+        PriceSource::Dex => dex_price(provider, config, price_config).await?,
+    };
+    *cached = Some((price, Instant::now()));
+
+    Ok(Some(price))
+}
+
+pub fn usd_value(amount: U256, price: f64) -> f64 {
+    let amount: f64 = format_units(amount, token_info().decimals)
+        .ok()
+        .and_then(|amount| amount.parse().ok())
+        .unwrap_or_default();
+
+    amount * price
+}
+
+// Returns false when the wallet should keep its tokens for now, BELOW_MIN = "skip"
+pub async fn wait_for_min_price<P, T>(
+    provider: &P,
+    config: &Config,
+    address: Address,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(price_config) = &config.price else {
+        return Ok(true);
+    };
+    let Some(minimum) = price_config.min_token_price_usd else {
+        return Ok(true);
+    };
+
+    loop {
+        let price = usd_price(provider, config).await?.unwrap_or_default();
+        if price >= minimum {
+            return Ok(true);
+        }
+
+        match price_config.below_min {
+            PriceGateAction::Skip => {
+                tracing::info!(
+                    "{address} | ${} trades at {price} USD, below MIN_TOKEN_PRICE_USD {minimum}, keeping the tokens",
+                    token_info().symbol
+                );
+                return Ok(false);
+            }
+            PriceGateAction::Wait => {
+                tracing::info!(
+                    "{address} | ${} trades at {price} USD, waiting for MIN_TOKEN_PRICE_USD {minimum}",
+                    token_info().symbol
+                );
+                tokio::time::sleep(Duration::from_secs(price_config.poll_interval)).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{LazyLock, Mutex},
+};
+
+use alloy::primitives::Address;
+use serde::Deserialize;
+
+// Declared from the most to the least important, so sorting puts high priority wallets first
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum Priority {
+    High,
+    #[default]
+    Normal,
+    Low,
+}
+
+impl FromStr for Priority {
+    type Err = eyre::Report;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "high" => Ok(Priority::High),
+            "normal" => Ok(Priority::Normal),
+            "low" => Ok(Priority::Low),
+            other => eyre::bail!("Unknown priority class {other}, expected high, normal or low"),
+        }
+    }
+}
+
+static PRIORITIES: LazyLock<Mutex<HashMap<Address, Priority>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn assign(address: Address, priority: Priority) {
+    PRIORITIES.lock().unwrap().insert(address, priority);
+}
+
+pub fn priority_of(address: Address) -> Priority {
+    PRIORITIES
+        .lock()
+        .unwrap()
+        .get(&address)
+        .copied()
+        .unwrap_or_default()
+}
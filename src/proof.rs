@@ -1,16 +1,49 @@
-use std::{collections::HashMap, time::Duration};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+    sync::LazyLock,
+    time::{Duration, Instant},
+};
 
 use alloy::{
     hex,
-    primitives::{Address, FixedBytes, U256},
+    primitives::{Address, FixedBytes, B256, U256},
 };
+use rand::thread_rng;
+use regex::Regex;
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue},
-    Client, Method, Proxy,
+    header::{HeaderMap, HeaderName, HeaderValue, ACCEPT, ACCEPT_LANGUAGE, COOKIE, USER_AGENT},
+    Client, Method, Proxy, StatusCode,
+};
+use serde::{Deserialize, Serialize};
+
+use tokio::sync::Mutex;
+
+use crate::{
+    auth::sign_request,
+    campaign::campaign,
+    config::Config,
+    constants::{DEBUG_FOLDER_PATH, PROOFS_FOLDER_PATH},
+    cookies::{jar_for, CookieJar},
+    embed::proof_provider,
+    journal::unix_timestamp,
+    merkle::{compute_root, leaf_hash, local_tree},
+    metrics::{self, Timing},
+    proxy_health, retry_budget,
+    usage::{self, proxy_label},
 };
-use serde::Serialize;
 
-use crate::constants::REQUEST_PROOF_URL;
+static CLIENTS: LazyLock<std::sync::Mutex<HashMap<String, Client>>> =
+    LazyLock::new(|| std::sync::Mutex::new(HashMap::new()));
+// Proxies whose session was bootstrapped from the claim page
+static BOOTSTRAPPED: LazyLock<Mutex<HashSet<String>>> =
+    LazyLock::new(|| Mutex::new(HashSet::new()));
+// The distributor root cached proofs are read for, unset until a claim path reads it
+static MERKLE_ROOT: std::sync::Mutex<Option<B256>> = std::sync::Mutex::new(None);
+
+const DEFAULT_ACCEPT_LANGUAGE: &str = "en-US,en;q=0.9";
 
 #[derive(Clone)]
 pub struct RequestParams<'a, S: Serialize> {
@@ -20,21 +53,46 @@ pub struct RequestParams<'a, S: Serialize> {
     pub query_args: Option<HashMap<&'a str, &'a str>>,
 }
 
+fn build_client(proxy_url: Option<&str>, config: &Config) -> eyre::Result<Client> {
+    let mut builder = Client::builder()
+        .pool_idle_timeout(Duration::from_secs(config.http_pool_idle_timeout))
+        .tcp_keepalive(config.http_tcp_keepalive.map(Duration::from_secs));
+
+    if !config.http2 {
+        builder = builder.http1_only();
+    }
+
+    if let Some(max_idle) = config.http_pool_max_idle {
+        builder = builder.pool_max_idle_per_host(max_idle);
+    }
+
+    if let Some(proxy_url) = proxy_url {
+        builder = builder.proxy(Proxy::all(proxy_url)?);
+    }
+
+    Ok(builder.build()?)
+}
+
+pub fn client_for(proxy_url: Option<&str>, config: &Config) -> eyre::Result<Client> {
+    let key = proxy_url.unwrap_or_default().to_string();
+    let mut clients = CLIENTS.lock().unwrap();
+
+    if let Some(client) = clients.get(&key) {
+        return Ok(client.clone());
+    }
+
+    let client = build_client(proxy_url, config)?;
+    clients.insert(key, client.clone());
+
+    Ok(client)
+}
+
 pub async fn send_http_request(
     request_params: &RequestParams<'_, impl Serialize>,
     headers: Option<&HeaderMap>,
-    proxy: Option<&Proxy>,
+    client: &Client,
+    cookie_jar: Option<&Mutex<CookieJar>>,
 ) -> eyre::Result<String> {
-    let client = proxy.map_or_else(Client::new, |proxy| {
-        Client::builder()
-            .proxy(proxy.clone())
-            .build()
-            .unwrap_or_else(|err| {
-                tracing::error!("Failed to build a client with proxy: {proxy:?}. Error: {err}");
-                Client::new()
-            })
-    });
-
     let mut request = client.request(request_params.method.clone(), request_params.url);
 
     if let Some(params) = &request_params.query_args {
@@ -49,12 +107,38 @@ pub async fn send_http_request(
         request = request.headers(headers.clone());
     }
 
+    if let Some(cookie_jar) = cookie_jar {
+        if let Some(cookie_header) = cookie_jar.lock().await.header_value() {
+            request = request.header(COOKIE, cookie_header);
+        }
+    }
+
     let response = request
         .send()
         .await
-        .inspect_err(|e| tracing::error!("Request failed: {}", e))?
-        .error_for_status()
-        .inspect_err(|e| tracing::error!("Non-successful status code: {}", e))?;
+        .inspect_err(|e| tracing::error!("Request failed: {}", e))?;
+
+    if let Some(cookie_jar) = cookie_jar {
+        let mut cookie_jar = cookie_jar.lock().await;
+        if cookie_jar.store_from(response.headers()) {
+            if let Err(e) = cookie_jar.save().await {
+                tracing::warn!("Failed to persist cookies: {e}");
+            }
+        }
+    }
+
+    let status = response.status();
+    if !status.is_success() {
+        tracing::error!("Non-successful status code: {status}");
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        return Err(UnexpectedResponse {
+            status,
+            headers,
+            body,
+        }
+        .into());
+    }
 
     let text = response
         .text()
@@ -67,163 +151,649 @@ pub async fn send_http_request(
 pub async fn send_http_request_with_retries(
     request_params: &RequestParams<'_, impl Serialize>,
     headers: Option<&HeaderMap>,
-    proxy: Option<&Proxy>,
+    client: &Client,
+    cookie_jar: Option<&Mutex<CookieJar>>,
+    budget_owner: Option<Address>,
     max_retries: Option<usize>,
     retry_delay: Option<Duration>,
 ) -> eyre::Result<String> {
     let max_retries = max_retries.unwrap_or(5);
     let retry_delay = retry_delay.unwrap_or(Duration::from_secs(3));
 
+    let mut last_error = eyre::eyre!("No request was attempted");
     for _ in 0..max_retries {
-        match send_http_request(request_params, headers, proxy).await {
+        match send_http_request(request_params, headers, client, cookie_jar).await {
             Ok(response) => return Ok(response),
-            Err(_) => {
+            Err(e) => {
+                last_error = e;
+                if budget_owner.is_some_and(|owner| !retry_budget::spend(owner)) {
+                    break;
+                }
                 tokio::time::sleep(retry_delay).await;
             }
         }
     }
 
-    eyre::bail!("Amount of tries exceeded")
+    Err(last_error.wrap_err("Amount of tries exceeded"))
+}
+
+// Keeps what the API answered, challenges and block pages are only readable in the body
+#[derive(Debug)]
+pub struct UnexpectedResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl fmt::Display for UnexpectedResponse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Non-successful status code: {}", self.status)
+    }
+}
+
+impl std::error::Error for UnexpectedResponse {}
+
+pub fn is_rate_limited(error: &eyre::Report) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| {
+            cause
+                .downcast_ref::<UnexpectedResponse>()
+                .map(|response| response.status)
+                .or_else(|| cause.downcast_ref::<reqwest::Error>()?.status())
+        })
+        .any(|status| status == StatusCode::TOO_MANY_REQUESTS || status == StatusCode::FORBIDDEN)
+}
+
+pub struct ProofRequest {
+    pub url: String,
+    pub method: Method,
+    pub body: Option<serde_json::Value>,
+    pub query: Vec<(String, String)>,
+}
+
+fn proof_request_for(address: Address, config: &Config) -> eyre::Result<ProofRequest> {
+    let request_config = config.proof_request();
+    let render = |template: &str| template.replace("{address}", &address.to_string());
+
+    let body = request_config
+        .body
+        .as_deref()
+        .map(|body| serde_json::from_str(&render(body)))
+        .transpose()?;
+
+    Ok(ProofRequest {
+        url: render(request_config.url()),
+        method: request_config.method()?,
+        body,
+        query: request_config
+            .query
+            .iter()
+            .map(|(arg, value)| (arg.clone(), render(value)))
+            .collect(),
+    })
+}
+
+fn headers_for(address: Address, proxy_url: &str, config: &Config) -> eyre::Result<HeaderMap> {
+    let mut headers = get_headers(
+        config
+            .locale_for(address, proxy_url)
+            .unwrap_or(DEFAULT_ACCEPT_LANGUAGE),
+        config,
+    )?;
+
+    if let Some(auth) = &config.proof_auth {
+        sign_request(address, auth, &mut headers)?;
+    }
+
+    Ok(headers)
+}
+
+// Loads the claim page once per proxy so a redeploy of the site doesn't leave its jar with stale
+// cookies, a failed load is retried before the proxy's next proof request
+async fn bootstrap_session(
+    proxy_url: &str,
+    headers: &HeaderMap,
+    client: &Client,
+    cookie_jar: &Mutex<CookieJar>,
+    config: &Config,
+) {
+    let Some(proof_api) = config
+        .proof_api
+        .as_ref()
+        .filter(|proof_api| proof_api.bootstrap)
+    else {
+        return;
+    };
+    if !BOOTSTRAPPED.lock().await.insert(proxy_url.to_owned()) {
+        return;
+    }
+
+    let mut page_headers = HeaderMap::new();
+    for name in [USER_AGENT, ACCEPT_LANGUAGE] {
+        if let Some(value) = headers.get(&name) {
+            page_headers.insert(name, value.clone());
+        }
+    }
+    page_headers.insert(ACCEPT, HeaderValue::from_static("text/html"));
+
+    let request_params = RequestParams::<serde_json::Value> {
+        url: proof_api
+            .bootstrap_url
+            .as_deref()
+            .unwrap_or(&campaign().proof_url),
+        method: Method::GET,
+        body: None,
+        query_args: None,
+    };
+    match send_http_request(
+        &request_params,
+        Some(&page_headers),
+        client,
+        Some(cookie_jar),
+    )
+    .await
+    {
+        Ok(_) => tracing::debug!("Refreshed the session of {}", proxy_label(proxy_url)),
+        Err(e) => {
+            tracing::warn!(
+                "Session bootstrap through {} failed: {e}",
+                proxy_label(proxy_url)
+            );
+            BOOTSTRAPPED.lock().await.remove(proxy_url);
+        }
+    }
 }
 
-pub async fn get_proof(address: Address, proxy: reqwest::Proxy) -> eyre::Result<String> {
+pub async fn get_proof(address: Address, proxy_url: &str, config: &Config) -> eyre::Result<String> {
     tracing::info!("Getting proof and allocation for {address}");
 
-    let headers = get_headers();
+    let headers = headers_for(address, proxy_url, config)?;
+    let client = client_for(Some(proxy_url), config)?;
+    let cookie_jar = jar_for(proxy_url, config.proof_cookie()).await;
+    bootstrap_session(proxy_url, &headers, &client, &cookie_jar, config).await;
 
-    let query_args = [("step", "4")]
-        .iter() // UNCOMMENT IF GET
-        .map(|(arg, value)| (*arg, *value))
+    let proof_request = proof_request_for(address, config)?;
+    let query_args = proof_request
+        .query
+        .iter()
+        .map(|(arg, value)| (arg.as_str(), value.as_str()))
         .collect();
 
     let request_params = RequestParams {
-        url: REQUEST_PROOF_URL,
-        method: Method::POST,
-        body: Some(vec![address.to_string()]),
+        url: &proof_request.url,
+        method: proof_request.method.clone(),
+        body: proof_request.body.as_ref(),
         query_args: Some(query_args),
     };
 
-    let response =
-        send_http_request_with_retries(&request_params, Some(&headers), Some(&proxy), None, None)
-            .await?;
+    let response = send_http_request_with_retries(
+        &request_params,
+        Some(&headers),
+        &client,
+        Some(&cookie_jar),
+        Some(address),
+        None,
+        None,
+    )
+    .await;
+    usage::record_proof_call(proxy_url, response.as_ref().ok().map(String::len));
 
-    Ok(response)
+    response
 }
 
-pub fn extract_proof_and_amount(response_text: &str) -> eyre::Result<(Vec<FixedBytes<32>>, U256)> {
-    // Split the response text by "1:"
-    let parts: Vec<&str> = response_text.splitn(2, "1:").collect();
+#[derive(Clone)]
+pub enum ProofResponse {
+    Eligible {
+        proof: Vec<FixedBytes<32>>,
+        amount: U256,
+        fields: BTreeMap<String, String>,
+    },
+    Ineligible,
+}
 
-    if parts.len() < 2 {
-        eyre::bail!("Could not find '1:' in the response.");
-    }
+fn mentions_ineligibility(text: &str) -> bool {
+    let text = text.to_lowercase();
+    text.contains("not eligible") || text.contains("no allocation")
+}
 
-    let json_str = parts[1].trim();
+type ProofParser = fn(&str) -> eyre::Result<ProofResponse>;
 
-    let data: serde_json::Value = serde_json::from_str(json_str)?;
+// Result of the claim data server action, `null` or `false` for wallets without an allocation
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ActionResult {
+    Claim(ClaimData),
+    Failure(ActionError),
+    Empty(Option<bool>),
+}
 
-    let amount_str = data["amount"]
-        .as_str()
-        .ok_or_else(|| eyre::eyre!("'amount' field is missing or not a string"))?;
-    let amount = U256::from_str_radix(amount_str, 10)?;
+#[derive(Deserialize)]
+struct ActionError {
+    #[serde(alias = "message", alias = "reason")]
+    error: String,
+}
+
+#[derive(Deserialize)]
+struct ClaimData {
+    amount: String,
+    proof: Vec<String>,
+    #[serde(flatten)]
+    extra: BTreeMap<String, serde_json::Value>,
+}
 
-    let proof_array = data["proof"]
-        .as_array()
-        .ok_or_else(|| eyre::eyre!("'proof' field is missing or not an array"))?;
-    let proof = proof_array
+fn field_to_string(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(text) => Some(text.clone()),
+        serde_json::Value::Number(number) => Some(number.to_string()),
+        serde_json::Value::Bool(flag) => Some(flag.to_string()),
+        serde_json::Value::Array(items) => {
+            let items: Option<Vec<_>> = items.iter().map(field_to_string).collect();
+            Some(format!("[{}]", items?.join(",")))
+        }
+        _ => None,
+    }
+}
+
+fn decode_proof(proof: &[String]) -> eyre::Result<Vec<FixedBytes<32>>> {
+    proof
         .iter()
-        .map(|v| v.as_str().unwrap())
-        .collect::<Vec<_>>()
-        .into_iter()
         .map(|hex_str| {
-            let bytes = hex::decode(hex_str).unwrap();
-            FixedBytes::from_slice(&bytes)
+            let bytes = hex::decode(hex_str)?;
+            if bytes.len() != 32 {
+                eyre::bail!("Proof element {hex_str} is not 32 bytes long");
+            }
+            Ok(FixedBytes::from_slice(&bytes))
         })
+        .collect()
+}
+
+// Every parser ends here, so a zero amount is never claimed and an empty proof never sent
+fn eligible(claim_data: ClaimData) -> eyre::Result<ProofResponse> {
+    let amount = U256::from_str_radix(&claim_data.amount, 10)?;
+    if amount.is_zero() {
+        return Ok(ProofResponse::Ineligible);
+    }
+    if claim_data.proof.is_empty() {
+        eyre::bail!("Proof API returned an empty proof for {amount}");
+    }
+
+    Ok(ProofResponse::Eligible {
+        proof: decode_proof(&claim_data.proof)?,
+        amount,
+        fields: claim_data
+            .extra
+            .iter()
+            .filter_map(|(name, value)| Some((name.clone(), field_to_string(value)?)))
+            .collect(),
+    })
+}
+
+fn from_value(data: serde_json::Value) -> eyre::Result<ProofResponse> {
+    match serde_json::from_value(data)? {
+        ActionResult::Claim(claim_data) => eligible(claim_data),
+        ActionResult::Failure(failure) if mentions_ineligibility(&failure.error) => {
+            Ok(ProofResponse::Ineligible)
+        }
+        ActionResult::Failure(failure) => {
+            eyre::bail!("Proof API returned an error: {}", failure.error)
+        }
+        ActionResult::Empty(None | Some(false)) => Ok(ProofResponse::Ineligible),
+        ActionResult::Empty(Some(true)) => eyre::bail!("Proof API returned no claim data"),
+    }
+}
+
+// React Server Components payload: every line is `<id>:<json>`, the action result is chunk `1`
+fn parse_rsc(response_text: &str) -> eyre::Result<ProofResponse> {
+    let chunk = response_text
+        .lines()
+        .find_map(|line| line.strip_prefix("1:"))
+        .ok_or_else(|| eyre::eyre!("Could not find chunk '1:' in the response."))?;
+
+    from_value(serde_json::from_str(chunk.trim())?)
+}
+
+fn parse_regex(response_text: &str) -> eyre::Result<ProofResponse> {
+    let amount_re = Regex::new(r#""amount"\s*:\s*"(\d+)""#)?;
+    let proof_re = Regex::new(r#""proof"\s*:\s*\[([^\]]*)\]"#)?;
+    let hash_re = Regex::new(r"0x[0-9a-fA-F]{64}")?;
+
+    let amount = amount_re
+        .captures(response_text)
+        .ok_or_else(|| eyre::eyre!("No amount found in the response"))?[1]
+        .to_string();
+    let proof_list = proof_re
+        .captures(response_text)
+        .ok_or_else(|| eyre::eyre!("No proof found in the response"))?;
+    let proof = hash_re
+        .find_iter(&proof_list[1])
+        .map(|hash| hash.as_str().to_string())
         .collect();
 
-    Ok((proof, amount))
+    eligible(ClaimData {
+        amount,
+        proof,
+        extra: BTreeMap::new(),
+    })
 }
 
-fn get_headers() -> HeaderMap {
-    let mut headers = HeaderMap::new();
+fn parse_embedded_json(response_text: &str) -> eyre::Result<ProofResponse> {
+    for (start, _) in response_text.match_indices('{') {
+        let mut values = serde_json::Deserializer::from_str(&response_text[start..])
+            .into_iter::<serde_json::Value>();
 
-    // Add headers from the curl command
-    headers.insert(
-        HeaderName::from_static("accept"),
-        HeaderValue::from_static("text/x-component"),
-    );
-    headers.insert(
-        HeaderName::from_static("accept-language"),
-        HeaderValue::from_static("en-US,en;q=0.9"),
-    );
-    headers.insert(
-        HeaderName::from_static("cache-control"),
-        HeaderValue::from_static("no-cache"),
-    );
-    headers.insert(
-        HeaderName::from_static("content-type"),
-        HeaderValue::from_static("text/plain;charset=UTF-8"),
-    );
-    headers.insert(
-        HeaderName::from_static("cookie"),
-        HeaderValue::from_static("_ga=GA1.1.1149305761.1729541261; _ga_XR3MGVSHFC=GS1.1.1729558399.3.1.1729558399.0.0.0; _ga_0CM3JHPD29=GS1.1.1729580355.1.1.1729580661.0.0.0; _vcrcs=1.1729581708.3600.NjMzMTc4NTk3MDEyNTg3YTBlYTY1NDhjZjczYjJhYjE=.5d4f132acc2af79b849b411174d86b27"),
-    );
-    headers.insert(
-        HeaderName::from_static("dnt"),
-        HeaderValue::from_static("1"),
-    );
-    headers.insert(
-        HeaderName::from_static("next-action"),
-        HeaderValue::from_static("2ab5dbb719cdef833b891dc475986d28393ae963"),
-    );
-    headers.insert(
-        HeaderName::from_static("next-router-state-tree"),
-        HeaderValue::from_static("%5B%22%22%2C%7B%22children%22%3A%5B%22(claim)%22%2C%7B%22children%22%3A%5B%22__PAGE__%22%2C%7B%7D%2C%22%2F%3Fstep%3D4%22%2C%22refresh%22%5D%7D%5D%7D%2Cnull%2Cnull%2Ctrue%5D"),
-    );
-    headers.insert(
-        HeaderName::from_static("origin"),
-        HeaderValue::from_static("https://claim.scroll.io"),
-    );
-    headers.insert(
-        HeaderName::from_static("pragma"),
-        HeaderValue::from_static("no-cache"),
-    );
-    headers.insert(
-        HeaderName::from_static("priority"),
-        HeaderValue::from_static("u=1, i"),
-    );
-    headers.insert(
-        HeaderName::from_static("referer"),
-        HeaderValue::from_static("https://claim.scroll.io/?step=4"),
-    );
-    headers.insert(
-        HeaderName::from_static("sec-ch-ua"),
-        HeaderValue::from_static("\"Not?A_Brand\";v=\"99\", \"Chromium\";v=\"130\""),
-    );
-    headers.insert(
-        HeaderName::from_static("sec-ch-ua-mobile"),
-        HeaderValue::from_static("?0"),
-    );
-    headers.insert(
-        HeaderName::from_static("sec-ch-ua-platform"),
-        HeaderValue::from_static("\"macOS\""),
-    );
-    headers.insert(
-        HeaderName::from_static("sec-fetch-dest"),
-        HeaderValue::from_static("empty"),
-    );
-    headers.insert(
-        HeaderName::from_static("sec-fetch-mode"),
-        HeaderValue::from_static("cors"),
+        if let Some(Ok(value)) = values.next() {
+            if value.get("amount").is_some() && value.get("proof").is_some() {
+                return from_value(value);
+            }
+        }
+    }
+
+    eyre::bail!("No embedded JSON object with amount and proof found")
+}
+
+pub fn parse_proof_response(response_text: &str) -> eyre::Result<ProofResponse> {
+    let parsers: [(&str, ProofParser); 3] = [
+        ("rsc", parse_rsc),
+        ("regex", parse_regex),
+        ("embedded json", parse_embedded_json),
+    ];
+
+    let mut errors = vec![];
+    for (name, parser) in parsers {
+        match parser(response_text) {
+            Ok(response) => return Ok(response),
+            Err(e) => errors.push(format!("{name}: {e}")),
+        }
+    }
+
+    eyre::bail!("All proof parsers failed ({})", errors.join("; "))
+}
+
+pub fn describe_proof_response(result: &eyre::Result<ProofResponse>) -> String {
+    match result {
+        Ok(ProofResponse::Eligible { proof, amount, .. }) => {
+            format!("eligible {amount} {}", proof.len())
+        }
+        Ok(ProofResponse::Ineligible) => String::from("ineligible"),
+        Err(_) => String::from("error"),
+    }
+}
+
+pub async fn parse_fixture(path: impl AsRef<Path>) -> eyre::Result<()> {
+    let response_text = tokio::fs::read_to_string(&path).await?;
+    let result = parse_proof_response(&response_text);
+
+    tracing::info!(
+        "{}: {}",
+        path.as_ref().display(),
+        describe_proof_response(&result)
     );
-    headers.insert(
-        HeaderName::from_static("sec-fetch-site"),
-        HeaderValue::from_static("same-origin"),
+    if let Err(e) = result {
+        tracing::info!("{e}");
+    }
+
+    Ok(())
+}
+
+async fn persist_raw_response(address: Address, response_text: &str) -> eyre::Result<PathBuf> {
+    tokio::fs::create_dir_all(DEBUG_FOLDER_PATH).await?;
+
+    let path =
+        Path::new(DEBUG_FOLDER_PATH).join(format!("proof-{address}-{}.txt", unix_timestamp()));
+    tokio::fs::write(&path, response_text).await?;
+
+    Ok(path)
+}
+
+fn dump_unexpected_response(response: &UnexpectedResponse) -> String {
+    let mut dump = format!("HTTP {}\n", response.status);
+    for (name, value) in &response.headers {
+        dump += &format!("{name}: {}\n", String::from_utf8_lossy(value.as_bytes()));
+    }
+
+    dump + "\n" + response.body.as_str()
+}
+
+#[derive(Serialize, Deserialize)]
+struct CachedProof {
+    amount: U256,
+    proof: Vec<FixedBytes<32>>,
+    #[serde(default)]
+    fields: BTreeMap<String, String>,
+}
+
+pub fn set_merkle_root(merkle_root: B256) {
+    *MERKLE_ROOT.lock().unwrap() = Some(merkle_root);
+}
+
+fn cached_proof_path(merkle_root: B256, address: Address) -> PathBuf {
+    Path::new(PROOFS_FOLDER_PATH)
+        .join(merkle_root.to_string())
+        .join(format!("{address}.json"))
+}
+
+// A proof of another tranche would claim the wrong amount, so nothing is read before the
+// distributor root is known
+async fn read_cached_proof(address: Address) -> eyre::Result<Option<ProofResponse>> {
+    let Some(merkle_root) = *MERKLE_ROOT.lock().unwrap() else {
+        return Ok(None);
+    };
+    let path = cached_proof_path(merkle_root, address);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let cached: CachedProof = serde_json::from_str(&tokio::fs::read_to_string(path).await?)?;
+    Ok(Some(ProofResponse::Eligible {
+        proof: cached.proof,
+        amount: cached.amount,
+        fields: cached.fields,
+    }))
+}
+
+// What the plan shows before anything is fetched, a local tree or a proof cached for the current root
+pub async fn known_allocation(address: Address) -> Option<U256> {
+    if let Some(tree) = local_tree() {
+        return tree.proof(address).map(|(amount, _)| amount);
+    }
+
+    match read_cached_proof(address).await {
+        Ok(Some(ProofResponse::Eligible { amount, .. })) => Some(amount),
+        _ => None,
+    }
+}
+
+// Stored under the root the proof itself leads to, so proofs fetched before a root is posted are
+// found once it is
+async fn cache_proof(
+    address: Address,
+    proof_response: &ProofResponse,
+    config: &Config,
+) -> eyre::Result<()> {
+    let ProofResponse::Eligible {
+        proof,
+        amount,
+        fields,
+    } = proof_response
+    else {
+        return Ok(());
+    };
+
+    let merkle_root = compute_root(
+        leaf_hash(address, *amount, config.merkle_leaf_encoding),
+        proof,
     );
+    let cached = CachedProof {
+        amount: *amount,
+        proof: proof.clone(),
+        fields: fields.clone(),
+    };
+    let path = cached_proof_path(merkle_root, address);
+    tokio::fs::create_dir_all(path.parent().unwrap()).await?;
+    tokio::fs::write(path, serde_json::to_string_pretty(&cached)?).await?;
+
+    Ok(())
+}
+
+pub async fn fetch_proof(
+    address: Address,
+    proxy_url: &str,
+    config: &Config,
+) -> eyre::Result<ProofResponse> {
+    if let Some(tree) = local_tree() {
+        return Ok(tree.proof_response(address));
+    }
+
+    if let Some(proof_provider) = proof_provider() {
+        return proof_provider.proof_for(address).await;
+    }
+
+    match read_cached_proof(address).await {
+        Ok(Some(proof_response)) => {
+            tracing::debug!("Using the cached proof of {address}");
+            return Ok(proof_response);
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Ignoring the unreadable cached proof of {address}: {e}"),
+    }
+
+    let mut proxy = proxy_health::healthy_proxy(proxy_url, config).await;
+    let mut tried = vec![];
+    let started = Instant::now();
+    let response = loop {
+        let e = match get_proof(address, &proxy, config).await {
+            Ok(response) => {
+                proxy_health::record_success(&proxy);
+                break Ok(response);
+            }
+            Err(e) => e,
+        };
+        if is_rate_limited(&e) {
+            proxy_health::record_rate_limited(&proxy, config);
+        }
+
+        tried.push(proxy.clone());
+        match proxy_health::another(config, &tried, &mut thread_rng()) {
+            Some(next) if tried.len() <= config.proxy_retries => {
+                tracing::warn!(
+                    "Proof request for {address} through {} failed: {e}, retrying through {}",
+                    proxy_label(&proxy),
+                    proxy_label(&next)
+                );
+                proxy = next;
+            }
+            _ => break Err(e),
+        }
+    };
+
+    let response = match response {
+        Ok(response) => response,
+        #[cfg(feature = "browser")]
+        Err(e) if config.browser_fallback => {
+            tracing::warn!("Proof request for {address} failed: {e}, falling back to the browser");
+            let headers = headers_for(address, &proxy, config)?;
+            let proof_request = proof_request_for(address, config)?;
+            crate::browser::get_proof(address, &proxy, &headers, &proof_request).await?
+        }
+        Err(e) => {
+            let dump = e
+                .chain()
+                .find_map(|cause| cause.downcast_ref())
+                .map(dump_unexpected_response);
+            if let Some(dump) = dump {
+                match persist_raw_response(address, &dump).await {
+                    Ok(path) => {
+                        tracing::error!("Saved the failed proof response to {}", path.display())
+                    }
+                    Err(persist_error) => {
+                        tracing::error!("Failed to save proof response: {persist_error}")
+                    }
+                }
+            }
+            return Err(e);
+        }
+    };
+
+    metrics::record_duration(Timing::ProofFetch, started.elapsed());
+
+    match parse_proof_response(&response) {
+        Ok(proof_response) => {
+            if let Err(e) = cache_proof(address, &proof_response, config).await {
+                tracing::warn!("Failed to cache the proof of {address}: {e}");
+            }
+            Ok(proof_response)
+        }
+        Err(e) => {
+            match persist_raw_response(address, &response).await {
+                Ok(path) => {
+                    tracing::error!("Saved unparseable proof response to {}", path.display())
+                }
+                Err(persist_error) => {
+                    tracing::error!("Failed to save proof response: {persist_error}")
+                }
+            }
+            Err(e)
+        }
+    }
+}
+
+fn get_headers(accept_language: &str, config: &Config) -> eyre::Result<HeaderMap> {
+    let mut headers = HeaderMap::new();
+
+    for (name, value) in &campaign().headers {
+        headers.insert(HeaderName::from_str(name)?, HeaderValue::from_str(value)?);
+    }
+    if let Some(proof_api) = &config.proof_api {
+        for (name, value) in &proof_api.headers {
+            headers.insert(HeaderName::from_str(name)?, HeaderValue::from_str(value)?);
+        }
+        if let Some(next_action) = &proof_api.next_action {
+            headers.insert(
+                HeaderName::from_static("next-action"),
+                HeaderValue::from_str(next_action)?,
+            );
+        }
+        if let Some(user_agent) = &proof_api.user_agent {
+            headers.insert(USER_AGENT, HeaderValue::from_str(user_agent)?);
+        }
+    }
     headers.insert(
-        HeaderName::from_static("user-agent"),
-        HeaderValue::from_static("Mozilla/5.0 (Macintosh; Intel Mac OS X 10_15_7) AppleWebKit/537.36 (KHTML, like Gecko) Chrome/130.0.0.0 Safari/537.36"),
+        HeaderName::from_static("accept-language"),
+        HeaderValue::from_str(accept_language)?,
     );
 
-    headers
+    Ok(headers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURES_FOLDER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/fixtures/proof");
+
+    #[test]
+    fn parses_fixture_corpus() {
+        let mut fixtures = 0;
+
+        for entry in std::fs::read_dir(FIXTURES_FOLDER_PATH).unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_none_or(|extension| extension != "txt") {
+                continue;
+            }
+
+            let response_text = std::fs::read_to_string(&path).unwrap();
+            let expected = std::fs::read_to_string(path.with_extension("expected")).unwrap();
+
+            assert_eq!(
+                describe_proof_response(&parse_proof_response(&response_text)),
+                expected.trim(),
+                "fixture {}",
+                path.display()
+            );
+            fixtures += 1;
+        }
+
+        assert!(fixtures > 0, "no fixtures found in {FIXTURES_FOLDER_PATH}");
+    }
 }
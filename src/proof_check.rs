@@ -0,0 +1,49 @@
+use alloy::network::{Ethereum, EthereumWallet, NetworkWallet};
+use rand::thread_rng;
+
+use crate::{
+    campaign::campaign,
+    claimer::TokenDistributor,
+    config::Config,
+    merkle,
+    pool::init_provider_pool,
+    proof::{fetch_proof, ProofResponse},
+    utils::read_private_keys,
+};
+
+pub async fn verify_proofs(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let mut rng = thread_rng();
+
+    let distributor = TokenDistributor::new(campaign().distributor, providers.choose(&mut rng));
+    let root = distributor.merkleRoot().call().await?._0;
+    tracing::info!("Verifying proofs against on-chain root {root}");
+
+    let wallets = read_private_keys().await?;
+    let mut passed = 0;
+
+    for wallet in &wallets {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+        let proxy = config.proxy_for(address, &mut rng);
+
+        match fetch_proof(address, &proxy, &config).await {
+            Ok(ProofResponse::Ineligible) => tracing::info!("SKIP | {address} | not eligible"),
+            Ok(ProofResponse::Eligible { proof, amount, .. }) => {
+                match merkle::verify(address, amount, &proof, root) {
+                    Some(encoding) => {
+                        passed += 1;
+                        tracing::info!("PASS | {address} | amount {amount} | {encoding:?} leaf");
+                    }
+                    None => tracing::error!(
+                        "FAIL | {address} | amount {amount} | proof does not match root"
+                    ),
+                }
+            }
+            Err(e) => tracing::error!("FAIL | {address} | could not get proof: {e}"),
+        }
+    }
+
+    tracing::info!("{passed}/{} proofs verified", wallets.len());
+
+    Ok(())
+}
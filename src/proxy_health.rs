@@ -0,0 +1,212 @@
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{LazyLock, Mutex},
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::{keccak256, Address};
+use rand::{rngs::ThreadRng, seq::SliceRandom, thread_rng};
+use reqwest::{Client, Proxy};
+use tokio::task::JoinSet;
+
+use crate::{
+    campaign::campaign,
+    config::{Config, ProxyPolicy},
+    notifier::notify,
+    paths::config_path,
+    usage::proxy_label,
+};
+
+const PROXY_RELOAD_INTERVAL: Duration = Duration::from_secs(30);
+const PROXY_CHECK_TIMEOUT: Duration = Duration::from_secs(15);
+
+static BANNED: LazyLock<Mutex<HashSet<String>>> = LazyLock::new(|| Mutex::new(HashSet::new()));
+static STRIKES: LazyLock<Mutex<HashMap<String, u32>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+// Proxies picked up from the config file after startup
+static RELOADED: LazyLock<Mutex<Vec<String>>> = LazyLock::new(|| Mutex::new(vec![]));
+// Held by the task polling the config file, everyone else queues behind it
+static PAUSE: tokio::sync::Mutex<()> = tokio::sync::Mutex::const_new(());
+
+fn is_banned(proxy: &str) -> bool {
+    BANNED.lock().unwrap().contains(proxy)
+}
+
+pub fn healthy(config: &Config) -> Vec<String> {
+    let banned = BANNED.lock().unwrap();
+    config
+        .proxies
+        .iter()
+        .chain(RELOADED.lock().unwrap().iter())
+        .filter(|proxy| !banned.contains(*proxy))
+        .cloned()
+        .collect()
+}
+
+// Falls back to the whole list once everything is banned, fetch_proof then waits for fresh ones
+pub fn choose(config: &Config, rng: &mut ThreadRng) -> String {
+    healthy(config)
+        .choose(rng)
+        .or_else(|| config.proxies.choose(rng))
+        .unwrap()
+        .clone()
+}
+
+// Rendezvous hashing keeps a wallet on its proxy while that one stays healthy, a ban only moves
+// the wallets that were on the banned proxy
+fn sticky(address: Address, proxies: &[String]) -> Option<String> {
+    proxies
+        .iter()
+        .max_by_key(|proxy| keccak256([address.as_slice(), proxy.as_bytes()].concat()))
+        .cloned()
+}
+
+pub fn choose_for(config: &Config, address: Address, rng: &mut ThreadRng) -> String {
+    match config.proxy_policy {
+        ProxyPolicy::Rotate => choose(config, rng),
+        ProxyPolicy::Sticky => sticky(address, &healthy(config))
+            .or_else(|| sticky(address, &config.proxies))
+            .unwrap(),
+    }
+}
+
+// A healthy proxy that hasn't been tried for this request yet
+pub fn another(config: &Config, tried: &[String], rng: &mut ThreadRng) -> Option<String> {
+    healthy(config)
+        .into_iter()
+        .filter(|proxy| !tried.contains(proxy))
+        .collect::<Vec<_>>()
+        .choose(rng)
+        .cloned()
+}
+
+async fn probe(proxy: &str, url: &str) -> eyre::Result<Duration> {
+    let client = Client::builder()
+        .proxy(Proxy::all(proxy)?)
+        .timeout(PROXY_CHECK_TIMEOUT)
+        .build()?;
+    let started = Instant::now();
+    client.get(url).send().await?.error_for_status()?;
+
+    Ok(started.elapsed())
+}
+
+// Dead proxies are banned for the run before the first proof request goes through them
+pub async fn check_proxies(config: &Config) {
+    if !config.proxy_check {
+        return;
+    }
+
+    let url = config
+        .proxy_check_url
+        .clone()
+        .unwrap_or_else(|| campaign().proof_url.clone());
+    let mut checks = JoinSet::new();
+    for proxy in config.proxies.iter().cloned() {
+        let url = url.clone();
+        checks.spawn(async move {
+            let result = probe(&proxy, &url).await;
+            (proxy, result)
+        });
+    }
+
+    let mut alive = 0;
+    while let Some(Ok((proxy, result))) = checks.join_next().await {
+        match result {
+            Ok(latency) => {
+                alive += 1;
+                tracing::info!("Proxy {} answered in {latency:?}", proxy_label(&proxy));
+            }
+            Err(e) => {
+                tracing::warn!("Dropping proxy {}: {e}", proxy_label(&proxy));
+                BANNED.lock().unwrap().insert(proxy);
+            }
+        }
+    }
+    tracing::info!("{alive}/{} proxies passed the check", config.proxies.len());
+}
+
+pub fn record_success(proxy: &str) {
+    STRIKES.lock().unwrap().remove(proxy);
+}
+
+// Only 403 and 429 answers count, timeouts and 5xx say nothing about the proxy's standing
+pub fn record_rate_limited(proxy: &str, config: &Config) {
+    let mut strikes = STRIKES.lock().unwrap();
+    let count = strikes.entry(proxy.to_owned()).or_default();
+    *count += 1;
+
+    if *count >= config.proxy_ban_threshold && BANNED.lock().unwrap().insert(proxy.to_owned()) {
+        tracing::warn!(
+            "Proxy {} banned after {count} rate limited proof requests",
+            proxy_label(proxy)
+        );
+    }
+}
+
+async fn reload_proxies() -> eyre::Result<Vec<String>> {
+    let document: toml::Table = toml::from_str(&tokio::fs::read_to_string(config_path()).await?)?;
+    let proxies = document
+        .get("PROXIES")
+        .and_then(|proxies| proxies.as_array())
+        .ok_or_else(|| eyre::eyre!("PROXIES is missing from the config"))?;
+
+    Ok(proxies
+        .iter()
+        .filter_map(|proxy| proxy.as_str())
+        .filter(|proxy| !proxy.is_empty())
+        .map(String::from)
+        .collect())
+}
+
+async fn wait_for_healthy(config: &Config) {
+    let _pause = PAUSE.lock().await;
+    if !healthy(config).is_empty() {
+        return;
+    }
+
+    let message = format!(
+        "Every proxy is banned, proof fetching is paused until PROXIES in {} lists a fresh one",
+        config_path().display()
+    );
+    tracing::error!("{message}");
+    notify(config, &message).await;
+
+    loop {
+        tokio::time::sleep(PROXY_RELOAD_INTERVAL).await;
+        match reload_proxies().await {
+            Ok(proxies) => {
+                let known = healthy(config);
+                let fresh: Vec<String> = proxies
+                    .into_iter()
+                    .filter(|proxy| !is_banned(proxy) && !known.contains(proxy))
+                    .collect();
+                RELOADED.lock().unwrap().extend(fresh);
+            }
+            Err(e) => tracing::warn!("Failed to reload proxies: {e}"),
+        }
+
+        let available = healthy(config).len();
+        if available > 0 {
+            let message = format!("Resuming proof fetching with {available} fresh proxies");
+            tracing::info!("{message}");
+            notify(config, &message).await;
+            return;
+        }
+    }
+}
+
+// Keeps `proxy` while it is healthy, otherwise waits for the pool and hands out another one,
+// which also reroutes wallets bound to a banned proxy
+pub async fn healthy_proxy(proxy: &str, config: &Config) -> String {
+    if !is_banned(proxy) {
+        return proxy.to_owned();
+    }
+
+    loop {
+        if let Some(proxy) = healthy(config).choose(&mut thread_rng()) {
+            return proxy.clone();
+        }
+        wait_for_healthy(config).await;
+    }
+}
@@ -0,0 +1,481 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    eips::eip2718::Encodable2718,
+    network::{Ethereum, EthereumWallet, NetworkWallet, ReceiptResponse, TransactionBuilder},
+    primitives::{Address, Bytes, TxHash, U256},
+    providers::Provider,
+    rpc::types::TransactionRequest,
+};
+use rand::thread_rng;
+use tokio::task::JoinSet;
+
+use crate::{
+    campaign::chain,
+    claimer::{
+        cancel_transaction, claim_call_for, find_receipt, MIN_REPLACEMENT_MULTIPLIER_PERCENT,
+    },
+    config::{Config, RaceConfig},
+    constants::RACE_FILE_PATH,
+    fees::{estimate_fees, FeeQuote},
+    inflight,
+    journal::{self, Event},
+    ledger,
+    metrics::Stage,
+    monitor, nonces,
+    pool::{is_websocket, ProviderPool, RpcTransport},
+    prefetch,
+    proof::{fetch_proof, ProofResponse},
+    state,
+    webhook::{self, WebhookEvent},
+};
+
+const RECEIPT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+struct Racer {
+    address: Address,
+    wallet: Arc<EthereumWallet>,
+    // Every fee level broadcast at the racer's nonce, the last one is the current
+    tx_hashes: Vec<TxHash>,
+    // The unsigned claim, simulated on every new head until it stops reverting
+    request: TransactionRequest,
+    raw: Bytes,
+    first_ack: Option<Duration>,
+    accepted: usize,
+    bumps: u64,
+    block: Option<u64>,
+    status: Option<bool>,
+}
+
+impl Racer {
+    fn nonce(&self) -> u64 {
+        self.request.nonce.unwrap_or_default()
+    }
+
+    fn is_pending(&self) -> bool {
+        self.accepted > 0 && self.status.is_none()
+    }
+
+    async fn sign(&mut self) -> eyre::Result<()> {
+        let signed_transaction = self.request.clone().build(&self.wallet).await?;
+        self.tx_hashes.push(*signed_transaction.tx_hash());
+        self.raw = signed_transaction.encoded_2718().into();
+
+        Ok(())
+    }
+}
+
+async fn presign<P>(
+    provider: &P,
+    wallet: &Arc<EthereumWallet>,
+    race_config: &RaceConfig,
+    config: &Config,
+) -> eyre::Result<Racer>
+where
+    P: Provider<RpcTransport, Ethereum>,
+{
+    let address = <Arc<EthereumWallet> as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+    let proof_response = match prefetch::peek(address) {
+        Some(proof_response) => proof_response,
+        None => {
+            let proxy = config.proxy_for(address, &mut thread_rng());
+            fetch_proof(address, &proxy, config).await?
+        }
+    };
+    let ProofResponse::Eligible {
+        proof,
+        amount,
+        fields,
+    } = proof_response
+    else {
+        eyre::bail!("{address} is not eligible");
+    };
+
+    let (to, input) = claim_call_for(address, amount, proof, &fields, config)?;
+    let fees = estimate_fees(provider, config)
+        .await?
+        .scaled(race_config.fee_multiplier_percent as u128);
+    let mut request = TransactionRequest::default()
+        .with_from(address)
+        .with_to(to)
+        .with_input(input)
+        .with_nonce(provider.get_transaction_count(address).await?)
+        .with_chain_id(chain().chain_id)
+        .with_gas_limit(race_config.gas_limit);
+    fees.apply(&mut request);
+
+    let mut racer = Racer {
+        address,
+        wallet: wallet.clone(),
+        tx_hashes: vec![],
+        request,
+        raw: Bytes::new(),
+        first_ack: None,
+        accepted: 0,
+        bumps: 0,
+        block: None,
+        status: None,
+    };
+    racer.sign().await?;
+
+    Ok(racer)
+}
+
+async fn probe_heads<P>(
+    provider: &P,
+    probe: &TransactionRequest,
+    race_config: &RaceConfig,
+    websocket: bool,
+) -> (u64, Instant)
+where
+    P: Provider<RpcTransport, Ethereum>,
+{
+    if websocket {
+        match provider.subscribe_blocks().await {
+            Ok(mut heads) => {
+                tracing::info!("Race armed, following new heads for the opening block");
+                loop {
+                    match heads.recv().await {
+                        Ok(block) => {
+                            if provider.call(probe).await.is_ok() {
+                                return (block.header.number, Instant::now());
+                            }
+                        }
+                        Err(e) => {
+                            tracing::warn!("Lost the head subscription, polling instead: {e}");
+                            break;
+                        }
+                    }
+                }
+            }
+            Err(e) => tracing::warn!("Failed to subscribe to new heads, polling instead: {e}"),
+        }
+    }
+
+    let poll_interval = Duration::from_millis(race_config.head_poll_interval);
+    let mut head = 0;
+
+    tracing::info!("Race armed, polling for the opening block every {poll_interval:?}");
+    loop {
+        match provider.get_block_number().await {
+            Ok(current) if current != head => {
+                head = current;
+                if provider.call(probe).await.is_ok() {
+                    return (head, Instant::now());
+                }
+            }
+            Ok(_) => {}
+            Err(e) => tracing::warn!("Failed to poll the head: {e}"),
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+// Returns the first head on which the probe claim simulates and when it was seen, or `None` once
+// OPEN_WINDOW passes, the probe may never simulate if its wallet already claimed
+async fn wait_for_open<P>(
+    providers: &ProviderPool<P>,
+    probe: &TransactionRequest,
+    race_config: &RaceConfig,
+) -> Option<(u64, Instant)>
+where
+    P: Provider<RpcTransport, Ethereum>,
+{
+    let (provider, websocket) = match providers.endpoints().find(|(url, _)| is_websocket(url)) {
+        Some((_, provider)) => (provider.clone(), true),
+        None => (providers.choose(&mut thread_rng()), false),
+    };
+
+    tokio::time::timeout(
+        Duration::from_secs(race_config.open_window),
+        probe_heads(provider.as_ref(), probe, race_config, websocket),
+    )
+    .await
+    .ok()
+}
+
+// Sends the current claim of every racer `included` picks to every endpoint, returns the racers
+// at least one endpoint accepted
+async fn broadcast<P>(
+    providers: &ProviderPool<P>,
+    racers: &mut [Racer],
+    included: impl Fn(&Racer) -> bool,
+    opened: Instant,
+) -> Vec<usize>
+where
+    P: Provider<RpcTransport, Ethereum> + 'static,
+{
+    let mut handles = JoinSet::new();
+    for (index, racer) in racers
+        .iter()
+        .enumerate()
+        .filter(|(_, racer)| included(racer))
+    {
+        for (url, provider) in providers.endpoints() {
+            let (url, provider, raw) = (url.to_owned(), provider.clone(), racer.raw.clone());
+            handles.spawn(async move {
+                let sent = provider.send_raw_transaction(&raw).await.map(|_| ());
+                (index, url, opened.elapsed(), sent)
+            });
+        }
+    }
+
+    let mut accepted = vec![];
+    while let Some(res) = handles.join_next().await {
+        let (index, url, elapsed, sent) = res.unwrap();
+        let racer = &mut racers[index];
+        match sent {
+            Ok(()) => {
+                racer.accepted += 1;
+                racer.first_ack.get_or_insert(elapsed);
+                if !accepted.contains(&index) {
+                    accepted.push(index);
+                }
+            }
+            Err(e) => tracing::debug!("{} | {url} rejected the claim: {e}", racer.address),
+        }
+    }
+
+    accepted
+}
+
+async fn record_sent(racer: &Racer) {
+    let tx_hash = *racer.tx_hashes.last().expect("Racer to be signed");
+    journal::record(Event::TxSent {
+        from: racer.address,
+        tx_hash,
+        stage: Stage::Claim,
+    });
+    inflight::sent(racer.address, Stage::Claim, tx_hash);
+    monitor::record_own_nonce(racer.address, racer.nonce());
+    state::update(racer.address, |wallet| wallet.tx_hashes.push(tx_hash)).await;
+}
+
+async fn wait_for_receipts<P>(provider: &P, racers: &mut [Racer], timeout: Duration)
+where
+    P: Provider<RpcTransport, Ethereum>,
+{
+    let started = Instant::now();
+    while started.elapsed() < timeout {
+        let mut pending = 0;
+        for racer in racers.iter_mut().filter(|racer| racer.is_pending()) {
+            match find_receipt(provider, &racer.tx_hashes).await {
+                Ok(Some(receipt)) => {
+                    racer.block = receipt.block_number;
+                    racer.status = Some(receipt.status());
+                    nonces::settled(racer.address, racer.nonce());
+                    journal::record(Event::Receipt {
+                        from: racer.address,
+                        tx_hash: receipt.transaction_hash,
+                        stage: Stage::Claim,
+                        status: receipt.status(),
+                        gas_used: receipt.gas_used(),
+                        effective_gas_price: receipt.effective_gas_price(),
+                    });
+                    inflight::settled(racer.address);
+                    ledger::record(provider, racer.address, Stage::Claim, U256::ZERO, &receipt)
+                        .await;
+                    if receipt.status() {
+                        webhook::emit(WebhookEvent::ClaimConfirmed {
+                            address: racer.address,
+                            tx_hash: receipt.transaction_hash,
+                        });
+                    }
+                }
+                Ok(None) => pending += 1,
+                Err(e) => {
+                    tracing::warn!("{} | failed to read the race receipt: {e}", racer.address);
+                    pending += 1;
+                }
+            }
+        }
+
+        if pending == 0 {
+            return;
+        }
+        tokio::time::sleep(RECEIPT_POLL_INTERVAL).await;
+    }
+}
+
+async fn write_report(racers: &[Racer], open_block: u64) -> eyre::Result<()> {
+    let mut lines = vec![String::from(
+        "wallet,tx_hash,open_block,first_ack_ms,endpoints_accepted,bumps,block,status",
+    )];
+    lines.extend(racers.iter().map(|racer| {
+        format!(
+            "{},{},{open_block},{},{},{},{},{}",
+            racer.address,
+            racer.tx_hashes.last().copied().unwrap_or_default(),
+            racer
+                .first_ack
+                .map_or_else(String::new, |ack| ack.as_millis().to_string()),
+            racer.accepted,
+            racer.bumps,
+            racer
+                .block
+                .map_or_else(String::new, |block| block.to_string()),
+            racer
+                .status
+                .map_or_else(String::new, |status| status.to_string()),
+        )
+    }));
+
+    tokio::fs::write(RACE_FILE_PATH, lines.join("\n") + "\n").await?;
+    Ok(())
+}
+
+// Replaces the pending claims at their own nonce, a new claim behind them would wait on them
+async fn bump_pending<P>(
+    providers: &ProviderPool<P>,
+    racers: &mut [Racer],
+    opened: Instant,
+    config: &Config,
+) where
+    P: Provider<RpcTransport, Ethereum> + 'static,
+{
+    let multiplier = config
+        .fee_multiplier_percent(1)
+        .max(MIN_REPLACEMENT_MULTIPLIER_PERCENT);
+
+    let mut bumped = vec![];
+    for racer in racers.iter_mut().filter(|racer| racer.is_pending()) {
+        let previous = (racer.request.clone(), racer.raw.clone());
+        FeeQuote::of(&racer.request)
+            .scaled(multiplier)
+            .apply(&mut racer.request);
+        match racer.sign().await {
+            Ok(()) => {
+                racer.bumps += 1;
+                bumped.push(racer.address);
+            }
+            Err(e) => {
+                tracing::warn!("{} | failed to re-sign the raced claim: {e}", racer.address);
+                (racer.request, racer.raw) = previous;
+            }
+        }
+    }
+
+    let accepted = broadcast(
+        providers,
+        racers,
+        |racer| bumped.contains(&racer.address),
+        opened,
+    )
+    .await;
+    for index in accepted {
+        tracing::warn!(
+            "{} | raced claim is still pending, bumped its fees at nonce {}",
+            racers[index].address,
+            racers[index].nonce()
+        );
+        record_sent(&racers[index]).await;
+    }
+}
+
+// Wallets whose raced claim didn't land go through the regular pipeline afterwards, a claim still
+// pending at TX_DEADLINE is cancelled first so theirs doesn't queue behind it
+pub async fn race<P>(
+    providers: &ProviderPool<P>,
+    wallets: &[Arc<EthereumWallet>],
+    race_config: &RaceConfig,
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<RpcTransport, Ethereum> + 'static,
+{
+    let provider = providers.choose(&mut thread_rng());
+
+    let mut racers = vec![];
+    for wallet in wallets {
+        match presign(provider.as_ref(), wallet, race_config, config).await {
+            Ok(racer) => racers.push(racer),
+            Err(e) => tracing::warn!("Leaving a wallet out of the race: {e}"),
+        }
+    }
+    let Some(probe) = racers.first().map(|racer| racer.request.clone()) else {
+        tracing::warn!("No wallet could be pre-signed, skipping the race");
+        return Ok(());
+    };
+    tracing::info!("Pre-signed {} claims for the race", racers.len());
+
+    let Some((open_block, opened)) = wait_for_open(providers, &probe, race_config).await else {
+        tracing::warn!(
+            "Claims didn't open within {}s, skipping the race",
+            race_config.open_window
+        );
+        return Ok(());
+    };
+    let accepted = broadcast(providers, &mut racers, |_| true, opened).await;
+    tracing::info!(
+        "Claims opened at block {open_block}, broadcast {} claims in {:?}",
+        racers.len(),
+        opened.elapsed()
+    );
+
+    for index in accepted {
+        let racer = &racers[index];
+        // The pipeline's later sends of the wallet take the nonce after the raced claim
+        nonces::sent(racer.address, racer.nonce());
+        record_sent(racer).await;
+    }
+
+    let deadline = opened + Duration::from_secs(config.tx_deadline);
+    let inclusion_timeout = Duration::from_secs(config.tx_inclusion_timeout);
+    wait_for_receipts(provider.as_ref(), &mut racers, inclusion_timeout).await;
+
+    for _ in 0..config.max_fee_bumps {
+        if !racers.iter().any(Racer::is_pending) || Instant::now() >= deadline {
+            break;
+        }
+        bump_pending(providers, &mut racers, opened, config).await;
+        let timeout = inclusion_timeout.min(deadline.saturating_duration_since(Instant::now()));
+        wait_for_receipts(provider.as_ref(), &mut racers, timeout).await;
+    }
+    // The last fee level keeps its chance until the deadline
+    let remaining = deadline.saturating_duration_since(Instant::now());
+    wait_for_receipts(provider.as_ref(), &mut racers, remaining).await;
+
+    let mut won = 0;
+    for racer in &racers {
+        match (racer.status, racer.block) {
+            (Some(true), Some(block)) => {
+                won += 1;
+                state::update(racer.address, |wallet| wallet.claimed = true).await;
+                tracing::info!(
+                    "{} | raced claim mined {} blocks after the opening",
+                    racer.address,
+                    block.saturating_sub(open_block)
+                );
+            }
+            (Some(false), _) => tracing::warn!("{} | raced claim reverted", racer.address),
+            _ if racer.accepted == 0 => {
+                tracing::warn!("{} | no endpoint accepted the raced claim", racer.address)
+            }
+            _ => {
+                tracing::warn!(
+                    "{} | raced claim is still pending after {} bumps, cancelling it",
+                    racer.address,
+                    racer.bumps
+                );
+                cancel_transaction(
+                    provider.as_ref(),
+                    racer.wallet.clone(),
+                    &racer.request,
+                    config,
+                )
+                .await;
+                nonces::abandoned(racer.address, racer.nonce());
+            }
+        }
+    }
+    tracing::info!("Won {won}/{} raced claims", racers.len());
+
+    write_report(&racers, open_block).await?;
+    tracing::info!("Race timings written to {RACE_FILE_PATH}");
+
+    Ok(())
+}
@@ -0,0 +1,185 @@
+use std::{
+    sync::Arc,
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use alloy::{
+    rpc::json_rpc::{RequestPacket, ResponsePacket},
+    transports::{TransportError, TransportFut},
+};
+use tokio::{sync::Mutex, time::Instant};
+use tower::{Layer, Service};
+
+use crate::usage::record_throttled;
+
+// Token bucket holding up to one second of requests, callers reserve tokens up front and the
+// balance goes negative while they queue, so waiters are served in arrival order
+struct Bucket {
+    rate: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl Bucket {
+    async fn acquire(&self, requests: usize) -> Duration {
+        let wait = {
+            let mut state = self.state.lock().await;
+            let (tokens, refilled) = &mut *state;
+            let now = Instant::now();
+            *tokens = (*tokens + now.duration_since(*refilled).as_secs_f64() * self.rate)
+                .min(self.rate.max(1.0));
+            *refilled = now;
+            *tokens -= requests as f64;
+
+            match *tokens < 0.0 {
+                true => Duration::from_secs_f64(-*tokens / self.rate),
+                false => Duration::ZERO,
+            }
+        };
+
+        tokio::time::sleep(wait).await;
+        wait
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    endpoint: Arc<str>,
+    bucket: Option<Arc<Bucket>>,
+}
+
+impl RateLimitLayer {
+    // Requests per second, `None` lets everything through
+    pub fn new(endpoint: &str, rate: Option<f64>) -> Self {
+        let bucket = rate.filter(|rate| *rate > 0.0).map(|rate| {
+            Arc::new(Bucket {
+                rate,
+                state: Mutex::new((rate.max(1.0), Instant::now())),
+            })
+        });
+
+        Self {
+            endpoint: endpoint.into(),
+            bucket,
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimitService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimitService {
+            inner,
+            endpoint: self.endpoint.clone(),
+            bucket: self.bucket.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimitService<S> {
+    inner: S,
+    endpoint: Arc<str>,
+    bucket: Option<Arc<Bucket>>,
+}
+
+impl<S> Service<RequestPacket> for RateLimitService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let Some(bucket) = self.bucket.clone() else {
+            return Box::pin(self.inner.call(request));
+        };
+
+        // The inner service is called once the bucket allows it, which happens after this returns
+        let mut inner = self.inner.clone();
+        let endpoint = self.endpoint.clone();
+
+        Box::pin(async move {
+            let wait = bucket.acquire(request.len()).await;
+            if !wait.is_zero() {
+                record_throttled(&endpoint, wait);
+            }
+
+            inner.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bucket(rate: f64) -> Bucket {
+        Bucket {
+            rate,
+            state: Mutex::new((rate.max(1.0), Instant::now())),
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn refills_up_to_one_second_of_requests() {
+        let bucket = bucket(4.0);
+        assert_eq!(bucket.acquire(4).await, Duration::ZERO);
+
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(bucket.acquire(2).await, Duration::ZERO);
+        assert_eq!(bucket.acquire(1).await, Duration::from_millis(250));
+
+        // A long pause refills no more than the rate
+        tokio::time::advance(Duration::from_secs(10)).await;
+        assert_eq!(bucket.acquire(4).await, Duration::ZERO);
+        assert_eq!(bucket.acquire(1).await, Duration::from_millis(250));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn balance_goes_negative_for_a_large_batch() {
+        let bucket = bucket(2.0);
+        assert_eq!(bucket.acquire(6).await, Duration::from_secs(2));
+
+        // The batch slept off its own debt, the next request waits for a fresh token
+        assert_eq!(bucket.acquire(1).await, Duration::from_millis(500));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn waiters_are_served_in_arrival_order() {
+        let bucket = Arc::new(bucket(1.0));
+        let start = Instant::now();
+        bucket.acquire(1).await;
+
+        let waiters: Vec<_> = (0..3)
+            .map(|_| {
+                let bucket = bucket.clone();
+                tokio::spawn(async move {
+                    let wait = bucket.acquire(1).await;
+                    (wait, start.elapsed())
+                })
+            })
+            .collect();
+
+        let mut served = vec![];
+        for waiter in waiters {
+            served.push(waiter.await.unwrap());
+        }
+        assert_eq!(
+            served,
+            (1..=3)
+                .map(|i| (Duration::from_secs(i), Duration::from_secs(i)))
+                .collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,36 @@
+use std::{
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use crate::config::RelayerConfig;
+use alloy::{network::EthereumWallet, primitives::Address, signers::local::PrivateKeySigner};
+
+static RELAYER: OnceLock<Relayer> = OnceLock::new();
+
+// Submits every claim, the distributor credits the account in the calldata rather than the sender
+pub struct Relayer {
+    pub wallet: Arc<EthereumWallet>,
+    pub address: Address,
+}
+
+pub fn init_relayer(relayer_config: Option<&RelayerConfig>) -> eyre::Result<()> {
+    let Some(relayer_config) = relayer_config else {
+        return Ok(());
+    };
+
+    let signer = PrivateKeySigner::from_str(relayer_config.private_key.trim())?;
+    let address = signer.address();
+    tracing::info!("Claims are relayed from {address}");
+
+    RELAYER
+        .set(Relayer {
+            wallet: Arc::new(EthereumWallet::new(signer)),
+            address,
+        })
+        .map_err(|_| eyre::eyre!("The relayer is already initialized"))
+}
+
+pub fn relayer() -> Option<&'static Relayer> {
+    RELAYER.get()
+}
@@ -0,0 +1,172 @@
+use std::{collections::BTreeMap, path::Path};
+
+use alloy::primitives::{Address, TxHash, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    constants::{REPORT_CSV_FILE_PATH, REPORT_JSON_FILE_PATH},
+    metrics::Stage,
+    price::usd_value,
+    state,
+    utils::read_file_lines,
+};
+
+const NOT_ELIGIBLE: &str = "not eligible";
+
+#[derive(Deserialize)]
+struct Entry {
+    event: String,
+    address: Option<Address>,
+    from: Option<Address>,
+    amount: Option<U256>,
+    tx_hash: Option<TxHash>,
+    stage: Option<Stage>,
+    status: Option<bool>,
+    gas_used: Option<u128>,
+    effective_gas_price: Option<u128>,
+    error: Option<String>,
+    reason: Option<String>,
+}
+
+#[derive(Serialize, Default)]
+struct ReportRow {
+    address: Address,
+    eligible: Option<bool>,
+    claimed_amount: Option<U256>,
+    usd_value: Option<f64>,
+    claim_tx: Option<TxHash>,
+    transfer_tx: Option<TxHash>,
+    recipient: Option<Address>,
+    gas_spent: U256,
+    completed: bool,
+    failed_stages: Vec<Stage>,
+    error: Option<String>,
+    notes: Option<String>,
+}
+
+fn csv_field<T: ToString>(value: &Option<T>) -> String {
+    value.as_ref().map(T::to_string).unwrap_or_default()
+}
+
+// Errors and notes are free text, so they are quoted and stripped of anything that would break the row
+fn csv_text(value: &Option<String>) -> String {
+    match value {
+        Some(text) => format!("\"{}\"", text.replace('"', "'").replace('\n', " ")),
+        None => String::new(),
+    }
+}
+
+pub async fn export_report(
+    journal_path: impl AsRef<Path>,
+    recipients: &BTreeMap<Address, Address>,
+    usd_price: Option<f64>,
+) -> eyre::Result<U256> {
+    let mut rows: BTreeMap<Address, ReportRow> = recipients
+        .iter()
+        .map(|(address, recipient)| {
+            let row = ReportRow {
+                address: *address,
+                recipient: Some(*recipient),
+                ..Default::default()
+            };
+            (*address, row)
+        })
+        .collect();
+
+    for line in read_file_lines(&journal_path).await? {
+        let entry: Entry = serde_json::from_str(&line)?;
+        let Some(address) = entry.address.or(entry.from) else {
+            continue;
+        };
+        // Funder transactions and wallets outside this run don't get a row
+        let Some(row) = rows.get_mut(&address) else {
+            continue;
+        };
+
+        match entry.event.as_str() {
+            "proof_fetched" => {
+                row.eligible = Some(true);
+                row.claimed_amount = entry.amount;
+            }
+            "receipt" => {
+                let gas_spent = U256::from(entry.gas_used.unwrap_or_default())
+                    * U256::from(entry.effective_gas_price.unwrap_or_default());
+                row.gas_spent += gas_spent;
+
+                if entry.status == Some(true) {
+                    match entry.stage {
+                        Some(Stage::Claim) => row.claim_tx = entry.tx_hash,
+                        Some(Stage::Transfer | Stage::Sell) => row.transfer_tx = entry.tx_hash,
+                        _ => {}
+                    }
+                }
+            }
+            "skip" => {
+                if entry.reason.as_deref() == Some(NOT_ELIGIBLE) {
+                    row.eligible = Some(false);
+                } else {
+                    row.error = entry.reason;
+                }
+            }
+            "stage_failed" => row.failed_stages.extend(entry.stage),
+            "retry" => row.error = entry.error,
+            "completed" => {
+                row.completed = true;
+                row.error = None;
+            }
+            _ => {}
+        }
+    }
+
+    for row in rows.values_mut() {
+        row.usd_value = row
+            .claimed_amount
+            .zip(usd_price)
+            .map(|(amount, price)| usd_value(amount, price));
+        if let Some(wallet) = state::get(row.address).await? {
+            row.notes = Some(wallet.notes_summary()).filter(|notes| !notes.is_empty());
+        }
+    }
+
+    let mut lines = vec![String::from(
+        "address,eligible,claimed_amount,usd_value,claim_tx,transfer_tx,recipient,gas_spent_wei,completed,failed_stages,error,notes",
+    )];
+    lines.extend(rows.values().map(|row| {
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{},{}",
+            row.address,
+            csv_field(&row.eligible),
+            csv_field(&row.claimed_amount),
+            csv_field(&row.usd_value.map(|value| format!("{value:.2}"))),
+            csv_field(&row.claim_tx),
+            csv_field(&row.transfer_tx),
+            csv_field(&row.recipient),
+            row.gas_spent,
+            row.completed,
+            row.failed_stages
+                .iter()
+                .map(Stage::to_string)
+                .collect::<Vec<_>>()
+                .join(";"),
+            csv_text(&row.error),
+            csv_text(&row.notes),
+        )
+    }));
+    tokio::fs::write(REPORT_CSV_FILE_PATH, lines.join("\n") + "\n").await?;
+
+    let claimed_total = rows
+        .values()
+        .filter(|row| row.claim_tx.is_some())
+        .filter_map(|row| row.claimed_amount)
+        .sum();
+
+    let rows: Vec<_> = rows.into_values().collect();
+    tokio::fs::write(REPORT_JSON_FILE_PATH, serde_json::to_string_pretty(&rows)?).await?;
+
+    tracing::info!(
+        "Report of {} wallets written to {REPORT_CSV_FILE_PATH} and {REPORT_JSON_FILE_PATH}",
+        rows.len()
+    );
+
+    Ok(claimed_total)
+}
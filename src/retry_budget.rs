@@ -0,0 +1,95 @@
+use std::{
+    collections::HashMap,
+    sync::{LazyLock, Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use alloy::primitives::Address;
+
+use crate::priority::{priority_of, Priority};
+
+struct Limits {
+    attempts: Option<u64>,
+    class_attempts: HashMap<Priority, u64>,
+    time: Option<Duration>,
+}
+
+struct Usage {
+    started: Instant,
+    attempts: u64,
+}
+
+static LIMITS: OnceLock<Limits> = OnceLock::new();
+static USAGE: LazyLock<Mutex<HashMap<Address, Usage>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn init_retry_budget(
+    attempts: Option<u64>,
+    secs: Option<u64>,
+    class_attempts: HashMap<Priority, u64>,
+) {
+    if attempts.is_none() && secs.is_none() && class_attempts.is_empty() {
+        return;
+    }
+
+    let limits = Limits {
+        attempts,
+        class_attempts,
+        time: secs.map(Duration::from_secs),
+    };
+
+    if LIMITS.set(limits).is_err() {
+        tracing::warn!("Retry budget is already initialized");
+    }
+}
+
+fn remaining(limits: &Limits, address: Address, usage: &Usage) -> bool {
+    let attempts_left = limits
+        .class_attempts
+        .get(&priority_of(address))
+        .or(limits.attempts.as_ref())
+        .is_none_or(|attempts| usage.attempts < *attempts);
+    let time_left = limits
+        .time
+        .is_none_or(|time| usage.started.elapsed() < time);
+
+    attempts_left && time_left
+}
+
+// Records one retry of any stage, returns false once the wallet has used up its budget
+pub fn spend(address: Address) -> bool {
+    let Some(limits) = LIMITS.get() else {
+        return true;
+    };
+
+    let mut usage = USAGE.lock().unwrap();
+    let usage = usage.entry(address).or_insert_with(|| Usage {
+        started: Instant::now(),
+        attempts: 0,
+    });
+    usage.attempts += 1;
+
+    remaining(limits, address, usage)
+}
+
+pub fn ensure_available(address: Address) -> eyre::Result<()> {
+    let Some(limits) = LIMITS.get() else {
+        return Ok(());
+    };
+
+    let mut usage = USAGE.lock().unwrap();
+    let usage = usage.entry(address).or_insert_with(|| Usage {
+        started: Instant::now(),
+        attempts: 0,
+    });
+
+    if !remaining(limits, address, usage) {
+        eyre::bail!(
+            "Retry budget of {address} is exhausted after {} retries in {}s",
+            usage.attempts,
+            usage.started.elapsed().as_secs()
+        );
+    }
+
+    Ok(())
+}
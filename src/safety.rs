@@ -0,0 +1,50 @@
+use std::collections::BTreeSet;
+
+use alloy::{network::Ethereum, primitives::Address, providers::Provider, transports::Transport};
+
+use crate::config::{Config, ContractRecipientAction};
+
+// A token sent to a contract that can't move it is gone, so the recipients are looked at once
+// before anything is claimed
+pub async fn check_recipients<P, T>(
+    provider: &P,
+    config: &Config,
+    recipients: impl IntoIterator<Item = Address>,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let Some(safety_config) = &config.safety else {
+        return Ok(());
+    };
+
+    let recipients: BTreeSet<_> = recipients
+        .into_iter()
+        .filter(|recipient| !safety_config.contract_allowlist.contains(recipient))
+        .collect();
+
+    let mut contracts = vec![];
+    for recipient in recipients {
+        if !provider.get_code_at(recipient).await?.is_empty() {
+            contracts.push(recipient);
+        }
+    }
+    if contracts.is_empty() {
+        return Ok(());
+    }
+
+    match safety_config.contract_recipients {
+        ContractRecipientAction::Warn => {
+            for contract in &contracts {
+                tracing::warn!(
+                    "Recipient {contract} is a contract, add it to CONTRACT_ALLOWLIST if it can move the tokens"
+                );
+            }
+            Ok(())
+        }
+        ContractRecipientAction::Abort => eyre::bail!(
+            "Recipients {contracts:?} are contracts, add them to CONTRACT_ALLOWLIST if they can move the tokens"
+        ),
+    }
+}
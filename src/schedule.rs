@@ -0,0 +1,147 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::{Ethereum, TransactionBuilder},
+    primitives::Address,
+    providers::Provider,
+    rpc::types::TransactionRequest,
+    transports::Transport,
+};
+use rand::thread_rng;
+use serde::Deserialize;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::{
+    claimer::claim_call_for,
+    config::Config,
+    journal::unix_timestamp,
+    proof::{fetch_proof, ProofResponse},
+};
+
+// Long sleeps are cut into slices so progress shows up in the logs
+const COUNTDOWN_STEP: Duration = Duration::from_secs(60);
+// A proof API that keeps failing is only asked for a few wallets per round, not hammered
+const PROBE_WALLETS: usize = 3;
+const PROBE_PROOF_RETRY: Duration = Duration::from_secs(30);
+
+#[derive(Deserialize, Clone, Debug)]
+#[serde(untagged)]
+pub enum StartAt {
+    Unix(u64),
+    Rfc3339(String),
+}
+
+impl StartAt {
+    pub fn unix_timestamp(&self) -> eyre::Result<u64> {
+        match self {
+            StartAt::Unix(timestamp) => Ok(*timestamp),
+            StartAt::Rfc3339(datetime) => {
+                let timestamp = OffsetDateTime::parse(datetime, &Rfc3339)
+                    .map_err(|e| eyre::eyre!("START_AT {datetime:?} is not RFC3339: {e}"))?
+                    .unix_timestamp();
+                Ok(u64::try_from(timestamp)?)
+            }
+        }
+    }
+}
+
+pub async fn wait_for_start(start_at: &StartAt) -> eyre::Result<()> {
+    let start = start_at.unix_timestamp()?;
+
+    loop {
+        let remaining = start.saturating_sub(unix_timestamp());
+        if remaining == 0 {
+            return Ok(());
+        }
+
+        tracing::info!("Waiting {remaining}s for START_AT {start}");
+        tokio::time::sleep(Duration::from_secs(remaining).min(COUNTDOWN_STEP)).await;
+    }
+}
+
+async fn probe_proof(addresses: &[Address], config: &Config) -> Option<(Address, ProofResponse)> {
+    let mut rng = thread_rng();
+    let mut failures = 0;
+
+    for address in addresses {
+        let proxy = config.proxy_for(*address, &mut rng);
+        match fetch_proof(*address, &proxy, config).await {
+            Ok(ProofResponse::Ineligible) => {}
+            Ok(proof_response) => return Some((*address, proof_response)),
+            Err(e) => {
+                tracing::debug!("Readiness probe could not get a proof for {address}: {e}");
+                failures += 1;
+                if failures == PROBE_WALLETS {
+                    break;
+                }
+            }
+        }
+    }
+
+    None
+}
+
+// Claims are live once a claim of a wallet that hasn't claimed yet simulates without reverting
+pub async fn wait_until_claimable<P, T>(
+    provider: Arc<P>,
+    addresses: &[Address],
+    config: &Config,
+) -> eyre::Result<()>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    if addresses.is_empty() {
+        return Ok(());
+    }
+
+    let poll_interval = Duration::from_millis(config.readiness_poll_interval);
+    let mut probe = None;
+    let mut last_logged: Option<Instant> = None;
+
+    tracing::info!("Waiting for claims to go live");
+    loop {
+        if probe.is_none() {
+            probe = probe_proof(addresses, config).await;
+            if probe.is_none() {
+                tracing::info!("No proof to probe with yet, retrying in {PROBE_PROOF_RETRY:?}");
+                tokio::time::sleep(PROBE_PROOF_RETRY).await;
+                continue;
+            }
+        }
+
+        if let Some((
+            address,
+            ProofResponse::Eligible {
+                proof,
+                amount,
+                fields,
+            },
+        )) = &probe
+        {
+            let (to, input) = claim_call_for(*address, *amount, proof.clone(), fields, config)?;
+            let simulation = TransactionRequest::default()
+                .with_from(*address)
+                .with_to(to)
+                .with_input(input);
+
+            match provider.call(&simulation).await {
+                Ok(_) => {
+                    tracing::info!("Claims are live, simulated claim of {address} succeeded");
+                    return Ok(());
+                }
+                Err(e) => {
+                    if last_logged.is_none_or(|logged| logged.elapsed() >= COUNTDOWN_STEP) {
+                        tracing::info!("Claims are not live yet: {e}");
+                        last_logged = Some(Instant::now());
+                    }
+                }
+            }
+        }
+
+        tokio::time::sleep(poll_interval).await;
+    }
+}
@@ -0,0 +1,146 @@
+use std::{path::Path, str::FromStr, sync::OnceLock};
+
+use alloy::primitives::{utils::format_units, Address, U256};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::{priority::priority_of, token::token_info, wallets};
+
+// Bounds a runaway loop in a rule to a few milliseconds instead of a stuck wallet task
+const MAX_OPERATIONS: u64 = 100_000;
+
+static SCRIPT: OnceLock<Script> = OnceLock::new();
+
+struct Script {
+    engine: Engine,
+    ast: AST,
+}
+
+// What the `decide` function of the rules script asked for, every field defaults to the config
+#[derive(Default, Debug)]
+pub struct Decision {
+    pub recipient: Option<Address>,
+    pub hold: Option<String>,
+    pub skip_sell: bool,
+    pub skip_stake: bool,
+}
+
+pub fn init_script(path: Option<&Path>) -> eyre::Result<()> {
+    let Some(path) = path else {
+        return Ok(());
+    };
+
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+    let ast = engine
+        .compile_file(path.to_path_buf())
+        .map_err(|e| eyre::eyre!("Failed to compile {}: {e}", path.display()))?;
+    if !ast
+        .iter_functions()
+        .any(|function| function.name == "decide" && function.params.len() == 1)
+    {
+        eyre::bail!("{} has no `fn decide(wallet)`", path.display());
+    }
+    tracing::info!("Loaded wallet rules from {}", path.display());
+
+    SCRIPT
+        .set(Script { engine, ast })
+        .map_err(|_| eyre::eyre!("The rules script is already loaded"))
+}
+
+fn parse_decision(result: Dynamic) -> eyre::Result<Decision> {
+    let mut decision = Decision::default();
+    if result.is_unit() {
+        return Ok(decision);
+    }
+
+    let type_name = result.type_name();
+    let map = result
+        .try_cast::<Map>()
+        .ok_or_else(|| eyre::eyre!("decide returned a {type_name} instead of a map"))?;
+    for (key, value) in map {
+        let value_type = value.type_name();
+        let expected =
+            |kind: &str| eyre::eyre!("decide returned {key} as {value_type}, not {kind}");
+        match key.as_str() {
+            "recipient" => {
+                let recipient = value.into_string().map_err(|_| expected("a string"))?;
+                decision.recipient = Some(Address::from_str(&recipient)?);
+            }
+            "hold" => decision.hold = Some(value.into_string().map_err(|_| expected("a string"))?),
+            "skip_sell" => decision.skip_sell = value.as_bool().map_err(|_| expected("a bool"))?,
+            "skip_stake" => decision.skip_stake = value.as_bool().map_err(|_| expected("a bool"))?,
+            other => eyre::bail!(
+                "decide returned the unknown key {other}, expected recipient, hold, skip_sell or skip_stake"
+            ),
+        }
+    }
+
+    Ok(decision)
+}
+
+pub fn decide(address: Address, recipient: Address, allocation: U256) -> eyre::Result<Decision> {
+    let Some(script) = SCRIPT.get() else {
+        return Ok(Decision::default());
+    };
+
+    let allocation: f64 = format_units(allocation, token_info().decimals)?.parse()?;
+    let mut wallet = Map::new();
+    wallet.insert("address".into(), address.to_string().into());
+    wallet.insert("recipient".into(), recipient.to_string().into());
+    wallet.insert("allocation".into(), allocation.into());
+    wallet.insert(
+        "priority".into(),
+        format!("{:?}", priority_of(address)).to_lowercase().into(),
+    );
+    wallet.insert(
+        "label".into(),
+        wallets::label(address).map_or(Dynamic::UNIT, Dynamic::from),
+    );
+
+    let result = script
+        .engine
+        .call_fn::<Dynamic>(&mut Scope::new(), &script.ast, "decide", (wallet,))
+        .map_err(|e| eyre::eyre!("Wallet rules failed for {address}: {e}"))?;
+
+    parse_decision(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_the_decision_map() {
+        let engine = Engine::new();
+        let ast = engine
+            .compile(
+                r#"
+                fn decide(wallet) {
+                    if wallet.allocation > 5000.0 {
+                        #{ recipient: "0x000000000000000000000000000000000000dEaD", skip_sell: true }
+                    }
+                }
+                "#,
+            )
+            .unwrap();
+        let call = |allocation: f64| {
+            let mut wallet = Map::new();
+            wallet.insert("allocation".into(), allocation.into());
+            let result = engine
+                .call_fn::<Dynamic>(&mut Scope::new(), &ast, "decide", (wallet,))
+                .unwrap();
+            parse_decision(result).unwrap()
+        };
+
+        let routed = call(6000.0);
+        assert_eq!(
+            routed.recipient,
+            Some(Address::from_str("0x000000000000000000000000000000000000dEaD").unwrap())
+        );
+        assert!(routed.skip_sell && !routed.skip_stake && routed.hold.is_none());
+
+        let untouched = call(10.0);
+        assert!(untouched.recipient.is_none() && !untouched.skip_sell);
+        assert!(parse_decision(Dynamic::from(1_i64)).is_err());
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    num::NonZeroU32,
+    path::Path,
+    sync::{Mutex, OnceLock},
+};
+
+use alloy::primitives::Bytes;
+use ring::{
+    aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use serde::{Deserialize, Serialize};
+
+pub const STATE_PASSPHRASE_ENV: &str = "STATE_PASSPHRASE";
+
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const PBKDF2_ITERATIONS: u32 = 100_000;
+
+static PASSPHRASE: OnceLock<String> = OnceLock::new();
+static KEY: Mutex<Option<CachedKey>> = Mutex::new(None);
+
+struct CachedKey {
+    passphrase: String,
+    salt: [u8; SALT_LEN],
+    key: [u8; KEY_LEN],
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Envelope {
+    salt: Bytes,
+    nonce: Bytes,
+    ciphertext: Bytes,
+}
+
+pub fn init_sealing(enabled: bool) -> eyre::Result<()> {
+    if !enabled {
+        return Ok(());
+    }
+
+    let passphrase = std::env::var(STATE_PASSPHRASE_ENV)
+        .map_err(|_| eyre::eyre!("ENCRYPT_STATE is set but {STATE_PASSPHRASE_ENV} is not"))?;
+    if passphrase.is_empty() {
+        eyre::bail!("{STATE_PASSPHRASE_ENV} must not be empty");
+    }
+
+    PASSPHRASE
+        .set(passphrase)
+        .map_err(|_| eyre::eyre!("State sealing is already initialized"))
+}
+
+// Deriving the key takes a while, so the key of the last seen passphrase and salt is reused
+fn derive_key(passphrase: &str, salt: [u8; SALT_LEN]) -> eyre::Result<LessSafeKey> {
+    let mut cached = KEY.lock().unwrap_or_else(|e| e.into_inner());
+
+    let key = match &*cached {
+        Some(cached) if cached.passphrase == passphrase && cached.salt == salt => cached.key,
+        _ => {
+            let mut key = [0u8; KEY_LEN];
+            pbkdf2::derive(
+                pbkdf2::PBKDF2_HMAC_SHA256,
+                NonZeroU32::new(PBKDF2_ITERATIONS).unwrap(),
+                &salt,
+                passphrase.as_bytes(),
+                &mut key,
+            );
+            *cached = Some(CachedKey {
+                passphrase: passphrase.to_owned(),
+                salt,
+                key,
+            });
+            key
+        }
+    };
+
+    let key = UnboundKey::new(&AES_256_GCM, &key)
+        .map_err(|_| eyre::eyre!("Failed to build the state key"))?;
+    Ok(LessSafeKey::new(key))
+}
+
+fn current_salt(passphrase: &str) -> eyre::Result<[u8; SALT_LEN]> {
+    if let Some(cached) = &*KEY.lock().unwrap_or_else(|e| e.into_inner()) {
+        if cached.passphrase == passphrase {
+            return Ok(cached.salt);
+        }
+    }
+
+    let mut salt = [0u8; SALT_LEN];
+    SystemRandom::new()
+        .fill(&mut salt)
+        .map_err(|_| eyre::eyre!("Failed to generate a salt"))?;
+    Ok(salt)
+}
+
+pub fn is_sealed(contents: &str) -> bool {
+    serde_json::from_str::<Envelope>(contents).is_ok()
+}
+
+pub fn seal_with(passphrase: &str, plaintext: &str) -> eyre::Result<String> {
+    let salt = current_salt(passphrase)?;
+    let key = derive_key(passphrase, salt)?;
+
+    let mut nonce = [0u8; NONCE_LEN];
+    SystemRandom::new()
+        .fill(&mut nonce)
+        .map_err(|_| eyre::eyre!("Failed to generate a nonce"))?;
+
+    let mut ciphertext = plaintext.as_bytes().to_vec();
+    key.seal_in_place_append_tag(
+        Nonce::assume_unique_for_key(nonce),
+        Aad::empty(),
+        &mut ciphertext,
+    )
+    .map_err(|_| eyre::eyre!("Failed to encrypt"))?;
+
+    let envelope = Envelope {
+        salt: Bytes::copy_from_slice(&salt),
+        nonce: Bytes::copy_from_slice(&nonce),
+        ciphertext: ciphertext.into(),
+    };
+    Ok(serde_json::to_string_pretty(&envelope)?)
+}
+
+pub fn unseal_with(passphrase: &str, contents: &str) -> eyre::Result<String> {
+    let envelope: Envelope = serde_json::from_str(contents)?;
+
+    let salt: [u8; SALT_LEN] = envelope.salt.as_ref().try_into()?;
+    let nonce: [u8; NONCE_LEN] = envelope.nonce.as_ref().try_into()?;
+    let key = derive_key(passphrase, salt)?;
+
+    let mut ciphertext = envelope.ciphertext.to_vec();
+    let plaintext = key
+        .open_in_place(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::empty(),
+            &mut ciphertext,
+        )
+        .map_err(|_| eyre::eyre!("Failed to decrypt, wrong passphrase?"))?;
+
+    Ok(String::from_utf8(plaintext.to_vec())?)
+}
+
+pub fn seal(plaintext: &str) -> eyre::Result<String> {
+    match PASSPHRASE.get() {
+        Some(passphrase) => seal_with(passphrase, plaintext),
+        None => Ok(plaintext.to_owned()),
+    }
+}
+
+// Plaintext files are passed through, so turning ENCRYPT_STATE on migrates them on the next save
+pub fn unseal(contents: &str) -> eyre::Result<String> {
+    if !is_sealed(contents) {
+        return Ok(contents.to_owned());
+    }
+
+    let passphrase = PASSPHRASE.get().ok_or_else(|| {
+        eyre::eyre!("State is encrypted, set ENCRYPT_STATE and {STATE_PASSPHRASE_ENV}")
+    })?;
+
+    unseal_with(passphrase, contents)
+        .map_err(|_| eyre::eyre!("Failed to decrypt the state, wrong {STATE_PASSPHRASE_ENV}?"))
+}
+
+pub async fn read_sealed(path: impl AsRef<Path>) -> eyre::Result<Option<String>> {
+    if !path.as_ref().exists() {
+        return Ok(None);
+    }
+
+    let contents = tokio::fs::read_to_string(path).await?;
+    Ok(Some(unseal(&contents)?))
+}
+
+pub async fn write_sealed(path: impl AsRef<Path>, contents: &str) -> eyre::Result<()> {
+    tokio::fs::write(path, seal(contents)?).await?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sealed_contents_open_with_the_same_passphrase_only() {
+        let plaintext = "0xac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80";
+
+        let sealed = seal_with("correct horse", plaintext).unwrap();
+        assert!(!sealed.contains(plaintext));
+        assert_eq!(unseal_with("correct horse", &sealed).unwrap(), plaintext);
+
+        let error = unseal_with("battery staple", &sealed).unwrap_err();
+        assert!(error.to_string().contains("wrong passphrase"));
+    }
+}
@@ -0,0 +1,278 @@
+use std::{
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::{Ethereum, NetworkWallet},
+    primitives::{
+        aliases::{U160, U24},
+        Address, U256,
+    },
+    providers::Provider,
+    sol,
+    sol_types::SolCall,
+    transports::Transport,
+};
+use tokio::sync::Mutex;
+
+use QuoterV2::QuoteExactInputSingleParams;
+use SwapRouter02::{exactInputSingleCall, multicallCall, unwrapWETH9Call, ExactInputSingleParams};
+
+use crate::{
+    campaign::campaign,
+    claimer::{send_transaction, IERC20},
+    config::{Config, SellConfig},
+    metrics::Stage,
+    token::format_token,
+};
+
+sol! {
+    #[sol(rpc)]
+    contract QuoterV2 {
+        struct QuoteExactInputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint256 amountIn;
+            uint24 fee;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        function quoteExactInputSingle(QuoteExactInputSingleParams memory params)
+            external
+            returns (uint256 amountOut, uint160 sqrtPriceX96After, uint32 initializedTicksCrossed, uint256 gasEstimate);
+    }
+
+    #[sol(rpc)]
+    contract SwapRouter02 {
+        struct ExactInputSingleParams {
+            address tokenIn;
+            address tokenOut;
+            uint24 fee;
+            address recipient;
+            uint256 amountIn;
+            uint256 amountOutMinimum;
+            uint160 sqrtPriceLimitX96;
+        }
+
+        function exactInputSingle(ExactInputSingleParams calldata params) external payable returns (uint256 amountOut);
+        function unwrapWETH9(uint256 amountMinimum, address recipient) external payable;
+        function multicall(bytes[] calldata data) external payable returns (bytes[] memory results);
+    }
+}
+
+const REFERENCE_AMOUNT_DIVISOR: u64 = 1000;
+// SwapRouter02 reads this recipient as the router itself, which then unwraps in the same call
+const ROUTER_ADDRESS_THIS: Address = Address::with_last_byte(2);
+
+static LAST_SELL: Mutex<Option<Instant>> = Mutex::const_new(None);
+
+fn apply_slippage(amount: U256, slippage_percent: f64) -> U256 {
+    let keep_bps = 10_000 - (slippage_percent * 100.0).clamp(0.0, 10_000.0) as u64;
+    amount * U256::from(keep_bps) / U256::from(10_000)
+}
+
+pub async fn quote<P, T>(
+    provider: &P,
+    sell_config: &SellConfig,
+    token_out: Address,
+    amount_in: U256,
+) -> eyre::Result<U256>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let quoter = QuoterV2::new(sell_config.quoter, provider);
+    let params = QuoteExactInputSingleParams {
+        tokenIn: campaign().token,
+        tokenOut: token_out,
+        amountIn: amount_in,
+        fee: U24::from(sell_config.pool_fee),
+        sqrtPriceLimitX96: U160::ZERO,
+    };
+
+    Ok(quoter.quoteExactInputSingle(params).call().await?.amountOut)
+}
+
+pub async fn price_impact_percent<P, T>(
+    provider: &P,
+    sell_config: &SellConfig,
+    token_out: Address,
+    amount_in: U256,
+) -> eyre::Result<(f64, U256)>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let reference_in = (amount_in / U256::from(REFERENCE_AMOUNT_DIVISOR)).max(U256::from(1));
+    let reference_out = quote(provider, sell_config, token_out, reference_in).await?;
+    let amount_out = quote(provider, sell_config, token_out, amount_in).await?;
+
+    let spot_rate = f64::from(reference_out) / f64::from(reference_in);
+    let execution_rate = f64::from(amount_out) / f64::from(amount_in);
+
+    let impact = if spot_rate == 0.0 {
+        100.0
+    } else {
+        (1.0 - execution_rate / spot_rate) * 100.0
+    };
+
+    Ok((impact, amount_out))
+}
+
+async fn wait_for_sell_slot<P, T>(
+    provider: &P,
+    sell_config: &SellConfig,
+    token_out: Address,
+    amount_in: U256,
+) -> eyre::Result<U256>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let mut paused = false;
+
+    loop {
+        let mut last_sell = LAST_SELL.lock().await;
+
+        if let Some(last_sell) = *last_sell {
+            let spacing = Duration::from_secs(sell_config.min_sell_interval);
+            tokio::time::sleep(spacing.saturating_sub(last_sell.elapsed())).await;
+        }
+
+        let (impact, amount_out) =
+            price_impact_percent(provider, sell_config, token_out, amount_in).await?;
+
+        if impact <= sell_config.max_price_impact_percent {
+            if paused {
+                tracing::info!("Price impact back to {impact:.2}%, resuming sells");
+            }
+            *last_sell = Some(Instant::now());
+            return Ok(amount_out);
+        }
+
+        drop(last_sell);
+
+        if !paused {
+            tracing::warn!(
+                "Selling {amount_in} would move the price by {impact:.2}% (budget {}%), pausing sells",
+                sell_config.max_price_impact_percent
+            );
+            paused = true;
+        }
+
+        tokio::time::sleep(Duration::from_secs(sell_config.poll_interval)).await;
+    }
+}
+
+pub async fn sell<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    amount_in: U256,
+    token_out: Address,
+    recipient: Address,
+    config: &Config,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let sell_config = config
+        .sell
+        .as_ref()
+        .ok_or_else(|| eyre::eyre!("[sell] section is missing from the config"))?;
+    let address = wallet.default_signer_address();
+
+    let initial_quote = quote(&provider, sell_config, token_out, amount_in).await?;
+
+    let token = IERC20::new(campaign().token, provider.clone());
+    let allowance = token
+        .allowance(address, sell_config.router)
+        .call()
+        .await?
+        ._0;
+
+    if allowance < amount_in {
+        tracing::info!(
+            "Approving {} to the router for {address}",
+            format_token(amount_in)
+        );
+        let input = IERC20::approveCall {
+            spender: sell_config.router,
+            amount: amount_in,
+        }
+        .abi_encode();
+
+        if !send_transaction(
+            provider.clone(),
+            wallet.clone(),
+            campaign().token,
+            Some(input.into()),
+            U256::ZERO,
+            Stage::Approve,
+            config,
+        )
+        .await?
+        {
+            eyre::bail!("Approve for sell reverted");
+        }
+    }
+
+    let quoted_out = wait_for_sell_slot(&provider, sell_config, token_out, amount_in).await?;
+    let tolerance = |amount: U256| apply_slippage(amount, sell_config.slippage_percent);
+
+    if quoted_out < tolerance(initial_quote) {
+        eyre::bail!(
+            "Quote for {address} moved beyond tolerance: {initial_quote} -> {quoted_out}, aborting sell"
+        );
+    }
+
+    let amount_out_minimum = tolerance(quoted_out);
+
+    tracing::info!(
+        "Selling {} from {address}, expecting at least {amount_out_minimum}",
+        format_token(amount_in)
+    );
+
+    let swap = |recipient| {
+        exactInputSingleCall {
+            params: ExactInputSingleParams {
+                tokenIn: campaign().token,
+                tokenOut: token_out,
+                fee: U24::from(sell_config.pool_fee),
+                recipient,
+                amountIn: amount_in,
+                amountOutMinimum: amount_out_minimum,
+                sqrtPriceLimitX96: U160::ZERO,
+            },
+        }
+        .abi_encode()
+    };
+    let input = match sell_config.unwrap_weth {
+        true => multicallCall {
+            data: vec![
+                swap(ROUTER_ADDRESS_THIS).into(),
+                unwrapWETH9Call {
+                    amountMinimum: amount_out_minimum,
+                    recipient,
+                }
+                .abi_encode()
+                .into(),
+            ],
+        }
+        .abi_encode(),
+        false => swap(recipient),
+    };
+
+    send_transaction(
+        provider,
+        wallet,
+        sell_config.router,
+        Some(input.into()),
+        U256::ZERO,
+        Stage::Sell,
+        config,
+    )
+    .await
+}
@@ -0,0 +1,122 @@
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use crate::{constants::PID_FILE_PATH, journal::unix_timestamp};
+
+const LIVENESS_INTERVAL: Duration = Duration::from_secs(30);
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+
+// Services have nobody at the terminal, anything that would prompt has to fail instead
+pub fn is_enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn status_line(status: &str) {
+    println!(
+        "{}",
+        serde_json::json!({
+            "status": status,
+            "pid": std::process::id(),
+            "timestamp": unix_timestamp(),
+        })
+    );
+}
+
+#[cfg(unix)]
+fn notify(state: &str) {
+    use std::os::unix::net::UnixDatagram;
+
+    let Ok(socket_path) = std::env::var("NOTIFY_SOCKET") else {
+        return;
+    };
+
+    let result = UnixDatagram::unbound().and_then(|socket| {
+        #[cfg(target_os = "linux")]
+        if let Some(name) = socket_path.strip_prefix('@') {
+            use std::os::{linux::net::SocketAddrExt, unix::net::SocketAddr};
+
+            let address = SocketAddr::from_abstract_name(name)?;
+            return socket.send_to_addr(state.as_bytes(), &address);
+        }
+
+        socket.send_to(state.as_bytes(), &socket_path)
+    });
+
+    if let Err(e) = result {
+        tracing::warn!("Failed to notify the service manager: {e}");
+    }
+}
+
+#[cfg(not(unix))]
+fn notify(_state: &str) {}
+
+fn liveness_interval() -> Duration {
+    std::env::var("WATCHDOG_USEC")
+        .ok()
+        .and_then(|usec| usec.parse::<u64>().ok())
+        .map(|usec| Duration::from_micros(usec / 2))
+        .unwrap_or(LIVENESS_INTERVAL)
+}
+
+async fn shutdown_signal() -> eyre::Result<&'static str> {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+
+        let mut terminate = signal(SignalKind::terminate())?;
+        let mut interrupt = signal(SignalKind::interrupt())?;
+
+        tokio::select! {
+            _ = terminate.recv() => Ok("SIGTERM"),
+            _ = interrupt.recv() => Ok("SIGINT"),
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        tokio::signal::ctrl_c().await?;
+        Ok("ctrl-c")
+    }
+}
+
+pub fn stop() {
+    notify("STOPPING=1");
+    status_line("stopping");
+
+    if let Err(e) = std::fs::remove_file(PID_FILE_PATH) {
+        tracing::warn!("Failed to remove {PID_FILE_PATH}: {e}");
+    }
+}
+
+pub fn init_service() -> eyre::Result<()> {
+    ENABLED.store(true, Ordering::Relaxed);
+    std::fs::write(PID_FILE_PATH, format!("{}\n", std::process::id()))?;
+
+    tokio::spawn(async {
+        match shutdown_signal().await {
+            Ok(signal) => {
+                tracing::info!("Received {signal}, shutting down");
+                stop();
+                std::process::exit(0);
+            }
+            Err(e) => tracing::error!("Failed to listen for service signals: {e}"),
+        }
+    });
+
+    tokio::spawn(async {
+        let mut interval = tokio::time::interval(liveness_interval());
+        loop {
+            interval.tick().await;
+            notify("WATCHDOG=1");
+            status_line("alive");
+        }
+    });
+
+    notify("READY=1");
+    status_line("ready");
+
+    Ok(())
+}
@@ -0,0 +1,159 @@
+use std::{
+    future::Future,
+    path::PathBuf,
+    str::FromStr,
+    sync::{Arc, OnceLock},
+};
+
+use alloy::{network::EthereumWallet, signers::local::PrivateKeySigner};
+
+use crate::{
+    auth::register_signer,
+    config::{SignerBackendKind, SignerConfig},
+    keyfile::read_key_file,
+};
+
+static EXTERNAL: OnceLock<Vec<Arc<EthereumWallet>>> = OnceLock::new();
+
+// Everything that can hand the claimer wallets to sign with, loaded once at startup
+pub trait SignerBackend {
+    fn name(&self) -> &'static str;
+
+    fn wallets(
+        &self,
+        chain_id: u64,
+    ) -> impl Future<Output = eyre::Result<Vec<Arc<EthereumWallet>>>> + Send;
+}
+
+// One hex private key per line, the only backend whose keys can also sign proof requests
+pub struct LocalKeys {
+    pub path: PathBuf,
+}
+
+impl SignerBackend for LocalKeys {
+    fn name(&self) -> &'static str {
+        "local keys"
+    }
+
+    async fn wallets(&self, _chain_id: u64) -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+        read_key_file(&self.path)
+            .await?
+            .lines()
+            .enumerate()
+            .map(|(index, line)| {
+                let signer = PrivateKeySigner::from_str(line.trim()).map_err(|e| {
+                    eyre::eyre!(
+                        "{} line {}: invalid private key: {e}",
+                        self.path.display(),
+                        index + 1
+                    )
+                })?;
+                register_signer(&signer);
+                Ok(Arc::new(EthereumWallet::new(signer)))
+            })
+            .collect()
+    }
+}
+
+#[cfg_attr(not(feature = "ledger"), allow(dead_code))]
+pub struct Ledger {
+    pub legacy: bool,
+    pub indexes: Vec<usize>,
+}
+
+impl SignerBackend for Ledger {
+    fn name(&self) -> &'static str {
+        "Ledger"
+    }
+
+    #[cfg(not(feature = "ledger"))]
+    async fn wallets(&self, _chain_id: u64) -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+        eyre::bail!("The claimer was built without the `ledger` feature")
+    }
+
+    #[cfg(feature = "ledger")]
+    async fn wallets(&self, chain_id: u64) -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+        use alloy::signers::ledger::{HDPath, LedgerSigner};
+
+        let mut wallets = vec![];
+        for index in &self.indexes {
+            let path = match self.legacy {
+                true => HDPath::Legacy(*index),
+                false => HDPath::LedgerLive(*index),
+            };
+            let signer = LedgerSigner::new(path, Some(chain_id)).await?;
+            wallets.push(Arc::new(EthereumWallet::new(signer)));
+        }
+
+        Ok(wallets)
+    }
+}
+
+#[cfg_attr(not(feature = "aws-kms"), allow(dead_code))]
+pub struct AwsKms {
+    pub key_ids: Vec<String>,
+}
+
+impl SignerBackend for AwsKms {
+    fn name(&self) -> &'static str {
+        "AWS KMS"
+    }
+
+    #[cfg(not(feature = "aws-kms"))]
+    async fn wallets(&self, _chain_id: u64) -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+        eyre::bail!("The claimer was built without the `aws-kms` feature")
+    }
+
+    // Credentials and region come from the standard AWS environment, profile or instance role
+    #[cfg(feature = "aws-kms")]
+    async fn wallets(&self, chain_id: u64) -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+        use alloy::signers::aws::AwsSigner;
+
+        let aws_config = aws_config::load_defaults(aws_config::BehaviorVersion::latest()).await;
+        let client = aws_sdk_kms::Client::new(&aws_config);
+
+        let mut wallets = vec![];
+        for key_id in &self.key_ids {
+            let signer = AwsSigner::new(client.clone(), key_id.clone(), Some(chain_id)).await?;
+            wallets.push(Arc::new(EthereumWallet::new(signer)));
+        }
+
+        Ok(wallets)
+    }
+}
+
+async fn load(backend: impl SignerBackend, chain_id: u64) -> eyre::Result<()> {
+    let wallets = backend.wallets(chain_id).await?;
+    tracing::info!("Loaded {} wallets from {}", wallets.len(), backend.name());
+
+    EXTERNAL
+        .set(wallets)
+        .map_err(|_| eyre::eyre!("Signer wallets are already loaded"))
+}
+
+pub async fn init_signer(signer_config: Option<&SignerConfig>, chain_id: u64) -> eyre::Result<()> {
+    let Some(signer_config) = signer_config else {
+        return Ok(());
+    };
+
+    match signer_config.backend {
+        SignerBackendKind::Ledger => {
+            let backend = Ledger {
+                legacy: signer_config.ledger_legacy_path,
+                indexes: signer_config.ledger_indexes.clone(),
+            };
+            load(backend, chain_id).await
+        }
+        SignerBackendKind::AwsKms => {
+            let backend = AwsKms {
+                key_ids: signer_config.kms_key_ids.clone(),
+            };
+            load(backend, chain_id).await
+        }
+    }
+}
+
+// Wallets of the configured backend, they follow the keys of private_keys.txt
+pub fn external_wallets() -> &'static [Arc<EthereumWallet>] {
+    EXTERNAL.get().map_or(&[], Vec::as_slice)
+}
@@ -0,0 +1,119 @@
+use std::path::Path;
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::utils::format_ether,
+    providers::Provider,
+};
+use rand::thread_rng;
+
+use crate::{
+    campaign::campaign,
+    claimer::{TokenDistributor, IERC20},
+    config::Config,
+    constants::{MULTICALL3_ADDRESS, SNAPSHOTS_FOLDER_PATH},
+    journal::unix_timestamp,
+    multicall::{self, Multicall3},
+    pool::init_provider_pool,
+    utils::read_private_keys,
+    watchlist::{balance_calls, format_balances, resolve_watchlist},
+};
+
+const SNAPSHOT_BATCH_SIZE: usize = 100;
+const CALLS_PER_WALLET: usize = 4;
+
+pub async fn snapshot(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let wallets = read_private_keys().await?;
+
+    let watchlist = resolve_watchlist(&providers.choose(&mut thread_rng()), &config).await?;
+    let calls_per_wallet = CALLS_PER_WALLET + watchlist.len();
+
+    let mut header = String::from("wallet,eth_balance,scr_balance,nonce,has_claimed,delegation");
+    for token in &watchlist {
+        header.push_str(&format!(",{}_balance", token.symbol.to_lowercase()));
+    }
+    let mut lines = vec![header];
+
+    for batch in wallets.chunks(SNAPSHOT_BATCH_SIZE) {
+        let provider = providers.choose(&mut thread_rng());
+        let addresses: Vec<_> = batch
+            .iter()
+            .map(|wallet| {
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet)
+            })
+            .collect();
+
+        let calls = addresses
+            .iter()
+            .flat_map(|address| {
+                [
+                    multicall::call(
+                        MULTICALL3_ADDRESS,
+                        &Multicall3::getEthBalanceCall { addr: *address },
+                    ),
+                    multicall::call(
+                        campaign().token,
+                        &IERC20::balanceOfCall { account: *address },
+                    ),
+                    multicall::call(
+                        campaign().distributor,
+                        &TokenDistributor::hasClaimedCall { user: *address },
+                    ),
+                    multicall::call(
+                        campaign().token,
+                        &IERC20::delegatesCall {
+                            _delegator: *address,
+                        },
+                    ),
+                ]
+                .into_iter()
+                .chain(balance_calls(&watchlist, *address))
+            })
+            .collect();
+        let results = multicall::aggregate(&provider, calls).await?;
+
+        for (address, results) in addresses.iter().zip(results.chunks(calls_per_wallet)) {
+            let nonce = provider.get_transaction_count(*address).await?;
+
+            let eth_balance = multicall::decode::<Multicall3::getEthBalanceCall>(&results[0])
+                .map(|balance| format_ether(balance.balance))
+                .unwrap_or_default();
+            let scr_balance = multicall::decode::<IERC20::balanceOfCall>(&results[1])
+                .map(|balance| balance._0.to_string())
+                .unwrap_or_default();
+            let has_claimed = multicall::decode::<TokenDistributor::hasClaimedCall>(&results[2])
+                .map(|claimed| claimed.claimed.to_string())
+                .unwrap_or_default();
+            let delegation = multicall::decode::<IERC20::delegatesCall>(&results[3])
+                .map(|delegations| {
+                    delegations
+                        ._0
+                        .iter()
+                        .map(|split| format!("{}:{}", split._delegatee, split._numerator))
+                        .collect::<Vec<_>>()
+                        .join("|")
+                })
+                .unwrap_or_default();
+
+            let mut line =
+                format!("{address},{eth_balance},{scr_balance},{nonce},{has_claimed},{delegation}");
+            for balance in format_balances(&watchlist, &results[CALLS_PER_WALLET..]) {
+                line.push_str(&format!(",{balance}"));
+            }
+            lines.push(line);
+        }
+    }
+
+    tokio::fs::create_dir_all(SNAPSHOTS_FOLDER_PATH).await?;
+    let path = Path::new(SNAPSHOTS_FOLDER_PATH).join(format!("snapshot-{}.csv", unix_timestamp()));
+    tokio::fs::write(&path, lines.join("\n") + "\n").await?;
+
+    tracing::info!(
+        "Snapshot of {} wallets written to {}",
+        wallets.len(),
+        path.display()
+    );
+
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use std::{collections::BTreeMap, sync::Arc};
+
+use alloy::{
+    network::{Ethereum, NetworkWallet},
+    primitives::{Address, U256},
+    providers::Provider,
+    sol_types::SolCall,
+    transports::Transport,
+};
+
+use crate::{
+    calldata::encode_call,
+    campaign::campaign,
+    claimer::{send_transaction, IERC20},
+    config::{Config, StakeConfig},
+    metrics::Stage,
+    token::{format_token, parse_token},
+};
+
+pub fn kept_amount(
+    stake_config: &StakeConfig,
+    wallet: Address,
+    amount: U256,
+) -> eyre::Result<U256> {
+    let (keep_percent, keep_amount) = stake_config.keep_for(wallet);
+    if let Some(keep_amount) = keep_amount {
+        let keep_amount = parse_token(keep_amount)?;
+        return Ok(keep_amount.min(amount));
+    }
+
+    let keep_bps = (keep_percent * 100.0).clamp(0.0, 10_000.0) as u64;
+    Ok(amount * U256::from(keep_bps) / U256::from(10_000))
+}
+
+pub async fn stake<P, T, W>(
+    provider: Arc<P>,
+    wallet: Arc<W>,
+    amount: U256,
+    stake_config: &StakeConfig,
+    config: &Config,
+) -> eyre::Result<bool>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+    W: NetworkWallet<Ethereum>,
+{
+    let Some(contract) = stake_config.contract else {
+        return Ok(true);
+    };
+    let address = wallet.default_signer_address();
+
+    let token = IERC20::new(campaign().token, provider.clone());
+    let allowance = token.allowance(address, contract).call().await?._0;
+
+    if allowance < amount {
+        tracing::info!(
+            "Approving {} to the staking contract for {address}",
+            format_token(amount)
+        );
+        let input = IERC20::approveCall {
+            spender: contract,
+            amount,
+        }
+        .abi_encode();
+
+        if !send_transaction(
+            provider.clone(),
+            wallet.clone(),
+            campaign().token,
+            Some(input.into()),
+            U256::ZERO,
+            Stage::Approve,
+            config,
+        )
+        .await?
+        {
+            eyre::bail!("Approve for stake reverted");
+        }
+    }
+
+    let placeholders = BTreeMap::from([
+        (String::from("address"), address.to_string()),
+        (String::from("amount"), amount.to_string()),
+    ]);
+    let input = encode_call(
+        &stake_config.deposit_signature,
+        &stake_config.deposit_args,
+        &placeholders,
+    )?;
+
+    tracing::info!(
+        "Staking {} from {address} into {contract}",
+        format_token(amount)
+    );
+
+    send_transaction(
+        provider,
+        wallet,
+        contract,
+        Some(input),
+        U256::ZERO,
+        Stage::Stake,
+        config,
+    )
+    .await
+}
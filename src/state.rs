@@ -0,0 +1,230 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use alloy::primitives::{Address, TxHash};
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+
+use crate::{
+    claimer::is_dry_run,
+    constants::STATE_FOLDER_PATH,
+    fork::is_forked,
+    journal::unix_timestamp,
+    metrics::Stage,
+    seal::{read_sealed, unseal, write_sealed},
+};
+
+const STATE_FILE_NAME: &str = "wallets.json";
+const RUN_NOTES_FILE_NAME: &str = "runs.json";
+const BACKUPS_FOLDER_NAME: &str = "backups";
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Note {
+    pub timestamp: u64,
+    pub text: String,
+}
+
+impl Note {
+    pub fn new(text: String) -> Self {
+        Self {
+            timestamp: unix_timestamp(),
+            text,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct WalletState {
+    pub proof_fetched: bool,
+    pub claimed: bool,
+    pub transferred: bool,
+    pub tx_hashes: Vec<TxHash>,
+    #[serde(default)]
+    pub failed_stages: Vec<Stage>,
+    pub last_error: Option<String>,
+    #[serde(default)]
+    pub notes: Vec<Note>,
+}
+
+impl WalletState {
+    pub fn notes_summary(&self) -> String {
+        self.notes
+            .iter()
+            .map(|note| note.text.as_str())
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+type State = BTreeMap<Address, WalletState>;
+
+static STATE: Mutex<Option<State>> = Mutex::const_new(None);
+
+async fn load() -> eyre::Result<State> {
+    let path = Path::new(STATE_FOLDER_PATH).join(STATE_FILE_NAME);
+    match read_sealed(path).await? {
+        Some(state_str) => Ok(serde_json::from_str(&state_str)?),
+        None => Ok(BTreeMap::new()),
+    }
+}
+
+// Written to a temporary file first so an interrupted run never leaves a truncated state behind
+async fn save(state: &State) -> eyre::Result<()> {
+    tokio::fs::create_dir_all(STATE_FOLDER_PATH).await?;
+
+    let path = Path::new(STATE_FOLDER_PATH).join(STATE_FILE_NAME);
+    let tmp_path = path.with_extension("json.tmp");
+    write_sealed(&tmp_path, &serde_json::to_string_pretty(state)?).await?;
+    tokio::fs::rename(tmp_path, path).await?;
+
+    Ok(())
+}
+
+pub async fn get(address: Address) -> eyre::Result<Option<WalletState>> {
+    let mut state = STATE.lock().await;
+    if state.is_none() {
+        *state = Some(load().await?);
+    }
+
+    Ok(state
+        .as_ref()
+        .and_then(|state| state.get(&address))
+        .cloned())
+}
+
+pub async fn update(address: Address, change: impl FnOnce(&mut WalletState)) {
+    if is_dry_run() || is_forked() {
+        return;
+    }
+
+    let mut guard = STATE.lock().await;
+    if guard.is_none() {
+        match load().await {
+            Ok(state) => *guard = Some(state),
+            Err(e) => {
+                tracing::warn!("Failed to load the wallet state: {e}");
+                return;
+            }
+        }
+    }
+
+    let state = guard.get_or_insert_with(BTreeMap::new);
+    change(state.entry(address).or_default());
+
+    if let Err(e) = save(state).await {
+        tracing::warn!("Failed to save the wallet state: {e}");
+    }
+}
+
+pub async fn add_note(address: Address, text: String) -> eyre::Result<()> {
+    if is_dry_run() {
+        eyre::bail!("Notes are not saved in dry-run mode");
+    }
+
+    update(address, |wallet| wallet.notes.push(Note::new(text))).await;
+    Ok(())
+}
+
+// Run notes are keyed by the journal file name, the only identifier a run has
+pub async fn add_run_note(journal_path: &Path, text: String) -> eyre::Result<()> {
+    let path = Path::new(STATE_FOLDER_PATH).join(RUN_NOTES_FILE_NAME);
+    let mut notes: BTreeMap<String, Vec<Note>> = match read_sealed(&path).await? {
+        Some(notes_str) => serde_json::from_str(&notes_str)?,
+        None => BTreeMap::new(),
+    };
+
+    let run = journal_path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    notes.entry(run).or_default().push(Note::new(text));
+
+    tokio::fs::create_dir_all(STATE_FOLDER_PATH).await?;
+    write_sealed(path, &serde_json::to_string_pretty(&notes)?).await?;
+
+    Ok(())
+}
+
+async fn list_backups() -> eyre::Result<Vec<PathBuf>> {
+    let folder = Path::new(STATE_FOLDER_PATH).join(BACKUPS_FOLDER_NAME);
+    if !folder.exists() {
+        return Ok(vec![]);
+    }
+
+    let mut backups = vec![];
+    let mut entries = tokio::fs::read_dir(folder).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        if entry.file_type().await?.is_dir() {
+            backups.push(entry.path());
+        }
+    }
+
+    // Backups are named after their unix timestamp
+    backups.sort_by_key(|path| {
+        path.file_name()
+            .and_then(|name| name.to_str()?.parse::<u64>().ok())
+            .unwrap_or_default()
+    });
+    Ok(backups)
+}
+
+// Files are copied as they are on disk, encrypted state stays encrypted in its backups
+pub async fn backup_state(keep: usize) -> eyre::Result<Option<PathBuf>> {
+    let files: Vec<_> = [STATE_FILE_NAME, RUN_NOTES_FILE_NAME]
+        .into_iter()
+        .filter(|name| Path::new(STATE_FOLDER_PATH).join(name).exists())
+        .collect();
+    if keep == 0 || files.is_empty() {
+        return Ok(None);
+    }
+
+    let folder = Path::new(STATE_FOLDER_PATH)
+        .join(BACKUPS_FOLDER_NAME)
+        .join(unix_timestamp().to_string());
+    tokio::fs::create_dir_all(&folder).await?;
+    for name in files {
+        tokio::fs::copy(Path::new(STATE_FOLDER_PATH).join(name), folder.join(name)).await?;
+    }
+
+    let backups = list_backups().await?;
+    for stale in &backups[..backups.len().saturating_sub(keep)] {
+        tokio::fs::remove_dir_all(stale).await?;
+    }
+
+    Ok(Some(folder))
+}
+
+pub async fn restore_state(name: Option<String>) -> eyre::Result<()> {
+    let backups = list_backups().await?;
+    let folder = match name {
+        Some(name) => backups
+            .into_iter()
+            .find(|path| path.file_name().is_some_and(|file| *file == *name))
+            .ok_or_else(|| eyre::eyre!("No state backup named {name}"))?,
+        None => backups
+            .into_iter()
+            .last()
+            .ok_or_else(|| eyre::eyre!("No state backups found"))?,
+    };
+
+    // A backup is only restored if every file in it still decrypts and parses
+    let mut files = vec![];
+    for name in [STATE_FILE_NAME, RUN_NOTES_FILE_NAME] {
+        let path = folder.join(name);
+        if path.exists() {
+            let contents = tokio::fs::read_to_string(&path).await?;
+            serde_json::from_str::<serde_json::Value>(&unseal(&contents)?)
+                .map_err(|e| eyre::eyre!("{} is corrupt: {e}", path.display()))?;
+            files.push((name, contents));
+        }
+    }
+
+    for (name, contents) in files {
+        tokio::fs::write(Path::new(STATE_FOLDER_PATH).join(name), contents).await?;
+    }
+
+    tracing::info!("Restored the state from {}", folder.display());
+    Ok(())
+}
@@ -0,0 +1,40 @@
+use std::{sync::OnceLock, time::Duration};
+
+use tokio::{sync::Mutex, time::Instant};
+
+static THROTTLE: OnceLock<Throttle> = OnceLock::new();
+
+struct Throttle {
+    interval: Duration,
+    next_slot: Mutex<Instant>,
+}
+
+pub fn init_throttle(max_tps: Option<f64>) {
+    let Some(max_tps) = max_tps.filter(|tps| *tps > 0.0) else {
+        return;
+    };
+
+    let throttle = Throttle {
+        interval: Duration::from_secs_f64(1.0 / max_tps),
+        next_slot: Mutex::new(Instant::now()),
+    };
+
+    if THROTTLE.set(throttle).is_err() {
+        tracing::warn!("Transaction throttle is already initialized");
+    }
+}
+
+pub async fn acquire() {
+    let Some(throttle) = THROTTLE.get() else {
+        return;
+    };
+
+    let slot = {
+        let mut next_slot = throttle.next_slot.lock().await;
+        let slot = (*next_slot).max(Instant::now());
+        *next_slot = slot + throttle.interval;
+        slot
+    };
+
+    tokio::time::sleep_until(slot).await;
+}
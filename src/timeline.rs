@@ -0,0 +1,104 @@
+use std::{collections::BTreeMap, path::Path};
+
+use alloy::primitives::Address;
+use serde::Deserialize;
+
+use crate::{constants::TIMELINE_FILE_PATH, metrics::Stage, utils::read_file_lines};
+
+const HISTOGRAM_BUCKETS: u64 = 10;
+const HISTOGRAM_WIDTH: usize = 40;
+
+#[derive(Deserialize)]
+struct Entry {
+    timestamp: u64,
+    event: String,
+    address: Option<Address>,
+    from: Option<Address>,
+    stage: Option<Stage>,
+}
+
+#[derive(Default)]
+struct WalletTimeline {
+    proof_fetched: Option<u64>,
+    claim_broadcast: Option<u64>,
+    claim_mined: Option<u64>,
+    transfer_mined: Option<u64>,
+}
+
+fn format_timestamp(timestamp: Option<u64>) -> String {
+    timestamp.map(|ts| ts.to_string()).unwrap_or_default()
+}
+
+fn log_histogram(claim_broadcasts: &[u64]) {
+    let (Some(first), Some(last)) = (claim_broadcasts.iter().min(), claim_broadcasts.iter().max())
+    else {
+        return;
+    };
+
+    let bucket_span = ((last - first) / HISTOGRAM_BUCKETS).max(1);
+    let mut buckets = vec![0usize; HISTOGRAM_BUCKETS as usize];
+    for timestamp in claim_broadcasts {
+        let bucket = ((timestamp - first) / bucket_span).min(HISTOGRAM_BUCKETS - 1);
+        buckets[bucket as usize] += 1;
+    }
+
+    let peak = buckets.iter().copied().max().unwrap_or(1).max(1);
+    tracing::info!("Claim broadcasts over {}s:", last - first);
+    for (index, count) in buckets.iter().enumerate() {
+        let offset = index as u64 * bucket_span;
+        let bar = "#".repeat(count * HISTOGRAM_WIDTH / peak);
+        tracing::info!("{offset:>7}s | {bar} {count}");
+    }
+}
+
+pub async fn export_timeline(journal_path: impl AsRef<Path>) -> eyre::Result<()> {
+    let mut timelines: BTreeMap<Address, WalletTimeline> = BTreeMap::new();
+
+    for line in read_file_lines(&journal_path).await? {
+        let entry: Entry = serde_json::from_str(&line)?;
+        let Some(address) = entry.address.or(entry.from) else {
+            continue;
+        };
+        let timeline = timelines.entry(address).or_default();
+
+        match (entry.event.as_str(), entry.stage) {
+            ("proof_fetched", _) => {
+                timeline.proof_fetched.get_or_insert(entry.timestamp);
+            }
+            ("tx_sent", Some(Stage::Claim)) => {
+                timeline.claim_broadcast.get_or_insert(entry.timestamp);
+            }
+            ("receipt", Some(Stage::Claim)) => timeline.claim_mined = Some(entry.timestamp),
+            ("receipt", Some(Stage::Transfer | Stage::Sell)) => {
+                timeline.transfer_mined = Some(entry.timestamp)
+            }
+            _ => {}
+        }
+    }
+
+    let mut lines = vec![String::from(
+        "wallet,proof_fetched,claim_broadcast,claim_mined,transfer_mined",
+    )];
+    lines.extend(timelines.iter().map(|(address, timeline)| {
+        format!(
+            "{address},{},{},{},{}",
+            format_timestamp(timeline.proof_fetched),
+            format_timestamp(timeline.claim_broadcast),
+            format_timestamp(timeline.claim_mined),
+            format_timestamp(timeline.transfer_mined),
+        )
+    }));
+    tokio::fs::write(TIMELINE_FILE_PATH, lines.join("\n") + "\n").await?;
+    tracing::info!(
+        "Timeline of {} wallets written to {TIMELINE_FILE_PATH}",
+        timelines.len()
+    );
+
+    let claim_broadcasts: Vec<_> = timelines
+        .values()
+        .filter_map(|timeline| timeline.claim_broadcast)
+        .collect();
+    log_histogram(&claim_broadcasts);
+
+    Ok(())
+}
@@ -0,0 +1,121 @@
+use std::sync::OnceLock;
+
+use alloy::{
+    network::Ethereum,
+    primitives::{
+        utils::{format_units, parse_units},
+        U256,
+    },
+    providers::Provider,
+    transports::Transport,
+};
+
+use crate::{campaign::campaign, claimer::IERC20};
+
+const DEFAULT_SYMBOL: &str = "TOKEN";
+const DEFAULT_DECIMALS: u8 = 18;
+
+static TOKEN: OnceLock<TokenInfo> = OnceLock::new();
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+fn configured() -> TokenInfo {
+    TokenInfo {
+        symbol: campaign()
+            .token_symbol
+            .clone()
+            .unwrap_or_else(|| DEFAULT_SYMBOL.to_owned()),
+        decimals: campaign().token_decimals.unwrap_or(DEFAULT_DECIMALS),
+    }
+}
+
+pub fn token_info() -> &'static TokenInfo {
+    TOKEN.get_or_init(configured)
+}
+
+pub fn format_token(amount: U256) -> String {
+    let token = token_info();
+    let amount = format_units(amount, token.decimals).unwrap_or_else(|_| amount.to_string());
+
+    format!("{amount} ${}", token.symbol)
+}
+
+pub fn parse_token(amount: f64) -> eyre::Result<U256> {
+    Ok(parse_units(&amount.to_string(), token_info().decimals)?.into())
+}
+
+// None while the token has no code, campaigns are often planned before it's deployed
+pub async fn read_onchain<P, T>(provider: &P) -> eyre::Result<Option<TokenInfo>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let address = campaign().token;
+    if provider.get_code_at(address).await?.is_empty() {
+        return Ok(None);
+    }
+
+    let token = IERC20::new(address, provider);
+    Ok(Some(TokenInfo {
+        symbol: token.symbol().call().await?._0,
+        decimals: token.decimals().call().await?._0,
+    }))
+}
+
+pub fn mismatches(onchain: &TokenInfo) -> Vec<String> {
+    let configured = configured();
+    let mut mismatches = vec![];
+
+    if campaign().token_symbol.is_some() && configured.symbol != onchain.symbol {
+        mismatches.push(format!(
+            "TOKEN_SYMBOL is {}, the token says {}",
+            configured.symbol, onchain.symbol
+        ));
+    }
+    if campaign().token_decimals.is_some() && configured.decimals != onchain.decimals {
+        mismatches.push(format!(
+            "TOKEN_DECIMALS is {}, the token says {}",
+            configured.decimals, onchain.decimals
+        ));
+    }
+
+    mismatches
+}
+
+// On-chain values win once the token is live, the configured ones only stand in until then
+pub async fn reconcile_token<P, T>(provider: &P)
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let token = match read_onchain(provider).await {
+        Ok(Some(onchain)) => {
+            for mismatch in mismatches(&onchain) {
+                tracing::warn!("{mismatch}, using the on-chain value");
+            }
+            onchain
+        }
+        Ok(None) => {
+            let configured = configured();
+            tracing::info!(
+                "Token {} is not deployed yet, assuming {} decimals for ${}",
+                campaign().token,
+                configured.decimals,
+                configured.symbol
+            );
+            configured
+        }
+        Err(e) => {
+            tracing::warn!("Failed to read token metadata, using the configured values: {e}");
+            configured()
+        }
+    };
+
+    if TOKEN.set(token).is_err() {
+        tracing::debug!("Token metadata was already in use before reconciling");
+    }
+}
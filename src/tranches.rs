@@ -0,0 +1,64 @@
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+};
+
+use alloy::primitives::{Address, B256};
+use tokio::sync::Mutex;
+
+use crate::constants::TRANCHES_FILE_PATH;
+
+type Tranches = HashMap<B256, HashSet<Address>>;
+
+static TRANCHES: Mutex<Option<Tranches>> = Mutex::const_new(None);
+
+async fn load() -> eyre::Result<Tranches> {
+    if !Path::new(TRANCHES_FILE_PATH).exists() {
+        return Ok(HashMap::new());
+    }
+
+    let tranches_str = tokio::fs::read_to_string(TRANCHES_FILE_PATH).await?;
+    Ok(serde_json::from_str(&tranches_str)?)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrancheStatus {
+    Processed,
+    // An earlier root was processed, hasClaimed still says true from it
+    New,
+    // No root was processed yet, hasClaimed settles it
+    First,
+}
+
+pub async fn status(merkle_root: B256, address: Address) -> eyre::Result<TrancheStatus> {
+    let mut tranches = TRANCHES.lock().await;
+    if tranches.is_none() {
+        *tranches = Some(load().await?);
+    }
+
+    let tranches = tranches.get_or_insert_with(HashMap::new);
+    if tranches
+        .get(&merkle_root)
+        .is_some_and(|wallets| wallets.contains(&address))
+    {
+        Ok(TrancheStatus::Processed)
+    } else if tranches.values().any(|wallets| wallets.contains(&address)) {
+        Ok(TrancheStatus::New)
+    } else {
+        Ok(TrancheStatus::First)
+    }
+}
+
+pub async fn mark_processed(merkle_root: B256, address: Address) -> eyre::Result<()> {
+    let mut guard = TRANCHES.lock().await;
+    if guard.is_none() {
+        *guard = Some(load().await?);
+    }
+
+    let tranches = guard.get_or_insert_with(HashMap::new);
+    if tranches.entry(merkle_root).or_default().insert(address) {
+        tokio::fs::write(TRANCHES_FILE_PATH, serde_json::to_string_pretty(tranches)?).await?;
+    }
+
+    Ok(())
+}
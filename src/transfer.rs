@@ -0,0 +1,109 @@
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::Address,
+};
+use rand::thread_rng;
+
+use crate::{
+    campaign::campaign,
+    claimer::{get_token_balance, transfer_token},
+    config::Config,
+    pool::init_provider_pool,
+    safety::check_recipients,
+    token::{format_token, token_info},
+    utils::{check_whitelist, read_targets},
+};
+
+// Moves the campaign token and `extra` tokens of every wallet, to `destination` or its recipient
+async fn pay_out_balances(
+    config: Config,
+    extra: Vec<(String, Address)>,
+    destination: Option<Address>,
+) -> eyre::Result<()> {
+    if let Some(destination) = destination {
+        check_whitelist(&[destination]).await?;
+    }
+
+    let providers = init_provider_pool(&config).await?;
+    // The symbol is only final once the pool has reconciled it with the token
+    let mut tokens = vec![(format!("${}", token_info().symbol), campaign().token)];
+    tokens.extend(extra);
+    let targets = read_targets(&config).await?;
+    let mut rng = thread_rng();
+    let mut transferred = 0;
+
+    let recipients: Vec<_> = match destination {
+        Some(destination) => vec![destination],
+        None => targets.iter().map(|(_, recipient)| *recipient).collect(),
+    };
+    check_recipients(&providers.choose(&mut rng), &config, recipients).await?;
+
+    for (wallet, recipient) in &targets {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+        let to = destination.unwrap_or(*recipient);
+        let provider = providers.choose(&mut rng);
+        let mut moved = false;
+
+        for (symbol, token) in &tokens {
+            let balance = match get_token_balance(provider.clone(), address, *token).await {
+                Ok(balance) => balance,
+                Err(e) => {
+                    tracing::error!("{address} | failed to read {symbol} balance: {e}");
+                    continue;
+                }
+            };
+
+            if balance.is_zero() {
+                tracing::info!("{address} | no {symbol} to transfer");
+                continue;
+            }
+
+            let amount = match *token == campaign().token {
+                true => format_token(balance),
+                false => format!("{balance} {symbol}"),
+            };
+            tracing::info!("Sending {amount} from {address} to {to}");
+            match transfer_token(
+                provider.clone(),
+                wallet.clone(),
+                *token,
+                to,
+                balance,
+                &config,
+            )
+            .await
+            {
+                Ok(true) => moved = true,
+                Ok(false) => tracing::error!("{address} | {symbol} transfer to {to} reverted"),
+                Err(e) => tracing::error!("{address} | {symbol} transfer to {to} failed: {e}"),
+            }
+        }
+
+        if moved {
+            transferred += 1;
+        }
+    }
+
+    tracing::info!(
+        "Transferred tokens from {transferred}/{} wallets",
+        targets.len()
+    );
+
+    Ok(())
+}
+
+pub async fn transfer_all(config: Config) -> eyre::Result<()> {
+    pay_out_balances(config, vec![], None).await
+}
+
+// Skips the claim path, for wallets claimed by hand or by a run that died before paying out
+pub async fn sweep_all(config: Config) -> eyre::Result<()> {
+    let tokens = config
+        .sweep_tokens
+        .iter()
+        .map(|symbol| (symbol.clone(), config.watchlist[symbol]))
+        .collect();
+    let destination = config.sweep_to;
+
+    pay_out_balances(config, tokens, destination).await
+}
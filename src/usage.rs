@@ -0,0 +1,222 @@
+use std::{
+    collections::BTreeMap,
+    sync::{Arc, Mutex},
+    task::{Context, Poll},
+    time::Duration,
+};
+
+use alloy::{
+    rpc::json_rpc::{RequestPacket, ResponsePacket, ResponsePayload},
+    transports::{TransportError, TransportErrorKind, TransportFut},
+};
+use reqwest::Url;
+use serde::Serialize;
+use tower::{Layer, Service};
+
+use crate::constants::USAGE_FILE_PATH;
+
+static RPC_USAGE: Mutex<BTreeMap<String, Usage>> = Mutex::new(BTreeMap::new());
+static PROOF_USAGE: Mutex<BTreeMap<String, Usage>> = Mutex::new(BTreeMap::new());
+
+// Byte counts cover JSON-RPC payloads and proof bodies, not headers or TLS overhead
+#[derive(Serialize, Default, Clone, Debug)]
+pub struct Usage {
+    pub calls: u64,
+    pub failures: u64,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    // 429 answers from the endpoint and requests held back by the local rate limit
+    pub rate_limited: u64,
+    pub throttled: u64,
+    pub throttled_millis: u64,
+}
+
+#[derive(Serialize)]
+struct Report<'a> {
+    duration_secs: u64,
+    rpc: &'a BTreeMap<String, Usage>,
+    proof_api: &'a BTreeMap<String, Usage>,
+}
+
+#[derive(Clone)]
+pub struct UsageLayer {
+    endpoint: Arc<str>,
+}
+
+impl UsageLayer {
+    pub fn new(endpoint: &str) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+        }
+    }
+}
+
+impl<S> Layer<S> for UsageLayer {
+    type Service = UsageService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        UsageService {
+            inner,
+            endpoint: self.endpoint.clone(),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct UsageService<S> {
+    inner: S,
+    endpoint: Arc<str>,
+}
+
+fn request_bytes(request: &RequestPacket) -> usize {
+    match request {
+        RequestPacket::Single(request) => request.serialized().get().len(),
+        RequestPacket::Batch(requests) => requests
+            .iter()
+            .map(|request| request.serialized().get().len())
+            .sum(),
+    }
+}
+
+fn response_bytes(response: &ResponsePacket) -> usize {
+    let payload_bytes = |payload: &ResponsePayload| match payload {
+        ResponsePayload::Success(payload) => payload.get().len(),
+        ResponsePayload::Failure(error) => error.message.len(),
+    };
+
+    match response {
+        ResponsePacket::Single(response) => payload_bytes(&response.payload),
+        ResponsePacket::Batch(responses) => responses
+            .iter()
+            .map(|response| payload_bytes(&response.payload))
+            .sum(),
+    }
+}
+
+impl<S> Service<RequestPacket> for UsageService<S>
+where
+    S: Service<RequestPacket, Response = ResponsePacket, Error = TransportError> + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = ResponsePacket;
+    type Error = TransportError;
+    type Future = TransportFut<'static>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: RequestPacket) -> Self::Future {
+        let endpoint = self.endpoint.clone();
+        let calls = request.len() as u64;
+        let sent = request_bytes(&request) as u64;
+        let response = self.inner.call(request);
+
+        Box::pin(async move {
+            let response = response.await;
+            let mut usage = RPC_USAGE.lock().unwrap_or_else(|e| e.into_inner());
+            let usage = usage.entry(endpoint.to_string()).or_default();
+
+            usage.calls += calls;
+            usage.bytes_sent += sent;
+            match &response {
+                Ok(packet) => {
+                    usage.bytes_received += response_bytes(packet) as u64;
+                    if packet.is_error() {
+                        usage.failures += 1;
+                    }
+                }
+                Err(TransportError::Transport(TransportErrorKind::HttpError(e)))
+                    if e.status == 429 =>
+                {
+                    usage.failures += 1;
+                    usage.rate_limited += 1;
+                }
+                Err(_) => usage.failures += 1,
+            }
+
+            response
+        })
+    }
+}
+
+pub fn record_throttled(endpoint: &str, wait: Duration) {
+    let mut usage = RPC_USAGE.lock().unwrap_or_else(|e| e.into_inner());
+    let usage = usage.entry(endpoint.to_owned()).or_default();
+
+    usage.throttled += 1;
+    usage.throttled_millis += wait.as_millis() as u64;
+}
+
+// Proxy credentials stay out of the report, host and port are enough to tell proxies apart
+pub fn proxy_label(proxy_url: &str) -> String {
+    match Url::parse(proxy_url) {
+        Ok(url) => format!(
+            "{}:{}",
+            url.host_str().unwrap_or_default(),
+            url.port_or_known_default().unwrap_or_default()
+        ),
+        Err(_) => String::from("unparsable proxy"),
+    }
+}
+
+pub fn record_proof_call(proxy_url: &str, response: Option<usize>) {
+    let mut usage = PROOF_USAGE.lock().unwrap_or_else(|e| e.into_inner());
+    let usage = usage.entry(proxy_label(proxy_url)).or_default();
+
+    usage.calls += 1;
+    match response {
+        Some(bytes) => usage.bytes_received += bytes as u64,
+        None => usage.failures += 1,
+    }
+}
+
+fn format_bytes(bytes: u64) -> String {
+    match bytes {
+        0..1_000_000 => format!("{:.1} KB", bytes as f64 / 1e3),
+        _ => format!("{:.1} MB", bytes as f64 / 1e6),
+    }
+}
+
+pub async fn write_usage_report(elapsed: Duration) -> eyre::Result<()> {
+    let rpc = RPC_USAGE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    let proof_api = PROOF_USAGE
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    if rpc.is_empty() && proof_api.is_empty() {
+        return Ok(());
+    }
+
+    for (endpoint, usage) in &rpc {
+        tracing::info!(
+            "RPC {endpoint}: {} calls | {} failed | {} rate limited | {} throttled for {:.1}s | {} sent | {} received",
+            usage.calls,
+            usage.failures,
+            usage.rate_limited,
+            usage.throttled,
+            usage.throttled_millis as f64 / 1e3,
+            format_bytes(usage.bytes_sent),
+            format_bytes(usage.bytes_received)
+        );
+    }
+    for (proxy, usage) in &proof_api {
+        tracing::info!(
+            "Proof API via {proxy}: {} calls | {} failed | {} received",
+            usage.calls,
+            usage.failures,
+            format_bytes(usage.bytes_received)
+        );
+    }
+    tracing::info!("Run took {:.1} minutes", elapsed.as_secs_f64() / 60.0);
+
+    let report = Report {
+        duration_secs: elapsed.as_secs(),
+        rpc: &rpc,
+        proof_api: &proof_api,
+    };
+    tokio::fs::write(USAGE_FILE_PATH, serde_json::to_string_pretty(&report)?).await?;
+    tracing::info!("Resource usage written to {USAGE_FILE_PATH}");
+
+    Ok(())
+}
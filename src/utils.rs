@@ -1,10 +1,32 @@
-use std::{path::Path, str::FromStr, sync::Arc};
+use std::{
+    collections::{HashMap, HashSet},
+    path::Path,
+    str::FromStr,
+    sync::Arc,
+};
 
-use alloy::{network::EthereumWallet, primitives::Address, signers::local::PrivateKeySigner};
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::Address,
+    signers::local::PrivateKeySigner,
+};
 
-use tokio::io::AsyncBufReadExt;
+use eyre::WrapErr;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
 
-use crate::constants::{PRIVATE_KEYS_FILE_PATH, RECIPIENTS_FILE_PATH};
+use crate::{
+    campaign::chain,
+    config::{Config, MnemonicConfig},
+    constants::{
+        DERIVED_RECIPIENTS_FILE_PATH, INELIGIBLE_FILE_PATH, RECIPIENTS_WHITELIST_FILE_PATH,
+    },
+    mnemonic::{derive_signers, derived_wallets, RECIPIENT_MNEMONIC_ENV},
+    paths::{private_keys_path, recipients_path, wallets_path},
+    priority::{self, Priority},
+    seal::{read_sealed, write_sealed},
+    signer::{external_wallets, LocalKeys, SignerBackend},
+    wallets::read_wallets,
+};
 
 pub async fn read_file_lines(path: impl AsRef<Path>) -> eyre::Result<Vec<String>> {
     let file = tokio::fs::read(path).await?;
@@ -18,23 +40,252 @@ pub async fn read_file_lines(path: impl AsRef<Path>) -> eyre::Result<Vec<String>
     Ok(contents)
 }
 
-pub async fn read_private_keys() -> Vec<Arc<EthereumWallet>> {
-    read_file_lines(PRIVATE_KEYS_FILE_PATH)
+pub async fn read_private_keys() -> eyre::Result<Vec<Arc<EthereumWallet>>> {
+    if wallets_path().exists() {
+        return Ok(read_wallets()
+            .await
+            .wrap_err_with(|| format!("Invalid wallets file {}", wallets_path().display()))?
+            .into_iter()
+            .map(|entry| entry.wallet)
+            .collect());
+    }
+
+    let other_sources = !derived_wallets().is_empty() || !external_wallets().is_empty();
+    let mut wallets = match !other_sources || private_keys_path().exists() {
+        true => LocalKeys {
+            path: private_keys_path().to_path_buf(),
+        }
+        .wallets(chain().chain_id)
         .await
-        .expect("Private keys file to be present")
+        .wrap_err_with(|| {
+            format!(
+                "Invalid private keys file {}",
+                private_keys_path().display()
+            )
+        })?,
+        false => vec![],
+    };
+    wallets.extend_from_slice(derived_wallets());
+    wallets.extend_from_slice(external_wallets());
+
+    Ok(wallets)
+}
+
+// Lines are `<recipient>` or `<recipient>,<priority class>`
+pub async fn try_read_recipients() -> eyre::Result<Vec<(Address, Priority)>> {
+    let path = recipients_path();
+    read_file_lines(path)
+        .await
+        .map_err(|e| eyre::eyre!("Failed to read {}: {e}", path.display()))?
         .iter()
-        .map(|pk| {
-            let signer = PrivateKeySigner::from_str(pk).expect("Private key to be valid");
-            Arc::new(EthereumWallet::new(signer))
+        .enumerate()
+        .map(|(index, line)| {
+            let invalid = |what: &str, e: &dyn std::fmt::Display| {
+                eyre::eyre!("{} line {}: invalid {what}: {e}", path.display(), index + 1)
+            };
+            let (address, priority) = match line.split_once(',') {
+                Some((address, priority)) => (
+                    address,
+                    Priority::from_str(priority).map_err(|e| invalid("priority class", &e))?,
+                ),
+                None => (line.as_str(), Priority::default()),
+            };
+            let address =
+                parse_recipient(address.trim()).map_err(|e| invalid("recipient address", &e))?;
+            Ok((address, priority))
         })
         .collect()
 }
 
-pub async fn read_recipients() -> Vec<Address> {
-    read_file_lines(RECIPIENTS_FILE_PATH)
-        .await
-        .expect("Recipients file must be present")
+// Mixed case means the address carries an EIP-55 checksum, which catches most typos
+pub fn parse_recipient(text: &str) -> eyre::Result<Address> {
+    let address = Address::from_str(text)?;
+    let hex = text.strip_prefix("0x").unwrap_or(text);
+    let mixed_case =
+        hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    if mixed_case && address.to_checksum(None)[2..] != *hex {
+        eyre::bail!("{text} fails its checksum, check it for a typo");
+    }
+
+    Ok(address)
+}
+
+// The n-th wallet gets the signer of the n-th PATH, so the mnemonic alone restores every key
+pub fn derive_recipient_signers(
+    recipient_mnemonic: &MnemonicConfig,
+    wallets: usize,
+) -> eyre::Result<Vec<PrivateKeySigner>> {
+    let signers = derive_signers(
+        recipient_mnemonic,
+        "recipient_mnemonic",
+        RECIPIENT_MNEMONIC_ENV,
+    )?;
+    if signers.len() < wallets {
+        eyre::bail!(
+            "[recipient_mnemonic] PATH {} has {} paths but there are {wallets} wallets",
+            recipient_mnemonic.path,
+            signers.len()
+        );
+    }
+
+    Ok(signers)
+}
+
+// Sealed like the state, the mapping ties every wallet to where it pays
+async fn read_recipient_mapping() -> eyre::Result<HashMap<Address, Address>> {
+    let Some(contents) = read_sealed(DERIVED_RECIPIENTS_FILE_PATH).await? else {
+        return Ok(HashMap::new());
+    };
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let (wallet, recipient) = line
+                .split_once(',')
+                .ok_or_else(|| eyre::eyre!("Malformed recipient mapping line: {line}"))?;
+            Ok((Address::from_str(wallet)?, Address::from_str(recipient)?))
+        })
+        .collect()
+}
+
+// The mapping file records where earlier runs paid, a mnemonic or wallet order that derives
+// something else would send the next payouts to a different address
+pub async fn derive_recipients(
+    recipient_mnemonic: &MnemonicConfig,
+    wallets: &[Arc<EthereumWallet>],
+) -> eyre::Result<Vec<Address>> {
+    let signers = derive_recipient_signers(recipient_mnemonic, wallets.len())?;
+    let mut mapping = read_recipient_mapping().await?;
+
+    let mut recipients = vec![];
+    for (wallet, signer) in wallets.iter().zip(signers) {
+        let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+        let recipient = signer.address();
+        match mapping.insert(address, recipient) {
+            Some(recorded) if recorded != recipient => eyre::bail!(
+                "{DERIVED_RECIPIENTS_FILE_PATH} maps {address} to {recorded} but [recipient_mnemonic] derives {recipient}, check the mnemonic and the wallet order"
+            ),
+            _ => recipients.push(recipient),
+        }
+    }
+
+    let mut lines: Vec<_> = mapping
         .iter()
-        .map(|a| Address::from_str(a).expect("Recipinet address to be valid"))
+        .map(|(wallet, recipient)| format!("{wallet},{recipient}"))
+        .collect();
+    lines.sort();
+    write_sealed(DERIVED_RECIPIENTS_FILE_PATH, &(lines.join("\n") + "\n")).await?;
+
+    Ok(recipients)
+}
+
+pub async fn read_ineligible() -> eyre::Result<Vec<Address>> {
+    if !Path::new(INELIGIBLE_FILE_PATH).exists() {
+        return Ok(vec![]);
+    }
+
+    read_file_lines(INELIGIBLE_FILE_PATH)
+        .await?
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(Address::from_str(line.trim())?))
         .collect()
 }
+
+pub async fn mark_ineligible(address: Address) -> eyre::Result<()> {
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(INELIGIBLE_FILE_PATH)
+        .await?;
+    file.write_all(format!("{address}\n").as_bytes()).await?;
+
+    Ok(())
+}
+
+pub async fn check_whitelist(recipients: &[Address]) -> eyre::Result<()> {
+    if !Path::new(RECIPIENTS_WHITELIST_FILE_PATH).exists() {
+        return Ok(());
+    }
+
+    let whitelist = read_file_lines(RECIPIENTS_WHITELIST_FILE_PATH)
+        .await?
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| Ok(Address::from_str(line.trim())?))
+        .collect::<eyre::Result<HashSet<_>>>()?;
+
+    if let Some(recipient) = recipients
+        .iter()
+        .find(|recipient| !whitelist.contains(*recipient))
+    {
+        eyre::bail!("Recipient {recipient} is not in {RECIPIENTS_WHITELIST_FILE_PATH}");
+    }
+
+    Ok(())
+}
+
+pub async fn read_targets(config: &Config) -> eyre::Result<Vec<(Arc<EthereumWallet>, Address)>> {
+    let targets: Vec<_> = match wallets_path().exists() {
+        true => read_wallets()
+            .await
+            .wrap_err_with(|| format!("Invalid wallets file {}", wallets_path().display()))?
+            .into_iter()
+            .map(|entry| (entry.wallet, entry.recipient, entry.priority))
+            .collect(),
+        false => {
+            let wallets = read_private_keys().await?;
+            let recipients = match &config.recipient_mnemonic {
+                Some(recipient_mnemonic) => derive_recipients(recipient_mnemonic, &wallets)
+                    .await
+                    .wrap_err("Failed to derive the recipients from [recipient_mnemonic]")?
+                    .into_iter()
+                    .map(|recipient| (recipient, Priority::default()))
+                    .collect(),
+                None => try_read_recipients().await?,
+            };
+            if wallets.len() != recipients.len() {
+                eyre::bail!(
+                    "{} has {} private keys but {} has {} recipients",
+                    private_keys_path().display(),
+                    wallets.len(),
+                    recipients_path().display(),
+                    recipients.len()
+                );
+            }
+
+            wallets
+                .into_iter()
+                .zip(recipients)
+                .map(|(wallet, (recipient, priority))| (wallet, recipient, priority))
+                .collect()
+        }
+    };
+
+    let addresses: Vec<_> = targets.iter().map(|(_, recipient, _)| *recipient).collect();
+    check_whitelist(&addresses).await?;
+
+    let ineligible = read_ineligible()
+        .await
+        .wrap_err_with(|| format!("Invalid ineligible wallets file {INELIGIBLE_FILE_PATH}"))?;
+    let mut targets: Vec<_> = targets
+        .into_iter()
+        .filter(|(wallet, _, priority)| {
+            let address =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet);
+            priority::assign(address, *priority);
+            let skip = ineligible.contains(&address);
+            if skip {
+                tracing::info!("Skipping {address}: previously reported as ineligible");
+            }
+            !skip
+        })
+        .collect();
+    targets.sort_by_key(|(_, _, priority)| *priority);
+
+    Ok(targets
+        .into_iter()
+        .map(|(wallet, recipient, _)| (wallet, recipient))
+        .collect())
+}
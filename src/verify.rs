@@ -0,0 +1,203 @@
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{Address, U256},
+    providers::Provider,
+};
+use rand::thread_rng;
+
+use crate::{
+    campaign::campaign,
+    config::{Config, DelegationStep, PayoutMode},
+    constants::{LEDGER_FILE_PATH, VERIFY_FILE_PATH},
+    ledger::{recorded_transactions, token_transfers},
+    multicall::{read_claim_statuses, ClaimStatus},
+    pool::init_provider_pool,
+    proof::{fetch_proof, ProofResponse},
+    stake::kept_amount,
+    token::{format_token, parse_token},
+    utils::read_targets,
+};
+
+// Rounding of swaps and percentages may leave a few wei behind
+pub const BALANCE_TOLERANCE: f64 = 0.000001;
+
+struct Verdict {
+    allocation: U256,
+    claimed: bool,
+    balance: U256,
+    paid_out: U256,
+    delivered: U256,
+    issues: Vec<String>,
+}
+
+// What the wallet keeps by design, its delegation or stake share
+fn expected_kept(config: &Config, address: Address, allocation: U256) -> eyre::Result<U256> {
+    if config
+        .delegation
+        .as_ref()
+        .is_some_and(|delegation| delegation.after_claim == DelegationStep::InsteadOfTransfer)
+    {
+        return Ok(allocation);
+    }
+
+    match &config.stake {
+        Some(stake_config) => kept_amount(stake_config, address, allocation),
+        None => Ok(U256::ZERO),
+    }
+}
+
+// Recorded payouts are re-read from the chain, the ledger only says where to look
+pub async fn verify(config: Config) -> eyre::Result<()> {
+    let providers = init_provider_pool(&config).await?;
+    let mut rng = thread_rng();
+    let tolerance = parse_token(BALANCE_TOLERANCE)?;
+    let recorded = recorded_transactions().await?;
+
+    let targets = read_targets(&config).await?;
+    let addresses: Vec<_> = targets
+        .iter()
+        .map(|(wallet, _)| {
+            <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(wallet)
+        })
+        .collect();
+    let statuses = read_claim_statuses(&providers.choose(&mut rng), &addresses).await?;
+
+    // Swaps, stablecoin payouts and routing rules don't pay the recipient in $SCR
+    let pays_recipient = config.payout_mode() == PayoutMode::Token
+        && !config.sell.as_ref().is_some_and(|sell| sell.enabled)
+        && config.script.is_none();
+
+    let mut lines = vec![String::from(
+        "address,allocation,claimed,balance,paid_out,recipient,delivered,issues",
+    )];
+    let mut eligible = 0;
+    let mut discrepancies = 0;
+
+    for ((address, (_, recipient)), status) in addresses.into_iter().zip(&targets).zip(statuses) {
+        let Some(ClaimStatus { claimed, balance }) = status else {
+            tracing::error!("{address} | failed to read claim status");
+            discrepancies += 1;
+            continue;
+        };
+
+        let proxy = config.proxy_for(address, &mut rng);
+        let allocation = match fetch_proof(address, &proxy, &config).await {
+            Ok(ProofResponse::Eligible { amount, .. }) => amount,
+            Ok(ProofResponse::Ineligible) => continue,
+            Err(e) => {
+                tracing::error!("{address} | could not get proof: {e}");
+                discrepancies += 1;
+                continue;
+            }
+        };
+        eligible += 1;
+
+        let mut verdict = Verdict {
+            allocation,
+            claimed,
+            balance,
+            paid_out: U256::ZERO,
+            delivered: U256::ZERO,
+            issues: vec![],
+        };
+        if !claimed {
+            verdict.issues.push(String::from("not claimed"));
+        }
+
+        let transactions = recorded
+            .get(&address)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+        for (stage, tx_hash) in transactions {
+            let provider = providers.choose(&mut rng);
+            let receipt = match provider.get_transaction_receipt(*tx_hash).await {
+                Ok(Some(receipt)) if receipt.status() => receipt,
+                Ok(Some(_)) => {
+                    verdict
+                        .issues
+                        .push(format!("recorded {stage} {tx_hash} reverted"));
+                    continue;
+                }
+                Ok(None) => {
+                    verdict
+                        .issues
+                        .push(format!("recorded {stage} {tx_hash} is not on chain"));
+                    continue;
+                }
+                Err(e) => {
+                    verdict
+                        .issues
+                        .push(format!("failed to read recorded {stage} {tx_hash}: {e}"));
+                    continue;
+                }
+            };
+
+            for transfer in token_transfers(&receipt)
+                .into_iter()
+                .filter(|transfer| transfer.token == campaign().token && transfer.from == address)
+            {
+                verdict.paid_out += transfer.amount;
+                if transfer.to == *recipient {
+                    verdict.delivered += transfer.amount;
+                }
+            }
+        }
+
+        let kept = expected_kept(&config, address, allocation)?;
+        let expected = allocation.saturating_sub(kept);
+        if balance > kept + tolerance {
+            verdict.issues.push(format!(
+                "still holds {}, expected {}",
+                format_token(balance),
+                format_token(kept)
+            ));
+        }
+        let (moved, what) = match pays_recipient {
+            true => (verdict.delivered, "the recipient received"),
+            false => (verdict.paid_out, "the wallet paid out"),
+        };
+        if claimed && moved + tolerance < expected {
+            let mut issue = format!(
+                "{what} {} of {}",
+                format_token(moved),
+                format_token(expected)
+            );
+            if transactions.is_empty() {
+                issue += &format!(", no payout in {LEDGER_FILE_PATH}");
+            }
+            verdict.issues.push(issue);
+        }
+
+        match verdict.issues.is_empty() {
+            true => tracing::info!(
+                "{address} | ok, {} claimed and paid out",
+                format_token(allocation)
+            ),
+            false => {
+                tracing::error!("{address} | {}", verdict.issues.join("; "));
+                discrepancies += 1;
+            }
+        }
+        lines.push(format!(
+            "{address},{},{},{},{},{recipient},{},\"{}\"",
+            verdict.allocation,
+            verdict.claimed,
+            verdict.balance,
+            verdict.paid_out,
+            verdict.delivered,
+            verdict.issues.join("; ")
+        ));
+    }
+
+    tokio::fs::write(VERIFY_FILE_PATH, lines.join("\n") + "\n").await?;
+    tracing::info!(
+        "Verified {eligible} eligible wallets of {}, written to {VERIFY_FILE_PATH}",
+        targets.len()
+    );
+
+    if discrepancies > 0 {
+        eyre::bail!("{discrepancies} wallets failed verification");
+    }
+
+    Ok(())
+}
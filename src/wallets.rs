@@ -0,0 +1,164 @@
+use std::{
+    collections::HashMap,
+    str::FromStr,
+    sync::{Arc, LazyLock, Mutex},
+};
+
+use alloy::{network::EthereumWallet, primitives::Address, signers::local::PrivateKeySigner};
+
+use crate::{
+    auth::register_signer,
+    keyfile::read_key_file,
+    paths::wallets_path,
+    priority::{self, Priority},
+    utils::parse_recipient,
+};
+
+pub struct WalletEntry {
+    pub wallet: Arc<EthereumWallet>,
+    pub recipient: Address,
+    pub priority: Priority,
+}
+
+struct Binding {
+    proxy: Option<String>,
+    label: Option<String>,
+}
+
+static BINDINGS: LazyLock<Mutex<HashMap<Address, Binding>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+fn optional(column: Option<&str>) -> Option<&str> {
+    column.map(str::trim).filter(|value| !value.is_empty())
+}
+
+// Columns are `private_key,recipient,proxy,label,priority`, everything after the recipient may be empty
+pub async fn read_wallets() -> eyre::Result<Vec<WalletEntry>> {
+    let path = wallets_path();
+    let contents = read_key_file(path).await?;
+    let lines: Vec<_> = contents.lines().collect();
+
+    let mut entries = vec![];
+    let mut bindings = vec![];
+    let mut first_seen: HashMap<Address, usize> = HashMap::new();
+    let mut errors = vec![];
+
+    for (index, line) in lines.iter().enumerate() {
+        let line_number = index + 1;
+        if line.trim().is_empty() || line.trim_start().starts_with("private_key") {
+            continue;
+        }
+
+        let columns: Vec<_> = line.split(',').collect();
+        if columns.len() > 5 {
+            errors.push(format!(
+                "line {line_number}: expected at most 5 columns, found {}",
+                columns.len()
+            ));
+            continue;
+        }
+
+        let (Some(private_key), Some(recipient)) = (
+            optional(columns.first().copied()),
+            optional(columns.get(1).copied()),
+        ) else {
+            errors.push(format!(
+                "line {line_number}: private_key and recipient are required"
+            ));
+            continue;
+        };
+
+        let signer = match PrivateKeySigner::from_str(private_key) {
+            Ok(signer) => signer,
+            Err(e) => {
+                errors.push(format!("line {line_number}: invalid private key: {e}"));
+                continue;
+            }
+        };
+        let recipient = match parse_recipient(recipient) {
+            Ok(recipient) => recipient,
+            Err(e) => {
+                errors.push(format!(
+                    "line {line_number}: invalid recipient {recipient}: {e}"
+                ));
+                continue;
+            }
+        };
+        let priority = match optional(columns.get(4).copied()).map(Priority::from_str) {
+            Some(Ok(priority)) => priority,
+            Some(Err(e)) => {
+                errors.push(format!("line {line_number}: {e}"));
+                continue;
+            }
+            None => Priority::default(),
+        };
+
+        let address = signer.address();
+        if let Some(first_line) = first_seen.insert(address, line_number) {
+            errors.push(format!(
+                "line {line_number}: wallet {address} duplicates line {first_line}"
+            ));
+            continue;
+        }
+
+        register_signer(&signer);
+        entries.push(WalletEntry {
+            wallet: Arc::new(EthereumWallet::new(signer)),
+            recipient,
+            priority,
+        });
+        bindings.push((
+            address,
+            Binding {
+                proxy: optional(columns.get(2).copied()).map(String::from),
+                label: optional(columns.get(3).copied()).map(String::from),
+            },
+        ));
+    }
+
+    if !errors.is_empty() {
+        eyre::bail!(
+            "{} has {} invalid lines:\n{}",
+            path.display(),
+            errors.len(),
+            errors.join("\n")
+        );
+    }
+
+    BINDINGS.lock().unwrap().extend(bindings);
+    for entry in &entries {
+        let address = entry.wallet.default_signer().address();
+        priority::assign(address, entry.priority);
+    }
+
+    Ok(entries)
+}
+
+pub fn bound_proxy(address: Address) -> Option<String> {
+    BINDINGS
+        .lock()
+        .unwrap()
+        .get(&address)
+        .and_then(|binding| binding.proxy.clone())
+}
+
+// `<address> (<label>)` for labelled wallets, the plain address otherwise
+pub fn display(address: Address) -> String {
+    match BINDINGS
+        .lock()
+        .unwrap()
+        .get(&address)
+        .and_then(|binding| binding.label.as_ref())
+    {
+        Some(label) => format!("{address} ({label})"),
+        None => address.to_string(),
+    }
+}
+
+pub fn label(address: Address) -> Option<String> {
+    BINDINGS
+        .lock()
+        .unwrap()
+        .get(&address)
+        .and_then(|binding| binding.label.clone())
+}
@@ -0,0 +1,237 @@
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+
+use alloy::{
+    network::{Ethereum, EthereumWallet, NetworkWallet},
+    primitives::{Address, U256},
+    providers::Provider,
+    transports::Transport,
+};
+use rand::thread_rng;
+use tracing::Instrument;
+
+use crate::{
+    campaign::campaign,
+    claimer::{claim_and_transfer, get_token_balance, transfer, TokenDistributor, WalletOutcome},
+    config::{Config, DelegationStep},
+    journal::{self, Event},
+    logger::wallet_span,
+    monitor::{foreign_nonces, incidents, read_distributor_state},
+    notifier::{notify, notify_event, Digests},
+    pool::init_provider_pool,
+    token::format_token,
+    utils::read_targets,
+};
+
+// A transaction this process didn't send means someone else holds the key
+async fn handle_foreign_transactions<P, T>(
+    provider: Arc<P>,
+    wallet: Arc<EthereumWallet>,
+    recipient: Address,
+    balance: U256,
+    nonces: Vec<u64>,
+    config: &Config,
+) where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    let address = <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+    tracing::error!("{address} sent transactions this claimer didn't, nonces {nonces:?}");
+    journal::record(Event::ForeignTx {
+        address,
+        nonces: nonces.clone(),
+    });
+    notify(
+        config,
+        format!(
+            "Possible key compromise: {address} sent unknown transactions at nonces {nonces:?}"
+        ),
+    )
+    .await;
+
+    if !config.compromise_sweep || balance.is_zero() {
+        return;
+    }
+
+    tracing::warn!(
+        "Sweeping {} from {address} to {recipient}",
+        format_token(balance)
+    );
+    let swept = match transfer(provider, wallet, recipient, balance, config).await {
+        Ok(true) => format!(
+            "Swept {} from {address} to {recipient}",
+            format_token(balance)
+        ),
+        Ok(false) => format!("Sweep of {address} reverted"),
+        Err(e) => format!("Sweep of {address} failed: {e}"),
+    };
+    tracing::warn!("{swept}");
+    notify(config, swept).await;
+}
+
+pub async fn watch(config: Config) -> eyre::Result<()> {
+    if config
+        .stake
+        .as_ref()
+        .is_some_and(|stake| stake.contract.is_none())
+    {
+        eyre::bail!("Idle wallets can't be detected while [stake] keeps tokens on the wallets");
+    }
+    if config
+        .delegation
+        .as_ref()
+        .is_some_and(|delegation| delegation.after_claim == DelegationStep::InsteadOfTransfer)
+    {
+        eyre::bail!(
+            "Idle wallets can't be detected while [delegation] keeps tokens on the wallets"
+        );
+    }
+
+    let config = Arc::new(config);
+    let providers = init_provider_pool(&config).await?;
+    let idle_after = Duration::from_secs(config.idle_requeue_hours * 3600);
+    let mut idle_since: HashMap<Address, Instant> = HashMap::new();
+    let mut nonces: HashMap<Address, u64> = HashMap::new();
+    let mut distributor_state = read_distributor_state(providers.choose(&mut thread_rng())).await?;
+    let mut digests = Digests::new(&config);
+
+    tracing::info!(
+        "Watching for wallets idle for more than {}h every {}s",
+        config.idle_requeue_hours,
+        config.watch_interval
+    );
+
+    loop {
+        let mut rng = thread_rng();
+        let mut requeued = vec![];
+        let mut pending = 0;
+
+        // A failed read only skips this tick, the watch is meant to outlast flaky RPCs
+        let current = match read_distributor_state(providers.choose(&mut rng)).await {
+            Ok(current) => current,
+            Err(e) => {
+                tracing::warn!("Failed to read the distributor state, retrying next tick: {e}");
+                tokio::time::sleep(Duration::from_secs(config.watch_interval)).await;
+                continue;
+            }
+        };
+        let incidents = incidents(&distributor_state, &current, &config);
+        if !incidents.is_empty() {
+            for incident in &incidents {
+                tracing::error!("Distributor incident: {incident}");
+            }
+            // Incidents stop the watch, so they are sent to every channel right away
+            notify(
+                &config,
+                format!(
+                    "Stopped watching, distributor incidents: {}",
+                    incidents.join("; ")
+                ),
+            )
+            .await;
+            eyre::bail!(
+                "Stopped watching after {} distributor incidents, check the contract before resuming",
+                incidents.len()
+            );
+        }
+        distributor_state = current;
+
+        for (wallet, recipient) in read_targets(&config).await? {
+            let address =
+                <EthereumWallet as NetworkWallet<Ethereum>>::default_signer_address(&wallet);
+            let provider = providers.choose(&mut rng);
+
+            let read = async {
+                let distributor = TokenDistributor::new(campaign().distributor, provider.clone());
+                let claimed = distributor.hasClaimed(address).call().await?.claimed;
+                let balance =
+                    get_token_balance(provider.clone(), address, campaign().token).await?;
+                let nonce = provider.get_transaction_count(address).await?;
+                eyre::Ok((claimed, balance, nonce))
+            };
+            let (claimed, balance, nonce) = match read.await {
+                Ok(read) => read,
+                Err(e) => {
+                    tracing::warn!("{address} | failed to read its state, skipping this tick: {e}");
+                    continue;
+                }
+            };
+
+            // The first scan only takes the baseline, later ones compare against it
+            if let Some(previous) = nonces.insert(address, nonce) {
+                let foreign = foreign_nonces(address, previous, nonce);
+                if !foreign.is_empty() {
+                    handle_foreign_transactions(
+                        provider, wallet, recipient, balance, foreign, &config,
+                    )
+                    .await;
+                    idle_since.remove(&address);
+                    digests.record_failure(address);
+                    continue;
+                }
+            }
+
+            if !claimed || balance == U256::ZERO {
+                idle_since.remove(&address);
+                continue;
+            }
+
+            let since = *idle_since.entry(address).or_insert_with(Instant::now);
+            if since.elapsed() < idle_after {
+                pending += 1;
+                continue;
+            }
+
+            tracing::warn!(
+                "{address} claimed but still holds {}, re-queueing it",
+                format_token(balance)
+            );
+            let proxy = config.proxy_for(address, &mut rng);
+            match claim_and_transfer(wallet, provider, recipient, proxy, config.clone())
+                .instrument(wallet_span(address))
+                .await
+            {
+                Ok(WalletOutcome::Completed | WalletOutcome::Partial { .. }) => {
+                    idle_since.remove(&address);
+                    requeued.push(address);
+                    digests.record_processed();
+                    notify_event(&config, format!("Re-queued idle wallet {address} paid out"))
+                        .await;
+                }
+                Ok(_) => {
+                    tracing::warn!("{address} still needs manual intervention");
+                    digests.record_failure(address);
+                    notify_event(
+                        &config,
+                        format!("{address} still needs manual intervention"),
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    tracing::error!("Re-queued wallet {address} failed again: {e}");
+                    digests.record_failure(address);
+                    notify_event(
+                        &config,
+                        format!("Re-queued wallet {address} failed again: {e}"),
+                    )
+                    .await;
+                }
+            }
+        }
+
+        if !requeued.is_empty() {
+            tracing::warn!(
+                "{} idle wallets required intervention: {requeued:?}",
+                requeued.len()
+            );
+        }
+
+        digests.set_pending(pending);
+        digests.send_due(&config).await;
+
+        tokio::time::sleep(Duration::from_secs(config.watch_interval)).await;
+    }
+}
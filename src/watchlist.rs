@@ -0,0 +1,78 @@
+use alloy::{
+    network::Ethereum,
+    primitives::{utils::format_units, Address, Bytes},
+    providers::Provider,
+    transports::Transport,
+};
+
+use crate::{
+    claimer::IERC20,
+    config::Config,
+    multicall::{self, Multicall3::Call3},
+};
+
+const DEFAULT_DECIMALS: u8 = 18;
+
+pub struct WatchedToken {
+    pub symbol: String,
+    pub address: Address,
+    pub decimals: u8,
+}
+
+pub async fn resolve_watchlist<P, T>(
+    provider: &P,
+    config: &Config,
+) -> eyre::Result<Vec<WatchedToken>>
+where
+    P: Provider<T, Ethereum>,
+    T: Transport + Clone,
+{
+    if config.watchlist.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let calls = config
+        .watchlist
+        .values()
+        .map(|address| multicall::call(*address, &IERC20::decimalsCall {}))
+        .collect();
+    let results = multicall::aggregate(provider, calls).await?;
+
+    Ok(config
+        .watchlist
+        .iter()
+        .zip(results)
+        .map(|((symbol, address), result)| WatchedToken {
+            symbol: symbol.clone(),
+            address: *address,
+            decimals: multicall::decode::<IERC20::decimalsCall>(&result)
+                .map(|decimals| decimals._0)
+                .unwrap_or_else(|| {
+                    tracing::warn!(
+                        "Could not read decimals of {symbol}, assuming {DEFAULT_DECIMALS}"
+                    );
+                    DEFAULT_DECIMALS
+                }),
+        })
+        .collect())
+}
+
+pub fn balance_calls(tokens: &[WatchedToken], account: Address) -> Vec<Call3> {
+    tokens
+        .iter()
+        .map(|token| multicall::call(token.address, &IERC20::balanceOfCall { account }))
+        .collect()
+}
+
+// One formatted balance per token, empty where the call failed
+pub fn format_balances(tokens: &[WatchedToken], results: &[Option<Bytes>]) -> Vec<String> {
+    tokens
+        .iter()
+        .zip(results)
+        .map(|(token, result)| {
+            multicall::decode::<IERC20::balanceOfCall>(result)
+                .and_then(|balance| format_units(balance._0, token.decimals).ok())
+                .unwrap_or_default()
+        })
+        .collect()
+}
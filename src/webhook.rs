@@ -0,0 +1,184 @@
+use std::{sync::OnceLock, time::Duration};
+
+use alloy::{
+    hex,
+    primitives::{Address, TxHash, U256},
+};
+use reqwest::Client;
+use ring::hmac;
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::{
+    config::{WebhookConfig, WebhookEventKind},
+    journal::unix_timestamp,
+    metrics::Stage,
+};
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+const MAX_DELIVERY_ATTEMPTS: u32 = 3;
+const FLUSH_TIMEOUT: Duration = Duration::from_secs(30);
+const SIGNATURE_HEADER: &str = "X-Signature-256";
+
+static WEBHOOK: OnceLock<WebhookConfig> = OnceLock::new();
+static QUEUE: OnceLock<mpsc::UnboundedSender<Message>> = OnceLock::new();
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum WebhookEvent {
+    WalletStarted {
+        address: Address,
+    },
+    ClaimConfirmed {
+        address: Address,
+        tx_hash: TxHash,
+    },
+    TransferConfirmed {
+        address: Address,
+        tx_hash: TxHash,
+        stage: Stage,
+    },
+    WalletFailed {
+        address: Address,
+        error: String,
+    },
+    RunFinished {
+        wallets: usize,
+        completed: usize,
+        failed: usize,
+        claimed: Option<U256>,
+    },
+    SubmissionsPaused {
+        base_fee: u64,
+    },
+    SubmissionsResumed {
+        base_fee: u64,
+    },
+}
+
+impl WebhookEvent {
+    fn kind(&self) -> WebhookEventKind {
+        match self {
+            Self::WalletStarted { .. } => WebhookEventKind::WalletStarted,
+            Self::ClaimConfirmed { .. } => WebhookEventKind::ClaimConfirmed,
+            Self::TransferConfirmed { .. } => WebhookEventKind::TransferConfirmed,
+            Self::WalletFailed { .. } => WebhookEventKind::WalletFailed,
+            Self::RunFinished { .. } => WebhookEventKind::RunFinished,
+            Self::SubmissionsPaused { .. } => WebhookEventKind::SubmissionsPaused,
+            Self::SubmissionsResumed { .. } => WebhookEventKind::SubmissionsResumed,
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Payload<'a> {
+    timestamp: u64,
+    #[serde(flatten)]
+    event: &'a WebhookEvent,
+}
+
+enum Message {
+    Event(WebhookEvent),
+    Flush(oneshot::Sender<()>),
+}
+
+pub fn init_webhook(webhook_config: Option<&WebhookConfig>) {
+    if let Some(webhook_config) = webhook_config {
+        let _ = WEBHOOK.set(webhook_config.clone());
+    }
+}
+
+async fn post(client: &Client, webhook_config: &WebhookConfig, body: &str) -> eyre::Result<()> {
+    let mut request = client
+        .post(&webhook_config.url)
+        .header("Content-Type", "application/json")
+        .body(body.to_owned());
+    if let Some(secret) = &webhook_config.secret {
+        let tag = hmac::sign(
+            &hmac::Key::new(hmac::HMAC_SHA256, secret.as_bytes()),
+            body.as_bytes(),
+        );
+        request = request.header(
+            SIGNATURE_HEADER,
+            format!("sha256={}", hex::encode(tag.as_ref())),
+        );
+    }
+
+    request.send().await?.error_for_status()?;
+
+    Ok(())
+}
+
+async fn deliver(client: &Client, webhook_config: &WebhookConfig, event: &WebhookEvent) {
+    let payload = Payload {
+        timestamp: unix_timestamp(),
+        event,
+    };
+    let body = match serde_json::to_string(&payload) {
+        Ok(body) => body,
+        Err(e) => {
+            tracing::warn!("Failed to serialize webhook event {event:?}: {e}");
+            return;
+        }
+    };
+
+    for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+        match post(client, webhook_config, &body).await {
+            Ok(()) => return,
+            Err(e) if attempt == MAX_DELIVERY_ATTEMPTS => {
+                tracing::warn!("Failed to deliver webhook event {:?}: {e}", event.kind());
+            }
+            Err(_) => tokio::time::sleep(Duration::from_secs(u64::from(attempt))).await,
+        }
+    }
+}
+
+// One worker delivers events in the order they happened, without holding up the wallets
+fn queue(webhook_config: &'static WebhookConfig) -> &'static mpsc::UnboundedSender<Message> {
+    QUEUE.get_or_init(|| {
+        let client = Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .expect("Webhook client to build");
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    Message::Event(event) => deliver(&client, webhook_config, &event).await,
+                    Message::Flush(done) => {
+                        let _ = done.send(());
+                    }
+                }
+            }
+        });
+
+        sender
+    })
+}
+
+pub fn emit(event: WebhookEvent) {
+    let Some(webhook_config) = WEBHOOK.get() else {
+        return;
+    };
+    if !webhook_config.events.is_empty() && !webhook_config.events.contains(&event.kind()) {
+        return;
+    }
+
+    let _ = queue(webhook_config).send(Message::Event(event));
+}
+
+// Waits for the queued events, so run_finished isn't lost when the process exits
+pub async fn flush() {
+    let Some(webhook_config) = WEBHOOK.get() else {
+        return;
+    };
+
+    let (done, flushed) = oneshot::channel();
+    if queue(webhook_config).send(Message::Flush(done)).is_err() {
+        return;
+    }
+    if tokio::time::timeout(FLUSH_TIMEOUT, flushed).await.is_err() {
+        tracing::warn!("Gave up waiting for the webhook to deliver queued events");
+    }
+}